@@ -0,0 +1,372 @@
+//! Entry points for using hopdr as a library.
+//!
+//! `solve_str`/`solve_file` do what the binaries do (preprocessing, parsing, solving),
+//! but return the results instead of printing them.
+//!
+//! ```no_run
+//! use hopdr::api::{solve_file, SolveOptions, Verdict};
+//! let solution = solve_file("input.in", &SolveOptions::new()).unwrap();
+//! if let Verdict::Valid { certificate: Some(certificate) } = &solution.verdict {
+//!     println!("{}", certificate);
+//! }
+//! ```
+use crate::checker;
+use crate::formula::hes::Problem;
+use crate::formula::Constraint;
+use crate::parse;
+use crate::pdr::{self, certificate, PDRConfig, VerificationResult};
+use crate::portfolio::{self, Engine, PortfolioResult};
+use crate::preprocess::{self, Context};
+use crate::stat::{self, Stat};
+use crate::util::{self, Pretty};
+
+use thiserror::Error;
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Error)]
+pub enum SolveError {
+    #[error("failed to read {path}: {source}")]
+    Io {
+        path: String,
+        source: std::io::Error,
+    },
+    #[error("{0}")]
+    Parse(#[from] parse::ParseError),
+    #[error("{0}")]
+    Type(#[from] preprocess::typing::Diagnostics),
+    #[error("invalid type hints: {0}")]
+    TypeHints(#[from] certificate::CertificateError),
+    #[error("timeout")]
+    Timeout,
+    #[error("the solver panicked")]
+    Panic,
+    #[error("interrupted by Ctrl-C")]
+    Interrupted,
+    #[error("random testing failed: {0}")]
+    Checker(String),
+    #[error("failed to start the async runtime: {0}")]
+    Runtime(std::io::Error),
+}
+
+/// Procedure used to solve the problem
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    HoPDR,
+    ModeTrans,
+    /// races HoPDR and ModeTrans (see `portfolio`)
+    Portfolio,
+}
+
+#[derive(Clone)]
+pub struct SolveOptions {
+    mode: Mode,
+    config: crate::Configuration,
+    timeout: Option<Duration>,
+    type_hints: Option<String>,
+    track_trace: bool,
+    checker_backend: checker::Backend,
+    checker_seed: Option<u64>,
+}
+
+impl Default for SolveOptions {
+    fn default() -> Self {
+        SolveOptions {
+            mode: Mode::HoPDR,
+            config: crate::Configuration::default(),
+            timeout: None,
+            type_hints: None,
+            track_trace: false,
+            checker_backend: checker::Backend::OCaml,
+            checker_seed: None,
+        }
+    }
+}
+
+impl SolveOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// set the procedure used to solve the problem
+    pub fn mode(mut self, mode: Mode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// set the configuration passed to the solvers
+    pub fn config(mut self, config: crate::Configuration) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// set the time limit of the whole procedure
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// set candidate refinement types of predicates for HoPDR (in the certificate
+    /// format, or JSON). They are added to those in `%TYPES` of the input.
+    pub fn type_hints(mut self, type_hints: String) -> Self {
+        self.type_hints = Some(type_hints);
+        self
+    }
+
+    /// set whether ModeTrans tracks the trace of a counterexample
+    pub fn track_trace(mut self, track_trace: bool) -> Self {
+        self.track_trace = track_trace;
+        self
+    }
+
+    /// set how ModeTrans runs the translated program
+    pub fn checker_backend(mut self, backend: checker::Backend) -> Self {
+        self.checker_backend = backend;
+        self
+    }
+
+    /// set the seed of the random testing of ModeTrans (a fresh one by default)
+    pub fn checker_seed(mut self, seed: u64) -> Self {
+        self.checker_seed = Some(seed);
+        self
+    }
+}
+
+/// Refinement type environment that witnesses the validity of a problem
+#[derive(Clone)]
+pub struct Certificate {
+    pub env: pdr::rtype::TyEnv,
+    /// the names of the predicates in the source
+    ctx: Context,
+}
+
+impl Certificate {
+    /// prints the certificate in the JSON format (see `certificate::parse_certificate_json`)
+    pub fn to_json(&self) -> String {
+        certificate::certificate_to_json(&self.env, &self.ctx)
+    }
+}
+
+/// in the format accepted by `verify-certificate`
+impl fmt::Display for Certificate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            certificate::certificate_to_string(&self.env, &self.ctx)
+        )
+    }
+}
+
+impl fmt::Debug for Certificate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Certificate")
+            .field("env", &self.env)
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Verdict {
+    /// with the certificate, which is missing if the problem has least fixpoint equations
+    Valid {
+        certificate: Option<Certificate>,
+    },
+    /// with the counterexample of HoPDR, or the trace found by ModeTrans if it is tracked
+    Invalid {
+        trace: Option<String>,
+    },
+    Unknown,
+}
+
+#[derive(Debug, Clone)]
+pub struct Solution {
+    pub verdict: Verdict,
+    /// the engine that gave the verdict
+    pub engine: Option<Engine>,
+    /// elapsed time of each phase (all zero without feature `stat`)
+    pub phases: stat::PhaseTimes,
+    pub elapsed: Duration,
+    /// the seed of the random testing of ModeTrans (None if it does not run), with which
+    /// `SolveOptions::checker_seed` replays it
+    pub seed: Option<u64>,
+}
+
+pub fn solve_file(path: &str, options: &SolveOptions) -> Result<Solution, SolveError> {
+    let contents = std::fs::read_to_string(path).map_err(|source| SolveError::Io {
+        path: path.to_string(),
+        source,
+    })?;
+    solve_str(&contents, options)
+}
+
+pub fn solve_str(input: &str, options: &SolveOptions) -> Result<Solution, SolveError> {
+    let start = Instant::now();
+    let problem = preprocess::hes::type_check(parse::parse_problem(input)?)?;
+
+    let mut options_cloned = options.clone();
+    // decided here so that the solution carries it
+    let seed = match options.mode {
+        Mode::HoPDR => None,
+        Mode::ModeTrans | Mode::Portfolio => {
+            Some(options.checker_seed.unwrap_or_else(checker::fresh_seed))
+        }
+    };
+    options_cloned.checker_seed = seed;
+    let mut pdr_config = PDRConfig::new(options.config.clone());
+    // so that HoPDR does not keep running after the timeout
+    let cancel = pdr_config.cancel_flag();
+    // each call has its own statistics so that concurrent calls do not mix them
+    let stat = Stat::new();
+    let stat_cloned = stat.clone();
+    let deadline = options.timeout.map(|timeout| start + timeout);
+    let r = util::executes_with_timeout(
+        move || {
+            let solvers = options_cloned.config.solvers.clone();
+            stat_cloned.scope(|| {
+                solvers.scope(|| solve_parsed(problem, &options_cloned, pdr_config, deadline))
+            })
+        },
+        options.timeout,
+    );
+    let (verdict, engine) = match r {
+        Ok(r) => r?,
+        Err(e) => {
+            cancel.store(true, std::sync::atomic::Ordering::Relaxed);
+            return Err(match e {
+                util::ExecutionError::Timeout => SolveError::Timeout,
+                util::ExecutionError::Panic => SolveError::Panic,
+                util::ExecutionError::Ctrlc => SolveError::Interrupted,
+            });
+        }
+    };
+    stat.finalize();
+    Ok(Solution {
+        verdict,
+        engine,
+        phases: stat.phase_times(),
+        elapsed: start.elapsed(),
+        seed,
+    })
+}
+
+fn preprocess_problem(
+    mut problem: preprocess::hes::TypedProblem,
+    config: preprocess::hes::Config,
+    options: &SolveOptions,
+) -> (Problem<Constraint>, Context, Vec<parse::TypeAssignment>) {
+    let types = std::mem::take(&mut problem.types);
+    let (vc, ctx) =
+        preprocess::hes::preprocess_typed(problem, &config.with_configuration(&options.config));
+    (vc, ctx, types)
+}
+
+fn type_hints(
+    options: &SolveOptions,
+    types: &[parse::TypeAssignment],
+    ctx: &Context,
+    problem: &Problem<Constraint>,
+) -> Result<pdr::rtype::TyEnv, SolveError> {
+    let mut hints = certificate::from_assignments(types, ctx, problem)?;
+    if let Some(s) = &options.type_hints {
+        let env = if s.trim_start().starts_with('{') {
+            certificate::parse_certificate_json(s, ctx, problem)?
+        } else {
+            certificate::parse_certificate(s, ctx, problem)?
+        };
+        hints.append(&env);
+    }
+    Ok(hints)
+}
+
+/// the preprocessing for ModeTrans, which keeps the structure of the equations when the
+/// trace is tracked
+fn checker_preprocess_config(options: &SolveOptions) -> preprocess::hes::Config {
+    if options.track_trace {
+        preprocess::hes::Config::checker_with_trace_default()
+    } else {
+        preprocess::hes::Config::checker_default()
+    }
+}
+
+/// `deadline` is the end of the time limit of the whole procedure, after which the
+/// program is stopped; otherwise it would keep running after `solve_str` returns
+fn checker_config(
+    options: &SolveOptions,
+    ctx: &Context,
+    deadline: Option<Instant>,
+) -> checker::Config {
+    let config = checker::Config::new(ctx, false, false, options.track_trace, false)
+        .solvers(options.config.solvers.clone())
+        .backend(options.checker_backend);
+    let config = match deadline {
+        Some(deadline) => config.deadline(deadline),
+        None => config,
+    };
+    match options.checker_seed {
+        Some(seed) => config.seed(seed),
+        None => config,
+    }
+}
+
+fn solve_parsed(
+    problem: preprocess::hes::TypedProblem,
+    options: &SolveOptions,
+    pdr_config: PDRConfig,
+    deadline: Option<Instant>,
+) -> Result<(Verdict, Option<Engine>), SolveError> {
+    let valid = |c: pdr::ValidCertificate, ctx: &Context| Verdict::Valid {
+        certificate: c.certificate.map(|env| Certificate {
+            env,
+            ctx: ctx.clone(),
+        }),
+    };
+    let r = match options.mode {
+        Mode::HoPDR => {
+            let (vc, ctx, types) =
+                preprocess_problem(problem, preprocess::hes::Config::new(), options);
+            let pdr_config = pdr_config.type_hints(type_hints(options, &types, &ctx, &vc)?);
+            match pdr::run(vc, pdr_config) {
+                VerificationResult::Valid(c) => (valid(c, &ctx), Some(Engine::HoPDR)),
+                VerificationResult::Invalid(c) => {
+                    let trace = Some(c.pretty_display_with_context(&ctx).to_string());
+                    (Verdict::Invalid { trace }, Some(Engine::HoPDR))
+                }
+                VerificationResult::Unknown => (Verdict::Unknown, None),
+            }
+        }
+        Mode::ModeTrans => {
+            let config = checker_preprocess_config(options);
+            let (vc, ctx, _) = preprocess_problem(problem, config, options);
+            let rt = tokio::runtime::Runtime::new().map_err(SolveError::Runtime)?;
+            match rt.block_on(checker::run(vc, checker_config(options, &ctx, deadline))) {
+                checker::ExecResult::Invalid(trace) => {
+                    (Verdict::Invalid { trace }, Some(Engine::ModeTrans))
+                }
+                checker::ExecResult::Unknown => (Verdict::Unknown, None),
+                checker::ExecResult::Fail(s) => return Err(SolveError::Checker(s)),
+            }
+        }
+        Mode::Portfolio => {
+            let config = checker_preprocess_config(options);
+            let (checker_vc, checker_ctx, _) = preprocess_problem(problem.clone(), config, options);
+            let (vc, ctx, types) =
+                preprocess_problem(problem, preprocess::hes::Config::new(), options);
+            let pdr_config = pdr_config.type_hints(type_hints(options, &types, &ctx, &vc)?);
+            let checker_config = checker_config(options, &checker_ctx, deadline);
+            let r = portfolio::run(vc, pdr_config, checker_vc, checker_config);
+            let engine = r.winner();
+            let verdict = match r {
+                PortfolioResult::Valid(c) => valid(c, &ctx),
+                PortfolioResult::InvalidByHoPDR(c) => Verdict::Invalid {
+                    trace: Some(c.pretty_display_with_context(&ctx).to_string()),
+                },
+                PortfolioResult::InvalidByModeTrans(trace) => Verdict::Invalid { trace },
+                PortfolioResult::Unknown => Verdict::Unknown,
+            };
+            (verdict, engine)
+        }
+    };
+    Ok(r)
+}
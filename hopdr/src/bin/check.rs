@@ -6,16 +6,41 @@ extern crate log;
 extern crate ctrlc;
 
 use crate::formula::hes;
+use hopdr::report::{self, Report};
 use hopdr::util::Pretty;
 use hopdr::*;
 
 use clap::Parser;
 use colored::Colorize;
-use nom::error::VerboseError;
 use std::fs;
+use std::time;
 use tokio::runtime;
 use tokio::task::JoinSet;
 
+#[derive(clap::ArgEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum Output {
+    Text,
+    /// One JSON object (see `hopdr::report`)
+    Json,
+}
+
+#[derive(clap::ArgEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum Backend {
+    /// Compile the program with ocamlopt
+    Ocaml,
+    /// Run the program by the interpreter in hopdr
+    Interpreter,
+}
+
+impl Backend {
+    fn to_checker_backend(self) -> checker::Backend {
+        match self {
+            Backend::Ocaml => checker::Backend::OCaml,
+            Backend::Interpreter => checker::Backend::Interpreter,
+        }
+    }
+}
+
 /// Validity checker for νHFL(Z)
 #[derive(Parser, Debug, Clone)]
 #[clap(author = "Hiroyuki Katsura", version, about, long_about = None)]
@@ -33,6 +58,30 @@ struct Args {
     do_format: bool,
     #[clap(long)]
     print_check_log: bool,
+    /// Format of the result printed to stdout
+    #[clap(long, arg_enum, default_value = "text")]
+    output: Output,
+    /// How the translated program is run
+    #[clap(long, arg_enum, default_value = "ocaml")]
+    backend: Backend,
+    /// Seed of the random testing (a fresh one by default). The seed printed with a result
+    /// replays the same execution
+    #[clap(long)]
+    seed: Option<u64>,
+    /// Bounds of the number of recursive calls in an epoch of the random testing, tried in
+    /// order (comma-separated; by default 1000,10000,100000,1000000,10000000000)
+    #[clap(long, use_value_delimiter = true, parse(try_from_str = parse_recursion_limit))]
+    recursion_limits: Option<Vec<u64>>,
+    /// Ranges [-r, r] of the random integers, tried in order for each bound of the recursion
+    /// (comma-separated; by default 1,5,150,100000)
+    #[clap(long, use_value_delimiter = true, parse(try_from_str = parse_int_range))]
+    int_ranges: Option<Vec<u32>>,
+    /// Number of retries (epochs) for each pair of a bound and a range (by default 1000)
+    #[clap(long, parse(try_from_str = parse_retries))]
+    retries: Option<usize>,
+    /// Time limit (sec) of each run of the translated program
+    #[clap(long)]
+    run_timeout: Option<u64>,
     #[clap(long)]
     /// Interpret the input CHC problems is defined by least fixpoint
     chc_least: bool,
@@ -50,6 +99,15 @@ struct Args {
     #[clap(long)]
     /// Enables tracing. Note that this option may slow down the procedure.
     trace: bool,
+    #[clap(long)]
+    /// Replays the trace of a counterexample on the problem to validate it (implies --trace)
+    validate_trace: bool,
+    #[clap(long)]
+    /// Shrinks the trace of a counterexample by re-running the program with small integers
+    /// and shallow recursion (implies --trace)
+    shrink: bool,
+    #[clap(flatten)]
+    solver: hopdr::solver::args::SolverArgs,
 }
 
 fn gen_configuration_from_args(args: &Args) -> hopdr::Configuration {
@@ -58,8 +116,9 @@ fn gen_configuration_from_args(args: &Args) -> hopdr::Configuration {
         //.remove_disjunction(args.remove_disjunction)
         .remove_disjunction(false)
         .wait_every_step(false)
-        .ultimate(!args.no_ultimate);
-    if args.trace {
+        .ultimate(!args.no_ultimate)
+        .solvers(args.solver.registry());
+    if track_trace(args) {
         cfg.trace(true).inlining(false)
     } else {
         cfg
@@ -83,82 +142,178 @@ fn generate_context_for_chc(vmap: &crate::util::info::VariableMap) -> hopdr::pre
     ctx
 }
 
+/// prints the report of the input rejected by `get_problem`
+fn report_input_error(r: &Report) {
+    match r.verdict {
+        report::Verdict::ParseError => println!("{}", "Parse error".red()),
+        report::Verdict::TypeError => println!("{}", "Type error".red()),
+        _ => panic!("program error"),
+    }
+    println!("{}", r.reason.as_deref().unwrap_or("").trim_end());
+}
+
 fn get_problem(
     filename: &str,
     config: &hopdr::Configuration,
-) -> (
-    hopdr::formula::hes::Problem<hopdr::formula::Constraint>,
-    hopdr::preprocess::Context,
-) {
-    let contents = if config.trace {
-        fs::read_to_string(filename).expect("input file not found")
-    } else {
-        preprocess::hfl_preprocessor::open_file_with_preprocess(&filename, &config).unwrap()
-    };
+) -> Result<
+    (
+        hopdr::formula::hes::Problem<hopdr::formula::Constraint>,
+        hopdr::preprocess::Context,
+    ),
+    Report,
+> {
+    let contents = fs::read_to_string(filename).expect("input file not found");
     debug!("starting Checker...");
-    let (_, f) = parse::parse::<VerboseError<&str>>(&contents).unwrap();
+    let f = parse::parse_problem(&contents)
+        .map_err(|e| Report::new(report::Verdict::ParseError).reason(e))?;
+    let problem = preprocess::hes::type_check(f)
+        .map_err(|e| Report::new(report::Verdict::TypeError).reason(e))?;
     title!("problem");
-    match &f {
-        parse::Problem::NuHFLZValidityChecking(vc) => {
-            for fml in vc.formulas.iter() {
-                debug!("{}", fml);
-            }
-            debug!("TOP={}", vc.toplevel);
-        }
+    for fml in problem.clauses().iter() {
+        debug!("{}", fml);
     }
+    debug!("TOP={}", problem.toplevel());
     title!("proprocessed");
     let conf = if config.trace {
         get_tracing_config()
     } else {
         get_preprocess_config()
     };
-    let (vc, ctx) = preprocess::hes::preprocess(f, &conf);
+    let (vc, ctx) = preprocess::hes::preprocess_typed(problem, &conf.with_configuration(config));
     for fml in vc.clauses.iter() {
         debug!("{}", fml);
     }
-    (vc, ctx)
+    Ok((vc, ctx))
 }
 
-fn report_result(result: checker::ExecResult) {
-    print!("Verification Result: ");
+fn report_result(args: &Args, result: checker::ExecResult) -> Report {
+    let text = args.output == Output::Text;
+    if text {
+        print!("Verification Result: ");
+    }
     match result {
-        checker::ExecResult::Unknown => println!("Unknown"),
+        checker::ExecResult::Unknown => {
+            if text {
+                println!("Unknown")
+            }
+            Report::new(report::Verdict::Unknown)
+        }
         checker::ExecResult::Invalid(s) => {
-            println!("Invalid");
-            match s {
-                Some(s) => println!("Trace: {s}"),
-                None => (),
+            if text {
+                println!("Invalid");
+                match &s {
+                    Some(s) => println!("Trace: {s}"),
+                    None => (),
+                }
+            }
+            Report::new(report::Verdict::Invalid)
+                .engine(portfolio::Engine::ModeTrans)
+                .trace(s)
+        }
+        checker::ExecResult::Fail(s) => {
+            if text {
+                println!("Fail\nReason: {s}")
             }
+            Report::new(report::Verdict::Fail).reason(s)
         }
-        checker::ExecResult::Fail(s) => println!("Fail\nReason: {s}"),
     }
 }
 
+#[cfg(feature = "stat")]
+fn print_stat(args: &Args) -> bool {
+    args.print_stat
+}
+
 #[cfg(not(feature = "stat"))]
+fn print_stat(_args: &Args) -> bool {
+    false
+}
+
+/// the configuration of the checker on the problem of `ctx`
+fn checker_config(args: &Args, ctx: &crate::preprocess::Context) -> checker::Config {
+    let config = checker::Config::new(
+        ctx,
+        args.print_check_log,
+        args.no_mode_analysis,
+        track_trace(args),
+        print_stat(args),
+    )
+    .backend(args.backend.to_checker_backend())
+    .validate_trace(args.validate_trace)
+    .shrink(args.shrink)
+    .seed(seed(args))
+    .schedule(schedule_from_args(args))
+    .expect("checked when the arguments are parsed");
+    match args.run_timeout {
+        Some(secs) => config.run_timeout(time::Duration::from_secs(secs)),
+        None => config,
+    }
+}
+
+fn parse_int_range(s: &str) -> Result<u32, String> {
+    let r = s
+        .parse()
+        .map_err(|e: std::num::ParseIntError| e.to_string())?;
+    ml::Schedule::check_int_range(r).map_err(|e| e.to_string())
+}
+
+fn parse_recursion_limit(s: &str) -> Result<u64, String> {
+    let n_rec = s
+        .parse()
+        .map_err(|e: std::num::ParseIntError| e.to_string())?;
+    ml::Schedule::check_recursion_limit(n_rec).map_err(|e| e.to_string())
+}
+
+fn parse_retries(s: &str) -> Result<usize, String> {
+    let retries = s
+        .parse()
+        .map_err(|e: std::num::ParseIntError| e.to_string())?;
+    ml::Schedule::check_retries(retries).map_err(|e| e.to_string())
+}
+
+fn schedule_from_args(args: &Args) -> ml::Schedule {
+    let default = ml::Schedule::default();
+    ml::Schedule {
+        recursion_limits: args
+            .recursion_limits
+            .clone()
+            .unwrap_or(default.recursion_limits),
+        ranges: args.int_ranges.clone().unwrap_or(default.ranges),
+        retries: args.retries.unwrap_or(default.retries),
+    }
+}
+
+fn seed(args: &Args) -> u64 {
+    args.seed.expect("the seed is decided in main")
+}
+
+fn track_trace(args: &Args) -> bool {
+    args.trace || args.validate_trace || args.shrink
+}
+
+/// runs the checker on the problems in parallel, and returns the first definitive result.
+///
+/// Each problem has its own statistics, which are added to the current ones at the end.
 fn run_multiple(
     problems: Vec<(
         hes::Problem<crate::formula::Constraint>,
         crate::preprocess::Context,
     )>,
-    print_check_log: bool,
-    no_mode_analysis: bool,
-    track_trace: bool,
-    print_stat: bool,
+    args: &Args,
 ) -> checker::ExecResult {
     info!("run parallel");
+    // the tasks run on the threads of the runtime
+    let solvers = hopdr::solver::registry::current();
+    let stats: Vec<_> = problems.iter().map(|_| stat::Stat::new()).collect();
     let rt = runtime::Runtime::new().unwrap();
-    rt.block_on(async {
+    let r = rt.block_on(async {
         let mut set = JoinSet::new();
 
-        for (problem, ctx) in problems {
-            let config = checker::Config::new(
-                &ctx,
-                print_check_log,
-                no_mode_analysis,
-                track_trace,
-                print_stat,
-            );
-            let t = checker::run(problem, config.clone());
+        for ((problem, ctx), s) in problems.into_iter().zip(stats.iter()) {
+            let config = checker_config(args, &ctx)
+                .stat(s.clone())
+                .solvers(solvers.clone());
+            let t = checker::run(problem, config);
             set.spawn(t);
         }
 
@@ -179,48 +334,20 @@ fn run_multiple(
             }
         }
         checker::ExecResult::Unknown
-    })
-}
-
-#[cfg(feature = "stat")]
-fn run_multiple(
-    problems: Vec<(
-        hes::Problem<crate::formula::Constraint>,
-        crate::preprocess::Context,
-    )>,
-    print_check_log: bool,
-    no_mode_analysis: bool,
-    track_trace: bool,
-    print_stat: bool,
-) -> checker::ExecResult {
-    info!("run sequentially");
-    let rt = runtime::Runtime::new().unwrap();
-    rt.block_on(async {
-        for (problem, ctx) in problems {
-            let config = checker::Config::new(
-                &ctx,
-                print_check_log,
-                no_mode_analysis,
-                track_trace,
-                print_stat,
-            );
-            let t = checker::run(problem, config.clone()).await;
-            match t {
-                checker::ExecResult::Invalid(x) => {
-                    return checker::ExecResult::Invalid(x);
-                }
-                checker::ExecResult::Unknown => {
-                    info!("result: unknown");
-                }
-                checker::ExecResult::Fail(s) => {
-                    return checker::ExecResult::Fail(s);
-                }
-            }
+    });
+    let current = stat::current();
+    for (i, s) in stats.iter().enumerate() {
+        s.finalize();
+        if print_stat(args) && stats.len() > 1 {
+            println!("[[Problem {}]]", i);
+            s.dump();
         }
-        checker::ExecResult::Unknown
-    })
+        current.merge(s);
+    }
+    r
 }
 
+/// the result is found during the preprocess of CHCs if it returns `Some`
 fn handle_chc_data(
     problems: &mut Vec<(
         hes::Problem<crate::formula::Constraint>,
@@ -229,10 +356,10 @@ fn handle_chc_data(
     data: &str,
     do_hoice_preprocess: bool,
     args: &Args,
-) {
+) -> Option<checker::ExecResult> {
     let (chcs, vmap) = match parse::parse_chc(&data, do_hoice_preprocess) {
         Ok(x) => x,
-        Err(r) if r.is_unsat() => return report_result(checker::ExecResult::Invalid(None)),
+        Err(r) if r.is_unsat() => return Some(checker::ExecResult::Invalid(None)),
         Err(r) => panic!("parse error: {:?}", r),
     };
     let ctx = generate_context_for_chc(&vmap);
@@ -294,8 +421,10 @@ fn handle_chc_data(
         let t = crate::preprocess::hes::preprocess_for_typed_problem(problem, &config);
         problems.push((t, ctx.clone()));
     };
+    None
 }
-fn check_main(args: Args) {
+
+fn check_main(args: Args) -> Report {
     let config = gen_configuration_from_args(&args);
 
     let vcs = if args.chc {
@@ -310,28 +439,24 @@ fn check_main(args: Args) {
         }
         let mut vcs = Vec::new();
         for do_hoice_preprocess in do_hoice_preprocess_or_not {
-            handle_chc_data(&mut vcs, &data, do_hoice_preprocess, &args);
+            if let Some(r) = handle_chc_data(&mut vcs, &data, do_hoice_preprocess, &args) {
+                return report_result(&args, r);
+            }
         }
         vcs
     } else {
-        let (problem, ctx) = get_problem(&args.input, &config);
-        vec![(problem, ctx)]
+        match get_problem(&args.input, &config) {
+            Ok((problem, ctx)) => vec![(problem, ctx)],
+            Err(r) => return r,
+        }
     };
 
-    let print_stat = false;
-    #[cfg(feature = "stat")]
-    let print_stat = args.print_stat;
-
-    report_result(run_multiple(
-        vcs,
-        args.print_check_log,
-        args.no_mode_analysis,
-        args.trace,
-        print_stat,
-    ))
+    let result = run_multiple(vcs, &args);
+    report_result(&args, result)
 }
 
 fn main() {
+    let start = time::Instant::now();
     // setting logs
     env_logger::builder()
         .format_timestamp(None)
@@ -351,28 +476,63 @@ fn main() {
     }
 
     // parsing command line args
-    let args = Args::parse();
+    let mut args = Args::parse();
+    // all the problems share one seed, which is printed with the result
+    args.seed = Some(args.seed.unwrap_or_else(checker::fresh_seed));
     let args_cloned = args.clone();
 
     crate::ml::set_format(args.do_format);
 
-    match util::executes_with_timeout_and_ctrlc(move || check_main(args_cloned), None) {
-        Ok(()) => (),
+    args.solver.load_cache();
+
+    // the preprocess also uses the solvers
+    let solvers = gen_configuration_from_args(&args).solvers;
+    let main = move || solvers.scope(|| check_main(args_cloned));
+    let text = args.output == Output::Text;
+    let report = match util::executes_with_timeout_and_ctrlc(main, None) {
+        Ok(report) => report,
         Err(util::ExecutionError::Timeout) => {
-            println!("{}", "Timeout".red());
+            if text {
+                println!("{}", "Timeout".red());
+            }
+            Report::new(report::Verdict::Timeout)
         }
         Err(util::ExecutionError::Panic) => {
-            println!("{}", "Fail".red());
+            if text {
+                println!("{}", "Fail".red());
+            }
+            Report::new(report::Verdict::Fail).reason("the solver panicked")
         }
         Err(util::ExecutionError::Ctrlc) => {
-            println!("{}", "Execution terminated".white());
+            if text {
+                println!("{}", "Execution terminated".white());
+            }
+            Report::new(report::Verdict::Terminated)
         }
-    }
+    };
+    args.solver.save_cache();
 
     crate::stat::finalize();
 
+    let input_error = matches!(
+        report.verdict,
+        report::Verdict::ParseError | report::Verdict::TypeError
+    );
+    if text && input_error {
+        report_input_error(&report);
+    }
+    let report = report.seed(seed(&args));
+    if text {
+        println!("Seed: {}", seed(&args));
+    } else {
+        println!("{}", report.finish(start).to_json());
+    }
+
     #[cfg(feature = "stat")]
     if args.print_stat {
         crate::stat::dump();
     }
+    if input_error {
+        std::process::exit(1);
+    }
 }
@@ -11,25 +11,56 @@ use clap::Parser;
 use colored::Colorize;
 use hopdr::pdr::PDRConfig;
 use hopdr::pdr::VerificationResult;
+use hopdr::preprocess::hes::TypedProblem;
 use hopdr::preprocess::Context;
+use hopdr::report::{self, Report};
 use hopdr::title;
 use hopdr::util::Pretty;
-use nom::error::VerboseError;
 
 use std::fs;
 use std::time;
 
+#[derive(clap::ArgEnum, Debug, Clone, Copy)]
+enum Mode {
+    /// Races HoPDR and the ModeTrans checker, and reports the first answer
+    Portfolio,
+}
+
+#[derive(clap::Subcommand, Debug, Clone)]
+enum Command {
+    /// Instead of solving the problem, checks that the given certificate
+    /// (refinement type environment) proves its validity
+    VerifyCertificate {
+        /// File of the certificate (in JSON if it starts with `{`)
+        certificate: String,
+    },
+}
+
+#[derive(clap::ArgEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum Output {
+    Text,
+    /// One JSON object (see `hopdr::report`)
+    Json,
+}
+
 /// Validity checker for νHFL(Z)
 #[derive(Parser, Debug, Clone)]
 #[clap(author = "Hiroyuki Katsura", version, about, long_about = None)]
 struct Args {
+    /// By default, only HoPDR is used
+    #[clap(arg_enum)]
+    mode: Option<Mode>,
     /// Name of the person to greet
     #[clap(short, long)]
     input: String,
+    /// Disables inlining and removal of disjunctions
     #[clap(long)]
     no_preprocess: bool,
     #[clap(long)]
     print_stat: bool,
+    /// Format of the result printed to stdout
+    #[clap(long, arg_enum, default_value = "text")]
+    output: Output,
     /// Timeout (sec); if set to 0, no timeout
     #[clap(short, long, default_value_t = 0)]
     timeout: u64,
@@ -47,57 +78,349 @@ struct Args {
     debug_wait_every_step: bool,
     #[clap(long)]
     default_interpolation_solver: Option<String>,
+    /// Writes the certificate of a valid problem to the given file
+    /// (in JSON if its extension is .json)
+    #[clap(long)]
+    certificate_output: Option<String>,
+    /// Candidate refinement types of predicates that seed HoPDR
+    /// (in the same format as certificates). They are added to those in `%TYPES`
+    #[clap(long)]
+    type_hints: Option<String>,
+    /// Seed of the random testing of ModeTrans in the portfolio (a fresh one by default).
+    /// The seed printed with a result replays the same execution
+    #[clap(long)]
+    seed: Option<u64>,
+    /// Maximum depth of the unrolling with which HoPDR proves least fixpoint equations
+    #[clap(long, default_value_t = 64)]
+    least_fixpoint_unrolling_bound: usize,
+    #[clap(flatten)]
+    solver: hopdr::solver::args::SolverArgs,
+    #[clap(subcommand)]
+    command: Option<Command>,
 }
 
-fn report_result(args: &Args, r: VerificationResult, ctx: &Context) {
+fn report_result(args: &Args, r: VerificationResult, ctx: &Context) -> Report {
+    let text = args.output == Output::Text;
     match r {
         pdr::VerificationResult::Valid(c) => {
-            println!("{}", "Valid".green());
-            if args.detailed_results {
-                println!("[Type Environment]");
-                println!("{}", c.certificate.pretty_display_with_context(ctx));
+            if text {
+                println!("{}", "Valid".green());
+                if args.detailed_results {
+                    print_certificate(&c, ctx);
+                }
+            }
+            if let Some(path) = &args.certificate_output {
+                write_certificate(path, &c, ctx);
             }
+            valid_report(&c, ctx).engine(portfolio::Engine::HoPDR)
         }
-        pdr::VerificationResult::Invalid => {
-            println!("{}", "Invalid".red());
+        pdr::VerificationResult::Invalid(c) => {
+            if text {
+                println!("{}", "Invalid".red());
+                if args.detailed_results {
+                    println!("[Counterexample]");
+                    println!("{}", c.pretty_display_with_context(ctx));
+                }
+            }
+            invalid_report(&c, ctx).engine(portfolio::Engine::HoPDR)
         }
         pdr::VerificationResult::Unknown => {
-            println!("{}", "Unknown".red());
+            if text {
+                println!("{}", "Unknown".red());
+            }
+            Report::new(report::Verdict::Unknown)
         }
     }
 }
 
-fn pdr_main(args: Args, contents: String, config: PDRConfig) {
-    debug!("starting PDR...");
-    let (_, f) = parse::parse::<VerboseError<&str>>(&contents).unwrap();
+fn valid_report(c: &pdr::ValidCertificate, ctx: &Context) -> Report {
+    let report = Report::new(report::Verdict::Valid);
+    match &c.certificate {
+        Some(certificate) => {
+            report.certificate(pdr::certificate::certificate_to_string(certificate, ctx))
+        }
+        None => report,
+    }
+}
 
-    title!("problem");
-    match &f {
-        parse::Problem::NuHFLZValidityChecking(vc) => {
-            for fml in vc.formulas.iter() {
-                debug!("{}", fml);
+const NO_CERTIFICATE: &str = "no certificate: least fixpoints are proved by finite unrolling";
+
+fn print_certificate(c: &pdr::ValidCertificate, ctx: &Context) {
+    match &c.certificate {
+        Some(certificate) => {
+            println!("[Type Environment]");
+            println!("{}", certificate.pretty_display_with_context(ctx));
+        }
+        None => println!("({})", NO_CERTIFICATE),
+    }
+}
+
+fn invalid_report(c: &pdr::Counterexample, ctx: &Context) -> Report {
+    let trace = c.pretty_display_with_context(ctx).to_string();
+    Report::new(report::Verdict::Invalid).trace(Some(trace))
+}
+
+fn write_certificate(path: &str, c: &pdr::ValidCertificate, ctx: &Context) {
+    use hopdr::pdr::certificate;
+    let certificate = match &c.certificate {
+        Some(certificate) => certificate,
+        None => {
+            warn!("{}; {} is not written", NO_CERTIFICATE, path);
+            return;
+        }
+    };
+    let s = if path.ends_with(".json") {
+        certificate::certificate_to_json(certificate, ctx)
+    } else {
+        certificate::certificate_to_string(certificate, ctx)
+    };
+    fs::write(path, s).expect("Something went wrong writing the certificate");
+}
+
+fn type_hints(
+    path: &Option<String>,
+    types: &[parse::TypeAssignment],
+    ctx: &Context,
+    problem: &formula::hes::Problem<formula::Constraint>,
+) -> Result<pdr::rtype::TyEnv, pdr::certificate::CertificateError> {
+    use hopdr::pdr::certificate;
+    let mut hints = certificate::from_assignments(types, ctx, problem)?;
+    if let Some(path) = path {
+        let s = fs::read_to_string(path).expect("Something went wrong reading the type hints");
+        let env = if s.trim_start().starts_with('{') {
+            certificate::parse_certificate_json(&s, ctx, problem)?
+        } else {
+            certificate::parse_certificate(&s, ctx, problem)?
+        };
+        hints.append(&env);
+    }
+    Ok(hints)
+}
+
+fn report_portfolio_result(args: &Args, r: portfolio::PortfolioResult, ctx: &Context) -> Report {
+    use hopdr::portfolio::PortfolioResult;
+    let text = args.output == Output::Text;
+    let engine = r.winner();
+    let winner = match engine {
+        Some(e) => format!(" ({})", e),
+        None => String::new(),
+    };
+    let result = match r {
+        PortfolioResult::Valid(c) => {
+            if text {
+                println!("{}{}", "Valid".green(), winner);
+                if args.detailed_results {
+                    print_certificate(&c, ctx);
+                }
+            }
+            if let Some(path) = &args.certificate_output {
+                write_certificate(path, &c, ctx);
+            }
+            valid_report(&c, ctx)
+        }
+        PortfolioResult::InvalidByHoPDR(c) => {
+            if text {
+                println!("{}{}", "Invalid".red(), winner);
+                if args.detailed_results {
+                    println!("[Counterexample]");
+                    println!("{}", c.pretty_display_with_context(ctx));
+                }
+            }
+            invalid_report(&c, ctx)
+        }
+        PortfolioResult::InvalidByModeTrans(trace) => {
+            if text {
+                println!("{}{}", "Invalid".red(), winner);
+                if args.detailed_results {
+                    if let Some(trace) = &trace {
+                        println!("[Trace]");
+                        println!("{}", trace);
+                    }
+                }
             }
-            debug!("TOP={}", vc.toplevel);
+            Report::new(report::Verdict::Invalid).trace(trace)
         }
+        PortfolioResult::Unknown => {
+            if text {
+                println!("{}", "Unknown".red());
+            }
+            Report::new(report::Verdict::Unknown)
+        }
+    };
+    match engine {
+        Some(e) => result.engine(e),
+        None => result,
     }
+}
+
+/// prints the report of the input rejected by `read_input`
+fn report_input_error(r: &Report) {
+    match r.verdict {
+        report::Verdict::ParseError => println!("{}", "Parse error".red()),
+        report::Verdict::TypeError => println!("{}", "Type error".red()),
+        _ => panic!("program error"),
+    }
+    println!("{}", r.reason.as_deref().unwrap_or("").trim_end());
+}
+
+/// parses and preprocesses the input, and sets the type hints to `config`
+///
+/// In JSON mode, invalid type hints are returned as a report instead of exiting.
+fn load_problem(
+    args: &Args,
+    mut problem: TypedProblem,
+    config: PDRConfig,
+) -> Result<
+    (
+        formula::hes::Problem<formula::Constraint>,
+        Context,
+        PDRConfig,
+    ),
+    Report,
+> {
+    let types = std::mem::take(&mut problem.types);
+
+    title!("problem");
+    for fml in problem.clauses().iter() {
+        debug!("{}", fml);
+    }
+    debug!("TOP={}", problem.toplevel());
 
     title!("proprocessed");
-    let (vc, ctx) = preprocess::hes::preprocess_with_default_config(f);
+    let preprocess_config =
+        preprocess::hes::Config::new().with_configuration(&gen_configuration_from_args(args));
+    let (vc, ctx) = preprocess::hes::preprocess_typed(problem, &preprocess_config);
     for fml in vc.clauses.iter() {
         debug!("{}", fml);
     }
 
+    let config = match type_hints(&args.type_hints, &types, &ctx, &vc) {
+        Ok(hints) => config.type_hints(hints),
+        Err(e) if args.output == Output::Json => {
+            let reason = format!("invalid type hints: {}", e);
+            return Err(Report::new(report::Verdict::Fail).reason(reason));
+        }
+        Err(e) => {
+            println!("{}", "Invalid type hints".red());
+            println!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    Ok((vc, ctx, config))
+}
+
+fn pdr_main(args: Args, problem: TypedProblem, config: PDRConfig) -> Report {
+    debug!("starting PDR...");
+    let (vc, ctx, config) = match load_problem(&args, problem, config) {
+        Ok(x) => x,
+        Err(r) => return r,
+    };
     report_result(&args, pdr::run(vc, config), &ctx)
 }
 
+fn seed(args: &Args) -> u64 {
+    args.seed.expect("the seed is decided in main")
+}
+
+fn portfolio_main(args: Args, problem: TypedProblem, config: PDRConfig) -> Report {
+    debug!("starting portfolio...");
+    // ModeTrans has its own preprocessing, and the trace is only needed when it is printed
+    let preprocess_config = if args.detailed_results {
+        preprocess::hes::Config::checker_with_trace_default()
+    } else {
+        preprocess::hes::Config::checker_default()
+    };
+    let configuration = gen_configuration_from_args(&args);
+    let (checker_vc, checker_ctx) = preprocess::hes::preprocess_typed(
+        problem.clone(),
+        &preprocess_config.with_configuration(&configuration),
+    );
+    let (vc, ctx, config) = match load_problem(&args, problem, config) {
+        Ok(x) => x,
+        Err(r) => return r,
+    };
+    let checker_config =
+        checker::Config::new(&checker_ctx, false, false, args.detailed_results, false)
+            .solvers(configuration.solvers)
+            .seed(seed(&args));
+    let r = portfolio::run(vc, config, checker_vc, checker_config);
+    report_portfolio_result(&args, r, &ctx)
+}
+
+fn verify_certificate_main(
+    problem: TypedProblem,
+    certificate_path: &str,
+    config: &hopdr::Configuration,
+) -> bool {
+    use hopdr::pdr::certificate::{self, CertificateError};
+
+    // the certificate is for the problem preprocessed in the same way
+    let preprocess_config = preprocess::hes::Config::new().with_configuration(config);
+    let (vc, ctx) = preprocess::hes::preprocess_typed(problem, &preprocess_config);
+    let certificate =
+        fs::read_to_string(certificate_path).expect("Something went wrong reading the certificate");
+    let r = if certificate.trim_start().starts_with('{') {
+        certificate::parse_certificate_json(&certificate, &ctx, &vc)
+    } else {
+        certificate::parse_certificate(&certificate, &ctx, &vc)
+    };
+    let r = r.and_then(|env| certificate::verify(&vc, &env));
+    match r {
+        Ok(()) => {
+            println!("{}", "Certificate verified".green());
+            true
+        }
+        Err(CertificateError::NotInductive { predicate, ty }) => {
+            println!("{}", "Certificate rejected".red());
+            println!(
+                "{}: {} is not derivable from the body of the clause",
+                predicate.pretty_display_with_context(&ctx),
+                ty.pretty_display_with_context(&ctx)
+            );
+            false
+        }
+        Err(e) => {
+            println!("{}", "Certificate rejected".red());
+            println!("{}", e);
+            false
+        }
+    }
+}
+
+/// reads the input, and checks that it is parsed and well-typed
+fn read_input(args: &Args) -> Result<TypedProblem, Report> {
+    let contents = fs::read_to_string(&args.input).expect("Something went wrong reading the file");
+    let f = parse::parse_problem(&contents)
+        .map_err(|e| Report::new(report::Verdict::ParseError).reason(e))?;
+    preprocess::hes::type_check(f).map_err(|e| Report::new(report::Verdict::TypeError).reason(e))
+}
+
+fn solvers_from_args(args: &Args) -> hopdr::solver::SolverRegistry {
+    use hopdr::solver::interpolation::InterpolationSolver;
+
+    let mut solvers = args.solver.registry();
+    if let Some(s) = &args.smt_interpol {
+        solvers.paths.smtinterpol = s.clone();
+    }
+    if let Some(s) = &args.default_interpolation_solver {
+        match InterpolationSolver::from_name(s) {
+            Some(sol) => solvers = solvers.interpolation(sol),
+            None => panic!("Unknown interpolation solver: {}", s),
+        }
+    }
+    solvers
+}
+
 fn gen_configuration_from_args(args: &Args) -> hopdr::Configuration {
     hopdr::Configuration::new()
-        .inlining(!args.no_inlining)
-        .remove_disjunction(args.remove_disjunction)
+        .inlining(!args.no_inlining && !args.no_preprocess)
+        .remove_disjunction(args.remove_disjunction && !args.no_preprocess)
         .wait_every_step(args.debug_wait_every_step)
+        .solvers(solvers_from_args(args))
 }
 
 fn main() {
+    let start = time::Instant::now();
     // setting logs
     env_logger::builder()
         .format_timestamp(None)
@@ -117,64 +440,94 @@ fn main() {
     }
 
     // parsing command line args
-    let args = Args::parse();
+    let mut args = Args::parse();
+    // the seed is printed with the result of the portfolio
+    args.seed = Some(args.seed.unwrap_or_else(checker::fresh_seed));
 
-    // FIXME: adhoc
-    match &args.smt_interpol {
-        Some(s) => hopdr::solver::interpolation::set_smt_interpol_path(s.clone()),
-        None => (),
-    }
+    let config = gen_configuration_from_args(&args);
 
-    match &args.default_interpolation_solver {
-        Some(s) => {
-            if !hopdr::solver::interpolation::set_default_solver(s) {
-                panic!("Unknown interpolation solver: {}", s)
+    let problem = match read_input(&args) {
+        Ok(problem) => problem,
+        Err(r) => {
+            match args.output {
+                Output::Text => report_input_error(&r),
+                Output::Json => println!("{}", r.finish(start).to_json()),
             }
+            std::process::exit(1);
         }
-        None => (),
-    }
+    };
 
-    let config = gen_configuration_from_args(&args);
+    if let Some(Command::VerifyCertificate { certificate }) = &args.command {
+        // the answers must not come from the cache, which may be stale
+        let solvers = config.solvers.clone().cache(false);
+        let ok = solvers.scope(|| verify_certificate_main(problem, certificate, &config));
+        std::process::exit(if ok { 0 } else { 1 });
+    }
 
-    let contents = if args.no_preprocess {
-        fs::read_to_string(&args.input).expect("Something went wrong reading the file")
-    } else {
-        preprocess::hfl_preprocessor::open_file_with_preprocess(&args.input, &config).unwrap()
-    };
+    args.solver.load_cache();
 
-    let pdr_config = pdr::PDRConfig::new(config).dump_tex_progress(args.dump_tex_progress);
+    // the preprocess also uses the solvers
+    let solvers = config.solvers.clone();
+    let pdr_config = pdr::PDRConfig::new(config)
+        .dump_tex_progress(args.dump_tex_progress)
+        .least_fixpoint_unrolling_bound(args.least_fixpoint_unrolling_bound);
 
     // RUST_LOG=info (trace, debug, etc..)
 
     // executes PDR with timeout
     // following https://gist.github.com/junha1/8ebaf53f46ea6fc14ab6797b9939b0f8
     let args_cloned = args.clone(); // FIXME
+    let mode = args.mode;
+    let main = move || {
+        solvers.scope(|| match mode {
+            None => pdr_main(args_cloned, problem, pdr_config),
+            Some(Mode::Portfolio) => portfolio_main(args_cloned, problem, pdr_config),
+        })
+    };
     let r = if args.timeout == 0 {
-        util::executes_with_timeout_and_ctrlc(
-            move || pdr_main(args_cloned, contents, pdr_config),
-            None,
-        )
+        util::executes_with_timeout_and_ctrlc(main, None)
     } else {
         let timeout = time::Duration::from_secs(args.timeout);
-        util::executes_with_timeout_and_ctrlc(
-            move || pdr_main(args_cloned, contents, pdr_config),
-            Some(timeout),
-        )
+        util::executes_with_timeout_and_ctrlc(main, Some(timeout))
     };
-    match r {
-        Ok(()) => (),
+    let text = args.output == Output::Text;
+    let report = match r {
+        Ok(report) => report,
         Err(util::ExecutionError::Timeout) => {
-            println!("{}", "Timeout".red());
+            if text {
+                println!("{}", "Timeout".red());
+            }
+            Report::new(report::Verdict::Timeout)
         }
         Err(util::ExecutionError::Panic) => {
-            println!("{}", "Fail".red());
+            if text {
+                println!("{}", "Fail".red());
+            }
+            Report::new(report::Verdict::Fail).reason("the solver panicked")
         }
         Err(util::ExecutionError::Ctrlc) => {
-            println!("{}", "Execution terminated".white());
+            if text {
+                println!("{}", "Execution terminated".white());
+            }
+            Report::new(report::Verdict::Terminated)
         }
-    }
+    };
+    let report = match mode {
+        Some(Mode::Portfolio) => {
+            if text {
+                println!("Seed: {}", seed(&args));
+            }
+            report.seed(seed(&args))
+        }
+        None => report,
+    };
+    args.solver.save_cache();
     crate::stat::finalize();
 
+    if !text {
+        println!("{}", report.finish(start).to_json());
+    }
+
     if args.print_stat {
         crate::stat::dump();
     }
@@ -1,5 +1,5 @@
 use crate::formula::hes::{GoalBase, GoalKind};
-use crate::formula::{Constraint, Fv, Ident, Op, PredKind};
+use crate::formula::{Constraint, Fv, Ident, Negation, Op, PredKind};
 use crate::ml::Range;
 
 type LB = Option<i64>;
@@ -337,7 +337,7 @@ fn analyze_inner<Aux>(x: Ident, g: &GoalBase<Constraint, Aux>) -> Domain {
             let d2 = analyze_inner(x, g2);
             d1.meet(d2)
         }
-        GoalKind::Univ(_, g) => analyze_inner(x, g),
+        GoalKind::Univ(_, g) | GoalKind::Exists(_, g) => analyze_inner(x, g),
         GoalKind::ITE(_, g1, g2) => {
             let d1 = analyze_inner(x, g1);
             let d2 = analyze_inner(x, g2);
@@ -346,8 +346,32 @@ fn analyze_inner<Aux>(x: Ident, g: &GoalBase<Constraint, Aux>) -> Domain {
     }
 }
 
-pub fn analyze<Aux>(x: Ident, g: &GoalBase<Constraint, Aux>) -> Range {
-    let d = analyze_inner(x, g);
+/// dual of `analyze_inner`: over-approximates the domain where g may be true
+fn analyze_witness_inner<Aux>(x: Ident, g: &GoalBase<Constraint, Aux>) -> Domain {
+    match g.kind() {
+        GoalKind::Constr(c) => c.negate().map_or(Domain::all(), |c| gen_bound(x, &c)),
+        GoalKind::Op(_) => panic!("program error"),
+        GoalKind::Var(_) | GoalKind::Abs(_, _) | GoalKind::App(_, _) => Domain::all(),
+        GoalKind::Conj(g1, g2) => {
+            let d1 = analyze_witness_inner(x, g1);
+            let d2 = analyze_witness_inner(x, g2);
+            d1.meet(d2)
+        }
+        GoalKind::Disj(g1, g2) => {
+            let d1 = analyze_witness_inner(x, g1);
+            let d2 = analyze_witness_inner(x, g2);
+            d1.join(d2)
+        }
+        GoalKind::Univ(_, g) | GoalKind::Exists(_, g) => analyze_witness_inner(x, g),
+        GoalKind::ITE(_, g1, g2) => {
+            let d1 = analyze_witness_inner(x, g1);
+            let d2 = analyze_witness_inner(x, g2);
+            d1.join(d2)
+        }
+    }
+}
+
+fn domain_to_range(d: Domain) -> Range {
     let mut r = Range::new();
     if d.is_bot() {
         return r;
@@ -362,3 +386,17 @@ pub fn analyze<Aux>(x: Ident, g: &GoalBase<Constraint, Aux>) -> Range {
     }
     r
 }
+
+pub fn analyze<Aux>(x: Ident, g: &GoalBase<Constraint, Aux>) -> Range {
+    domain_to_range(analyze_inner(x, g))
+}
+
+/// Range of candidate witnesses for `∃x. g`
+pub fn analyze_witness<Aux>(x: Ident, g: &GoalBase<Constraint, Aux>) -> Range {
+    let d = analyze_witness_inner(x, g);
+    // no witness exists at all
+    if d.is_bot() {
+        return Range::new().lb(0).ub(0);
+    }
+    domain_to_range(d)
+}
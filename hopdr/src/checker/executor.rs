@@ -28,11 +28,11 @@ fn parse_trace(s: &str) -> Option<String> {
     None
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 pub struct CounterStats {
-    retry: usize,
-    recursion: usize,
-    rand_int: usize,
+    pub(super) retry: usize,
+    pub(super) recursion: usize,
+    pub(super) rand_int: usize,
 }
 
 impl fmt::Display for CounterStats {
@@ -101,17 +101,18 @@ fn parse(s: &str) -> ExecResult {
     }
 }
 
-pub async fn executor(s: String) -> (ExecResult, Option<CounterStats>) {
+/// runs the program `s` for at most `timeout`
+pub async fn executor(s: String, timeout: Option<Duration>) -> (ExecResult, Option<CounterStats>) {
     let f = save_prog(s);
     let args = vec![f.path().to_str().unwrap()];
     debug!("filename: {}", &args[0]);
-    let out = util::exec_input_with_timeout_async(
-        "hopdr-check-runner",
-        &args,
-        &[],
-        Duration::from_secs(1),
-    )
-    .await;
+    let out = match util::exec_input_with_timeout_async("hopdr-check-runner", &args, &[], timeout)
+        .await
+    {
+        Ok(out) => out,
+        // no counterexample is found in time
+        Err(util::ExecError::Timeout) => return (ExecResult::Unknown, None),
+    };
     let s = String::from_utf8(out).unwrap();
     debug!("result: {s}");
     let stats = parse_counter_stats(&s);
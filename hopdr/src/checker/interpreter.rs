@@ -0,0 +1,911 @@
+//! Interpreter of the translated programs, which runs them without OCaml.
+//!
+//! It follows the semantics of the program printed with `library.ml`: each epoch runs the
+//! main expression with fresh random integers, and the program is refuted (i.e. the problem
+//! is invalid) as soon as an epoch terminates without `TrueExc`. The range of the integers
+//! and the bound of the recursion follow the schedule of the program (`ml::Schedule`), which
+//! is retried until the run is cancelled (`interpret` with a timeout).
+//!
+//! The integers are drawn from its own generator seeded by `Program::seed`, so a seed
+//! replays the same execution of the interpreter, but not that of the compiled program.
+//!
+//! The interpreter also shrinks counterexamples (`shrink`): it re-runs the program with
+//! small integers and shallow recursion, and keeps the smallest trace of the failures.
+use super::executor::{CounterStats, ExecResult};
+use super::trace;
+use super::{
+    F_EXISTS, F_EXISTS_TRACE, T_MK_APP, T_MK_CONJ, T_MK_DISJ, T_MK_EMPTY_TRACE, T_MK_UNIV,
+    T_PRINT_TRACE,
+};
+use crate::formula::{Constraint, ConstraintExpr, Ident, Op, OpExpr, OpKind, PredKind};
+use crate::ml::{Expr, ExprKind, Program, Schedule, TypeKind};
+
+use rpds::HashTrieMap;
+use thiserror::Error;
+
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// the recursion of the interpreter follows that of the program
+const STACK_SIZE: usize = 1024 * 1024 * 1024;
+/// the nesting of calls regarded as `Stack_overflow`
+const MAX_DEPTH: usize = 20000;
+
+/// the schedule of shrinking, from small integers and shallow recursion
+const SHRINK_RANGES: [u32; 4] = [0, 1, 5, 150];
+const SHRINK_RECURSIONS: [u64; 3] = [10, 100, 1000];
+const SHRINK_EPOCHS: usize = 100;
+
+const DEFAULT_SEED: u64 = 0x2545f4914f6cdd1d;
+
+/// OCaml's integers are 63-bit
+const MAX_INT: i64 = (1 << 62) - 1;
+const MIN_INT: i64 = -(1 << 62);
+
+/// exceptions raised during an epoch
+#[derive(Debug)]
+enum Exc {
+    /// `TrueExc`
+    True,
+    IntegerOverflow,
+    RecursionExceeded,
+    StackOverflow,
+    /// exceptions that the library does not catch, which make the run fail
+    Fatal(&'static str),
+    /// the run has been cancelled
+    Cancelled,
+}
+
+/// the failures of the arithmetic of `library.ml`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum ArithError {
+    #[error("Division_by_zero")]
+    DivisionByZero,
+    #[error("IntegerOverflow")]
+    Overflow,
+}
+
+impl From<ArithError> for Exc {
+    fn from(e: ArithError) -> Exc {
+        match e {
+            ArithError::DivisionByZero => Exc::Fatal("Division_by_zero"),
+            ArithError::Overflow => Exc::IntegerOverflow,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Trace {
+    Empty,
+    Conj(i64, Rc<Trace>),
+    Disj(Rc<Trace>, Rc<Trace>),
+    Univ(i64, Rc<Trace>),
+    App(Rc<str>, Vec<i64>, Rc<Trace>),
+}
+
+impl Trace {
+    /// the trace printed by `print_trace`
+    fn to_trace(&self) -> trace::Trace {
+        match self {
+            Trace::Empty => trace::Trace::Empty,
+            Trace::Conj(x, t) => trace::Trace::Conj(*x, Box::new(t.to_trace())),
+            Trace::Disj(t1, t2) => {
+                trace::Trace::Disj(Box::new(t1.to_trace()), Box::new(t2.to_trace()))
+            }
+            Trace::Univ(v, t) => trace::Trace::Univ(*v, Box::new(t.to_trace())),
+            // the arguments are listed from the last one
+            Trace::App(name, vs, t) => trace::Trace::App(
+                name.to_string(),
+                vs.iter().rev().cloned().collect(),
+                Box::new(t.to_trace()),
+            ),
+        }
+    }
+}
+
+struct Closure {
+    ident: Ident,
+    body: Expr,
+    env: Env,
+}
+
+/// partial application of a top-level function
+struct Partial {
+    idx: usize,
+    args: Vec<Value>,
+}
+
+#[derive(Clone)]
+enum Value {
+    Int(i64),
+    Bool(bool),
+    Unit,
+    Tuple(Rc<Vec<Value>>),
+    Closure(Rc<Closure>),
+    Global(Rc<Partial>),
+    Tag(Rc<str>),
+    List(Rc<Vec<Value>>),
+    Trace(Rc<Trace>),
+}
+
+impl Value {
+    fn int(&self) -> i64 {
+        match self {
+            Value::Int(x) => *x,
+            _ => panic!("program error: not an integer"),
+        }
+    }
+    fn bool(&self) -> bool {
+        match self {
+            Value::Bool(b) => *b,
+            _ => panic!("program error: not a boolean"),
+        }
+    }
+    fn trace(&self) -> Rc<Trace> {
+        match self {
+            Value::Trace(t) => t.clone(),
+            _ => panic!("program error: not a trace"),
+        }
+    }
+}
+
+type Env = HashTrieMap<Ident, Value>;
+
+/// xorshift64*
+///
+/// The sequence of the integers differs from that of OCaml's `Random`.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Rng {
+        Rng(if seed == 0 { DEFAULT_SEED } else { seed })
+    }
+    fn next(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.0 = x;
+        x.wrapping_mul(0x2545f4914f6cdd1d)
+    }
+    /// `Random.int n`
+    fn int(&mut self, n: i64) -> i64 {
+        (self.next() % n as u64) as i64
+    }
+}
+
+/// top-level function `f x1 ... xn = hopdr_count_recursion (); body`
+struct Function {
+    params: Vec<Ident>,
+    body: Expr,
+}
+
+/// The program in the form run by the interpreter
+pub struct Machine {
+    functions: Vec<Function>,
+    globals: HashMap<Ident, usize>,
+    main: Expr,
+    seed: u64,
+    schedule: Schedule,
+}
+
+impl Machine {
+    pub fn new(prog: &Program) -> Machine {
+        let mut globals = HashMap::new();
+        let functions = prog
+            .functions
+            .iter()
+            .enumerate()
+            .map(|(idx, f)| {
+                globals.insert(f.name, idx);
+                // the recursion is counted after the arguments of the type are given
+                // (see `handle_fun` of the printer)
+                let mut params = Vec::new();
+                let mut ty = f.ty.clone();
+                let mut body = f.body.clone();
+                loop {
+                    let next = match (ty.kind(), body.kind()) {
+                        (TypeKind::Arrow(_, t), ExprKind::Fun { ident, body: b }) => {
+                            Some((t.clone(), ident.ident, b.clone()))
+                        }
+                        _ => None,
+                    };
+                    match next {
+                        Some((t, x, b)) => {
+                            params.push(x);
+                            ty = t;
+                            body = b;
+                        }
+                        None => break,
+                    }
+                }
+                Function { params, body }
+            })
+            .collect();
+        Machine {
+            functions,
+            globals,
+            main: prog.main.clone(),
+            seed: prog.seed,
+            schedule: prog.schedule.clone(),
+        }
+    }
+
+    fn state<'a>(&'a self, cancel: &'a AtomicBool) -> State<'a> {
+        State {
+            machine: self,
+            cancel,
+            rng: Rng::new(self.seed),
+            check_mn: 0,
+            check_mx: 1,
+            n_recursion: 0,
+            n_recursion_limit: 0,
+            depth: 0,
+            counter: CounterStats::default(),
+            trace: None,
+        }
+    }
+
+    /// runs the schedule of random testing
+    fn run(&self, cancel: &AtomicBool) -> (ExecResult, Option<CounterStats>) {
+        let mut st = self.state(cancel);
+        let r = st.run_schedule();
+        (r, Some(st.counter))
+    }
+}
+
+struct State<'a> {
+    machine: &'a Machine,
+    cancel: &'a AtomicBool,
+    rng: Rng,
+    check_mn: i64,
+    check_mx: i64,
+    n_recursion: u64,
+    n_recursion_limit: u64,
+    depth: usize,
+    counter: CounterStats,
+    /// the trace printed by `print_trace` in the current epoch
+    trace: Option<trace::Trace>,
+}
+
+/// the arithmetic of `library.ml` on OCaml's integers
+pub(super) fn arith(o: &OpKind, x: i64, y: i64) -> Result<i64, ArithError> {
+    let (x2, y2) = (x as i128, y as i128);
+    let r = match o {
+        OpKind::Add => x2 + y2,
+        OpKind::Sub => x2 - y2,
+        OpKind::Mul => x2 * y2,
+        OpKind::Div if y == 0 => return Err(ArithError::DivisionByZero),
+        OpKind::Div => return Ok(x / y),
+        OpKind::Mod if y == 0 => return Err(ArithError::DivisionByZero),
+        // the result of `mod` of the library is not negative
+        OpKind::Mod if x % y < 0 => (x % y) as i128 + y2,
+        OpKind::Mod => (x % y) as i128,
+    };
+    if r < MIN_INT as i128 || (MAX_INT as i128) < r {
+        Err(ArithError::Overflow)
+    } else {
+        Ok(r as i64)
+    }
+}
+
+pub(super) fn compare(p: &PredKind, x: i64, y: i64) -> bool {
+    match p {
+        PredKind::Eq => x == y,
+        PredKind::Neq => x != y,
+        PredKind::Lt => x < y,
+        PredKind::Leq => x <= y,
+        PredKind::Gt => x > y,
+        PredKind::Geq => x >= y,
+    }
+}
+
+impl<'a> State<'a> {
+    /// `set_n_recursion_limit n_rec; set_range r`
+    fn configure(&mut self, n_rec: u64, r: u32) {
+        self.n_recursion_limit = n_rec;
+        self.check_mn = -(r as i64);
+        self.check_mx = r as i64 + 1;
+    }
+
+    /// runs the main expression once
+    fn epoch(&mut self) -> Result<Value, Exc> {
+        let machine = self.machine;
+        self.counter.retry += 1;
+        self.n_recursion = 0;
+        self.depth = 0;
+        self.trace = None;
+        self.eval(&machine.main, &Env::new())
+    }
+
+    /// retries the schedule until the program is refuted or the run is cancelled
+    /// (`if fail () then hopdr_main ...` of the library)
+    fn run_schedule(&mut self) -> ExecResult {
+        let machine = self.machine;
+        let schedule = &machine.schedule;
+        loop {
+            for n_rec in schedule.recursion_limits.iter() {
+                for r in schedule.ranges.iter() {
+                    self.configure(*n_rec, *r);
+                    for _ in 0..schedule.retries {
+                        // epochs without recursion never look at the flag
+                        if self.cancel.load(Ordering::Relaxed) {
+                            return ExecResult::Unknown;
+                        }
+                        match self.epoch() {
+                            // if it terminates, it means that the program is *NOT* safe
+                            Ok(_) => {
+                                let t = self.trace.take().map(|t| t.to_string());
+                                return ExecResult::Invalid(t);
+                            }
+                            Err(Exc::IntegerOverflow) => self.event_integer_overflow(),
+                            Err(Exc::True)
+                            | Err(Exc::RecursionExceeded)
+                            | Err(Exc::StackOverflow) => {}
+                            Err(Exc::Fatal(e)) => {
+                                return ExecResult::Fail(format!("Fatal error: exception {}", e))
+                            }
+                            Err(Exc::Cancelled) => return ExecResult::Unknown,
+                        }
+                    }
+                }
+            }
+            if self.cancel.load(Ordering::Relaxed) {
+                return ExecResult::Unknown;
+            }
+        }
+    }
+
+    /// returns the smallest trace of the failures in the schedule of shrinking
+    fn shrink(&mut self) -> Option<trace::Trace> {
+        let mut best: Option<trace::Trace> = None;
+        for r in SHRINK_RANGES.iter() {
+            for n_rec in SHRINK_RECURSIONS.iter() {
+                self.configure(*n_rec, *r);
+                for _ in 0..SHRINK_EPOCHS {
+                    match self.epoch() {
+                        Ok(_) => match (self.trace.take(), &best) {
+                            (Some(t), Some(b)) if !t.is_smaller_than(b) => (),
+                            (Some(t), _) => best = Some(t),
+                            (None, _) => (),
+                        },
+                        Err(Exc::IntegerOverflow) => self.event_integer_overflow(),
+                        Err(Exc::True) | Err(Exc::RecursionExceeded) | Err(Exc::StackOverflow) => {}
+                        Err(Exc::Fatal(_)) | Err(Exc::Cancelled) => return best,
+                    }
+                }
+            }
+        }
+        best
+    }
+
+    fn event_integer_overflow(&mut self) {
+        if self.check_mx > 10 {
+            self.check_mx /= 2;
+        }
+        if self.check_mn < -10 {
+            self.check_mn /= 2;
+        }
+    }
+
+    fn count_recursion(&mut self) -> Result<(), Exc> {
+        if self.cancel.load(Ordering::Relaxed) {
+            return Err(Exc::Cancelled);
+        }
+        self.n_recursion += 1;
+        if self.n_recursion > self.n_recursion_limit {
+            return Err(Exc::RecursionExceeded);
+        }
+        self.counter.recursion += 1;
+        Ok(())
+    }
+
+    fn rand_int(&mut self, lb: Option<i64>, ub: Option<i64>) -> Result<i64, Exc> {
+        self.counter.rand_int += 1;
+        let diff = self.check_mx - self.check_mn;
+        let (mn, mx) = match (lb, ub) {
+            (Some(x), Some(y)) => (x, y),
+            (Some(x), None) => (x, x + diff),
+            (None, Some(y)) => (y - diff, y),
+            (None, None) => (self.check_mn, self.check_mx),
+        };
+        let bound = mx - mn;
+        if bound <= 0 || bound >= 1 << 30 {
+            return Err(Exc::Fatal("Invalid_argument(\"Random.int\")"));
+        }
+        Ok(self.rng.int(bound) + mn)
+    }
+
+    fn lookup(&mut self, x: &Ident, env: &Env) -> Result<Value, Exc> {
+        if let Some(v) = env.get(x) {
+            return Ok(v.clone());
+        }
+        match self.machine.globals.get(x) {
+            Some(idx) => self.call_global(*idx, Vec::new()),
+            None => panic!("program error: unbound variable {}", x),
+        }
+    }
+
+    fn call_global(&mut self, idx: usize, args: Vec<Value>) -> Result<Value, Exc> {
+        let machine = self.machine;
+        let f = &machine.functions[idx];
+        if args.len() < f.params.len() {
+            return Ok(Value::Global(Rc::new(Partial { idx, args })));
+        }
+        let env = f
+            .params
+            .iter()
+            .zip(args)
+            .fold(Env::new(), |env, (x, v)| env.insert(*x, v));
+        self.count_recursion()?;
+        self.enter(&f.body, &env)
+    }
+
+    /// evaluates the body of a function
+    fn enter(&mut self, body: &Expr, env: &Env) -> Result<Value, Exc> {
+        if self.depth >= MAX_DEPTH {
+            return Err(Exc::StackOverflow);
+        }
+        self.depth += 1;
+        let r = self.eval(body, env);
+        self.depth -= 1;
+        r
+    }
+
+    fn apply(&mut self, f: Value, arg: Value) -> Result<Value, Exc> {
+        match f {
+            Value::Closure(c) => {
+                let env = c.env.insert(c.ident, arg);
+                self.enter(&c.body, &env)
+            }
+            Value::Global(p) => {
+                let mut args = p.args.clone();
+                args.push(arg);
+                self.call_global(p.idx, args)
+            }
+            _ => panic!("program error: not a function"),
+        }
+    }
+
+    fn eval_op(&mut self, o: &Op, env: &Env) -> Result<i64, Exc> {
+        match o.kind() {
+            OpExpr::Op(k, x, y) => {
+                let x = self.eval_op(x, env)?;
+                let y = self.eval_op(y, env)?;
+                Ok(arith(k, x, y)?)
+            }
+            OpExpr::Var(x) => Ok(self.lookup(x, env)?.int()),
+            OpExpr::Const(c) => Ok(*c),
+            OpExpr::ITE(c, x, y) => {
+                if self.eval_constraint(c, env)? {
+                    self.eval_op(x, env)
+                } else {
+                    self.eval_op(y, env)
+                }
+            }
+            OpExpr::Ptr(_, o) => self.eval_op(o, env),
+        }
+    }
+
+    fn eval_constraint(&mut self, c: &Constraint, env: &Env) -> Result<bool, Exc> {
+        match c.kind() {
+            ConstraintExpr::True => Ok(true),
+            ConstraintExpr::False => Ok(false),
+            ConstraintExpr::Pred(p, l) if l.len() == 2 => {
+                let x = self.eval_op(&l[0], env)?;
+                let y = self.eval_op(&l[1], env)?;
+                Ok(compare(p, x, y))
+            }
+            ConstraintExpr::Conj(c1, c2) => {
+                Ok(self.eval_constraint(c1, env)? && self.eval_constraint(c2, env)?)
+            }
+            ConstraintExpr::Disj(c1, c2) => {
+                Ok(self.eval_constraint(c1, env)? || self.eval_constraint(c2, env)?)
+            }
+            ConstraintExpr::Quantifier(q, x, g) => {
+                assert!(q.is_universal());
+                let v = self.rand_int(None, None)?;
+                self.eval_constraint(g, &env.insert(x.id, Value::Int(v)))
+            }
+            _ => panic!("program error"),
+        }
+    }
+
+    /// `check_witnesses` of `library.ml`: gives up the epoch when there are more candidate
+    /// witnesses than the integers of the current range
+    fn witnesses(&self, bounds: &[Value]) -> Result<std::ops::Range<i64>, Exc> {
+        let (mn, mx) = (bounds[0].int(), bounds[1].int());
+        if mx.saturating_sub(mn) > self.check_mx - self.check_mn {
+            Err(Exc::True)
+        } else {
+            Ok(mn..mx)
+        }
+    }
+
+    /// the functions of `library.ml` called by `CallNamedFun`
+    fn call_named_fun(&mut self, name: &str, args: Vec<Value>) -> Result<Value, Exc> {
+        let trace = |t| Ok(Value::Trace(Rc::new(t)));
+        match (name, args.as_slice()) {
+            (T_MK_EMPTY_TRACE, [_]) => trace(Trace::Empty),
+            (T_MK_CONJ, [x, t]) => trace(Trace::Conj(x.int(), t.trace())),
+            (T_MK_DISJ, [t1, t2]) => trace(Trace::Disj(t1.trace(), t2.trace())),
+            (T_MK_UNIV, [v, t]) => trace(Trace::Univ(v.int(), t.trace())),
+            (T_MK_APP, [Value::Tag(f), Value::List(vs), t]) => {
+                let vs = vs.iter().map(|v| v.int()).collect();
+                trace(Trace::App(f.clone(), vs, t.trace()))
+            }
+            (T_PRINT_TRACE, [t]) => {
+                self.trace = Some(t.trace().to_trace());
+                Ok(Value::Unit)
+            }
+            (F_EXISTS, [Value::Tuple(bounds), f]) => {
+                for v in self.witnesses(bounds)? {
+                    self.apply(f.clone(), Value::Int(v))?;
+                }
+                Ok(Value::Unit)
+            }
+            (F_EXISTS_TRACE, [Value::Tuple(bounds), f]) => {
+                let mut t = Rc::new(Trace::Empty);
+                for v in self.witnesses(bounds)? {
+                    let t2 = self.apply(f.clone(), Value::Int(v))?.trace();
+                    t = Rc::new(Trace::Disj(t, t2));
+                }
+                Ok(Value::Trace(t))
+            }
+            _ => panic!("program error: unknown function {}", name),
+        }
+    }
+
+    fn eval(&mut self, e: &Expr, env: &Env) -> Result<Value, Exc> {
+        match e.kind() {
+            ExprKind::Var(x) => self.lookup(x, env),
+            ExprKind::Constraint(c) => Ok(Value::Bool(self.eval_constraint(c, env)?)),
+            ExprKind::Or(x, y) => {
+                if self.eval(x, env)?.bool() {
+                    Ok(Value::Bool(true))
+                } else {
+                    self.eval(y, env)
+                }
+            }
+            ExprKind::And(x, y) => {
+                if self.eval(x, env)?.bool() {
+                    self.eval(y, env)
+                } else {
+                    Ok(Value::Bool(false))
+                }
+            }
+            // OCaml evaluates the argument first
+            ExprKind::App(f, x) => {
+                let x = self.eval(x, env)?;
+                let f = self.eval(f, env)?;
+                self.apply(f, x)
+            }
+            ExprKind::IApp(f, o) => {
+                let x = Value::Int(self.eval_op(o, env)?);
+                let f = self.eval(f, env)?;
+                self.apply(f, x)
+            }
+            ExprKind::Fun { ident, body } => Ok(Value::Closure(Rc::new(Closure {
+                ident: ident.ident,
+                body: body.clone(),
+                env: env.clone(),
+            }))),
+            ExprKind::If { cond, then, els } => {
+                if self.eval(cond, env)?.bool() {
+                    self.eval(then, env)
+                } else {
+                    self.eval(els, env)
+                }
+            }
+            ExprKind::LetRand { ident, range, body } => {
+                let v = self.rand_int(range.lb, range.ub)?;
+                self.eval(body, &env.insert(*ident, Value::Int(v)))
+            }
+            ExprKind::Raise => Err(Exc::True),
+            ExprKind::Unit => Ok(Value::Unit),
+            ExprKind::TryWith { body, handler } => match self.eval(body, env) {
+                Err(Exc::True) => self.eval(handler, env),
+                r => r,
+            },
+            ExprKind::Assert(c) => {
+                if self.eval(c, env)?.bool() {
+                    Ok(Value::Unit)
+                } else {
+                    Err(Exc::True)
+                }
+            }
+            ExprKind::Sequential { lhs, rhs } => {
+                self.eval(lhs, env)?;
+                self.eval(rhs, env)
+            }
+            ExprKind::Tuple(args) => {
+                let mut vs = Vec::new();
+                for arg in args.iter() {
+                    vs.push(self.eval(arg, env)?);
+                }
+                // `(e)` is just `e`
+                if vs.len() == 1 {
+                    Ok(vs.pop().unwrap())
+                } else {
+                    Ok(Value::Tuple(Rc::new(vs)))
+                }
+            }
+            ExprKind::LetTuple { idents, body, cont } => {
+                let v = self.eval(body, env)?;
+                let env = match (idents.len(), v) {
+                    (0, _) => env.clone(),
+                    (1, v) => env.insert(idents[0], v),
+                    (_, Value::Tuple(vs)) if vs.len() == idents.len() => idents
+                        .iter()
+                        .zip(vs.iter())
+                        .fold(env.clone(), |env, (x, v)| env.insert(*x, v.clone())),
+                    _ => panic!("program error: tuple mismatch"),
+                };
+                self.eval(cont, &env)
+            }
+            ExprKind::Op(o) => Ok(Value::Int(self.eval_op(o, env)?)),
+            ExprKind::CallNamedFun(name, args) => {
+                let mut vs = Vec::new();
+                for arg in args.iter() {
+                    vs.push(self.eval(arg, env)?);
+                }
+                self.call_named_fun(name, vs)
+            }
+            ExprKind::Tag(s) => Ok(Value::Tag(Rc::from(s.as_str()))),
+            ExprKind::List(l) => {
+                let mut vs = Vec::new();
+                for e in l.iter() {
+                    vs.push(self.eval(e, env)?);
+                }
+                Ok(Value::List(Rc::new(vs)))
+            }
+            ExprKind::LetTag(_, body, cont) => {
+                self.eval(body, env)?;
+                self.eval(cont, env)
+            }
+        }
+    }
+}
+
+/// stops the interpreter when the run is dropped (e.g. the portfolio aborts it)
+struct CancelOnDrop(Arc<AtomicBool>);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+/// runs `f` in its own thread, which is stopped when the returned future is dropped.
+///
+/// Returns None if `f` panics.
+async fn spawn<T, F>(f: F) -> Option<T>
+where
+    T: Send + 'static,
+    F: FnOnce(&AtomicBool) -> T + Send + 'static,
+{
+    let cancel = Arc::new(AtomicBool::new(false));
+    let _guard = CancelOnDrop(cancel.clone());
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    std::thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(move || {
+            let _ = tx.send(f(&cancel));
+        })
+        .unwrap();
+    rx.await.ok()
+}
+
+/// runs `machine` for at most `timeout`, and returns the result in the same form as
+/// `executor::executor`
+pub async fn interpret(
+    machine: Arc<Machine>,
+    timeout: Option<Duration>,
+) -> (ExecResult, Option<CounterStats>) {
+    let run = spawn(move |cancel| machine.run(cancel));
+    let r = match timeout {
+        // the interpreter is stopped when `run` is dropped
+        Some(timeout) => match tokio::time::timeout(timeout, run).await {
+            Ok(r) => r,
+            Err(_) => return (ExecResult::Unknown, None),
+        },
+        None => run.await,
+    };
+    match r {
+        Some(r) => r,
+        None => (
+            ExecResult::Fail("the interpreter panicked".to_string()),
+            None,
+        ),
+    }
+}
+
+/// returns the smallest trace of the failures of `machine` found by re-running it with
+/// small integers and shallow recursion, or `trace` if no smaller one is found
+pub async fn shrink(machine: Arc<Machine>, trace: trace::Trace) -> trace::Trace {
+    let best = spawn(move |cancel| machine.state(cancel).shrink()).await;
+    match best.flatten() {
+        Some(t) if t.is_smaller_than(&trace) => t,
+        _ => trace,
+    }
+}
+
+#[cfg(test)]
+fn machine_of_main(main: Expr) -> Machine {
+    let ctx = crate::preprocess::Context::empty();
+    let prog = Program {
+        functions: Vec::new(),
+        main,
+        ctx: &ctx,
+        seed: DEFAULT_SEED,
+        schedule: Schedule::default(),
+    };
+    Machine::new(&prog)
+}
+
+#[cfg(test)]
+fn run_main(main: Expr) -> ExecResult {
+    machine_of_main(main).run(&AtomicBool::new(false)).0
+}
+
+/// runs `machine` until it is refuted or a second passes, for programs that are not refuted
+#[cfg(test)]
+async fn run_for_a_while(machine: Machine) -> ExecResult {
+    interpret(Arc::new(machine), Some(Duration::from_secs(1)))
+        .await
+        .0
+}
+
+#[tokio::test]
+async fn test_interpret() {
+    use crate::ml::Range;
+    // let x = rand_int (Some 0, Some 10) in if x > 5 then raise TrueExc else ()
+    let x = Ident::fresh();
+    let c = Constraint::mk_gt(Op::mk_var(x), Op::mk_const(5));
+    let body = Expr::mk_if(Expr::mk_constraint(c), Expr::mk_raise(), Expr::mk_unit());
+    let main = Expr::mk_letrand(x, Range::new().lb(0).ub(10), body);
+    assert!(matches!(run_main(main), ExecResult::Invalid(None)));
+
+    // try raise TrueExc with TrueExc -> raise TrueExc
+    let main = Expr::mk_try_with(Expr::mk_raise(), Expr::mk_raise());
+    assert!(matches!(
+        run_for_a_while(machine_of_main(main)).await,
+        ExecResult::Unknown
+    ));
+
+    // print_trace (mk_conj 1 (mk_univ 3 (mk_empty_trace ())))
+    let t = Expr::mk_call_named_fun(T_MK_EMPTY_TRACE, vec![Expr::mk_unit()]);
+    let t = Expr::mk_call_named_fun(T_MK_UNIV, vec![Expr::mk_op(Op::mk_const(3)), t]);
+    let t = Expr::mk_call_named_fun(T_MK_CONJ, vec![Expr::mk_op(Op::mk_const(1)), t]);
+    let main = Expr::mk_call_named_fun(T_PRINT_TRACE, vec![t]);
+    match run_main(main) {
+        ExecResult::Invalid(Some(t)) => assert_eq!(t, "(conj 1 (univ 3 ()))"),
+        _ => panic!("trace is not printed"),
+    }
+}
+
+#[tokio::test]
+async fn test_interpret_exists() {
+    use crate::ml::{Type as SType, Variable};
+    // hopdr_exists (lb, ub) (fun x -> ())
+    let exists = |lb, ub| {
+        let bounds = Expr::mk_tuple(vec![
+            Expr::mk_op(Op::mk_const(lb)),
+            Expr::mk_op(Op::mk_const(ub)),
+        ]);
+        let x = Variable::mk(Ident::fresh(), SType::mk_type_int());
+        let f = Expr::mk_fun(x, Expr::mk_unit());
+        Expr::mk_call_named_fun(F_EXISTS, vec![bounds, f])
+    };
+    assert!(matches!(run_main(exists(0, 3)), ExecResult::Invalid(None)));
+
+    // more candidates than the integers of the range
+    assert!(matches!(
+        run_for_a_while(machine_of_main(exists(0, 1 << 40))).await,
+        ExecResult::Unknown
+    ));
+}
+
+#[tokio::test]
+async fn test_interpret_overflow() {
+    // 4611686018427387903 + 1 raises IntegerOverflow in every epoch
+    let o = Op::mk_add(Op::mk_const(MAX_INT), Op::mk_const(1));
+    let c = Constraint::mk_eq(o, Op::mk_const(0));
+    let main = Expr::mk_assert(Expr::mk_constraint(c));
+    assert!(matches!(
+        run_for_a_while(machine_of_main(main)).await,
+        ExecResult::Unknown
+    ));
+}
+
+#[test]
+fn test_interpret_seed() {
+    use crate::ml::Range;
+    // let x = rand_int (None, None) in print_trace (mk_univ x (mk_empty_trace ()))
+    let x = Ident::fresh();
+    let t = Expr::mk_call_named_fun(T_MK_EMPTY_TRACE, vec![Expr::mk_unit()]);
+    let t = Expr::mk_call_named_fun(T_MK_UNIV, vec![Expr::mk_var(x), t]);
+    let t = Expr::mk_call_named_fun(T_PRINT_TRACE, vec![t]);
+    let main = Expr::mk_letrand(x, Range::new(), t);
+    let ctx = crate::preprocess::Context::empty();
+    let prog = Program {
+        functions: Vec::new(),
+        main,
+        ctx: &ctx,
+        seed: DEFAULT_SEED,
+        // wide enough that two seeds hardly draw the same integer
+        schedule: Schedule {
+            ranges: vec![100000],
+            ..Schedule::default()
+        },
+    };
+    let mut machine = Machine::new(&prog);
+    let traces: Vec<_> = [1, 2, 1]
+        .iter()
+        .map(|seed| {
+            machine.seed = *seed;
+            match machine.run(&AtomicBool::new(false)).0 {
+                ExecResult::Invalid(Some(t)) => t,
+                _ => panic!("trace is not printed"),
+            }
+        })
+        .collect();
+    assert_eq!(traces[0], traces[2]);
+    assert_ne!(traces[0], traces[1]);
+}
+
+#[tokio::test]
+async fn test_interpret_schedule() {
+    use crate::ml::Range;
+    // let x = rand_int (None, None) in if x > 5 then () else raise TrueExc
+    let x = Ident::fresh();
+    let c = Constraint::mk_gt(Op::mk_var(x), Op::mk_const(5));
+    let body = Expr::mk_if(Expr::mk_constraint(c), Expr::mk_unit(), Expr::mk_raise());
+    let main = Expr::mk_letrand(x, Range::new(), body);
+    let schedule = Schedule {
+        recursion_limits: vec![10],
+        ranges: vec![1, 5],
+        retries: 100,
+    };
+    // retrying the schedule does not help
+    let mut machine = machine_of_main(main.clone());
+    machine.schedule = schedule.clone();
+    assert!(matches!(
+        run_for_a_while(machine).await,
+        ExecResult::Unknown
+    ));
+    let mut machine = machine_of_main(main);
+    machine.schedule = schedule;
+    machine.schedule.ranges.push(150);
+    assert!(matches!(
+        machine.run(&AtomicBool::new(false)).0,
+        ExecResult::Invalid(None)
+    ));
+}
+
+#[tokio::test]
+async fn test_shrink() {
+    use crate::ml::Range;
+    // let x = rand_int (None, None) in
+    // if x < 3 then raise TrueExc else print_trace (mk_univ x (mk_empty_trace ()))
+    let x = Ident::fresh();
+    let c = Constraint::mk_lt(Op::mk_var(x), Op::mk_const(3));
+    let t = Expr::mk_call_named_fun(T_MK_EMPTY_TRACE, vec![Expr::mk_unit()]);
+    let t = Expr::mk_call_named_fun(T_MK_UNIV, vec![Expr::mk_var(x), t]);
+    let t = Expr::mk_call_named_fun(T_PRINT_TRACE, vec![t]);
+    let body = Expr::mk_if(Expr::mk_constraint(c), Expr::mk_raise(), t);
+    let main = Expr::mk_letrand(x, Range::new(), body);
+    let machine = Arc::new(machine_of_main(main));
+
+    let found: trace::Trace = "(univ 99999 ())".parse().unwrap();
+    let t = shrink(machine.clone(), found).await;
+    assert_eq!(t.to_string(), "(univ 3 ())");
+
+    // the trace is kept if no smaller one is found
+    let found: trace::Trace = "()".parse().unwrap();
+    let t = shrink(machine, found).await;
+    assert_eq!(t.to_string(), "()");
+}
@@ -1,18 +1,29 @@
 mod ai;
 mod executor;
+mod interpreter;
 mod mode;
 mod mode_infer;
+pub mod trace;
 
 use crate::formula::hes::{GoalBase, GoalKind, Problem, ProblemBase};
-use crate::formula::{Bot, Constraint, Fv, Ident, Logic, Op, PredKind, Type as HFLType};
-use crate::ml::{optimize, Expr, Function, Program, Range, Type as SType, Variable};
+use crate::formula::{
+    Bot, Constraint, Fv, Ident, Logic, Op, PredKind, Type as HFLType, Variable as HFLVariable,
+};
+use crate::ml::{
+    optimize, Expr, Function, Program, Range, Schedule, ScheduleError, Type as SType, Variable,
+};
 use crate::preprocess::Context;
+use crate::solver::SolverRegistry;
 use crate::stat::check::stat;
+use crate::stat::Stat;
 use crate::util::Pretty;
 pub use executor::ExecResult;
+pub use interpreter::ArithError;
 use mode::{Mode, ModeEnv};
 
 use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 // trace functions
 const T_MK_APP: &str = "mk_app";
@@ -21,6 +32,8 @@ const T_MK_DISJ: &str = "mk_disj";
 const T_MK_UNIV: &str = "mk_univ";
 const T_MK_EMPTY_TRACE: &str = "mk_empty_trace";
 const T_PRINT_TRACE: &str = "print_trace";
+const F_EXISTS: &str = "hopdr_exists";
+const F_EXISTS_TRACE: &str = "hopdr_exists_trace";
 
 const TRACE_CONJ_LEFT: i64 = 0;
 const TRACE_CONJ_RIGHT: i64 = 1;
@@ -45,6 +58,15 @@ fn mk_univ_trace(v: Expr, cur: Expr) -> Expr {
     Expr::mk_call_named_fun(T_MK_UNIV, values)
 }
 
+/// How the translated program is run
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    /// compiled with `ocamlopt` and run by `hopdr-check-runner`
+    OCaml,
+    /// run by the interpreter in this process (see `checker::interpreter`)
+    Interpreter,
+}
+
 #[derive(Clone)]
 pub struct Config {
     context: Context,
@@ -52,6 +74,15 @@ pub struct Config {
     no_mode_analysis: bool,
     track_trace: bool,
     print_stat: bool,
+    stat: Option<Stat>,
+    solvers: Option<SolverRegistry>,
+    backend: Backend,
+    validate_trace: bool,
+    shrink: bool,
+    seed: u64,
+    schedule: Schedule,
+    run_timeout: Option<Duration>,
+    deadline: Option<Instant>,
 }
 
 impl Config {
@@ -68,8 +99,107 @@ impl Config {
             no_mode_analysis,
             track_trace,
             print_stat,
+            stat: None,
+            solvers: None,
+            backend: Backend::OCaml,
+            validate_trace: false,
+            shrink: false,
+            seed: fresh_seed(),
+            schedule: Schedule::default(),
+            run_timeout: None,
+            deadline: None,
         }
     }
+
+    /// set the statistics that the run updates (by default, the current ones of the thread
+    /// polling it)
+    pub fn stat(mut self, stat: Stat) -> Self {
+        self.stat = Some(stat);
+        self
+    }
+
+    /// set the solvers used in the translation (by default, the current ones of the thread
+    /// polling it)
+    pub fn solvers(mut self, solvers: SolverRegistry) -> Self {
+        self.solvers = Some(solvers);
+        self
+    }
+
+    /// set how the translated program is run
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// set whether the trace of a counterexample is replayed on the problem (see
+    /// `trace::validate`). An Invalid result whose trace is not validated becomes Fail.
+    /// This has no effect unless the trace is tracked.
+    pub fn validate_trace(mut self, validate_trace: bool) -> Self {
+        self.validate_trace = validate_trace;
+        self
+    }
+
+    /// set whether the trace of a counterexample is shrunk: the program is re-run by the
+    /// interpreter with small integers and shallow recursion, and the smallest trace of
+    /// the failures is reported. This has no effect unless the trace is tracked.
+    pub fn shrink(mut self, shrink: bool) -> Self {
+        self.shrink = shrink;
+        self
+    }
+
+    /// set the seed of the random testing (by default, a fresh one for each config). The
+    /// same seed replays the same execution of the same problem on the same backend.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// set the budget of the random testing (see `ml::Schedule`)
+    ///
+    /// Fails if the program cannot run `schedule` (e.g. the range of integers exceeds
+    /// what `Random.int` takes).
+    pub fn schedule(mut self, schedule: Schedule) -> Result<Self, ScheduleError> {
+        schedule.validate()?;
+        self.schedule = schedule;
+        Ok(self)
+    }
+
+    /// set the wall time of a run of the program, after which the result is Unknown
+    /// (by default, the compiled program runs until it is stopped)
+    pub fn run_timeout(mut self, timeout: Duration) -> Self {
+        self.run_timeout = Some(timeout);
+        self
+    }
+
+    /// set the time after which the program is no longer run and the result is Unknown
+    /// (e.g. the time limit of the whole procedure)
+    pub fn deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// the wall time of the run that starts now, bounded by both `run_timeout` and
+    /// `deadline`
+    fn remaining_run_time(&self) -> Option<Duration> {
+        let rest = self
+            .deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()));
+        match (self.run_timeout, rest) {
+            (Some(timeout), Some(rest)) => Some(timeout.min(rest)),
+            (timeout, rest) => timeout.or(rest),
+        }
+    }
+
+    fn shrinks(&self) -> bool {
+        self.shrink && self.track_trace
+    }
+}
+
+/// a seed of the random testing that differs from run to run
+pub fn fresh_seed() -> u64 {
+    use std::collections::hash_map::RandomState;
+    use std::hash::{BuildHasher, Hasher};
+    RandomState::new().build_hasher().finish()
 }
 
 struct Translator {
@@ -365,6 +495,7 @@ impl<'a> Translator {
             | GoalKind::Conj(_, _)
             | GoalKind::Disj(_, _)
             | GoalKind::Univ(_, _)
+            | GoalKind::Exists(_, _)
             | GoalKind::ITE(_, _, _) => panic!("program error"),
         }
     }
@@ -422,6 +553,33 @@ impl<'a> Translator {
             e
         }
     }
+    /// Enumerates the candidate witnesses of `∃v. g` by calling `name (lb, ub) (fun v -> body)`.
+    ///
+    /// Returns None if the witness range is not bounded; in that case the
+    /// caller gives up this path by regarding it as true.
+    fn mk_exists(
+        &self,
+        name: &'static str,
+        v: &HFLVariable,
+        g: &GoalM,
+        body: Expr,
+    ) -> Option<Expr> {
+        assert!(v.ty.is_int() || v.ty.is_bit());
+        let mut range = ai::analyze_witness(v.id, g);
+        if v.ty.is_bit() {
+            range = range.meet(Range::boolean())
+        }
+        let (lb, ub) = match (range.lb, range.ub) {
+            (Some(lb), Some(ub)) => (lb, ub),
+            _ => return None,
+        };
+        let bounds = Expr::mk_tuple(vec![
+            Expr::mk_op(Op::mk_const(lb)),
+            Expr::mk_op(Op::mk_const(ub)),
+        ]);
+        let f = Expr::mk_fun(Variable::mk(v.id, SType::mk_type_int()), body);
+        Some(Expr::mk_call_named_fun(name, vec![bounds, f]))
+    }
     fn with_empty_trace(&self, e: Expr) -> Expr {
         if self.config.track_trace {
             Expr::mk_sequential(
@@ -459,6 +617,7 @@ impl<'a> Translator {
             | GoalKind::Conj(_, _)
             | GoalKind::Disj(_, _)
             | GoalKind::Univ(_, _)
+            | GoalKind::Exists(_, _)
             | GoalKind::ITE(_, _, _) => panic!("program error: {}", goal),
         }
     }
@@ -555,6 +714,12 @@ impl<'a> Translator {
                 let body = self.destruct_trace(body, |x| mk_univ_trace(Expr::mk_var(v.id), x));
                 Expr::mk_letrand(v.id, range, body)
             }
+            GoalKind::Exists(v, g) => {
+                let body = self.translate_goalm2(g);
+                let body = Expr::mk_app(body, Expr::mk_var(p));
+                self.mk_exists(F_EXISTS_TRACE, v, g, body)
+                    .unwrap_or_else(Expr::mk_raise)
+            }
             GoalKind::ITE(c, g1, g2) => {
                 let e1 = self.translate_goalm2(g1);
                 let e1 = Expr::mk_app(e1, Expr::mk_var(p));
@@ -640,6 +805,16 @@ impl<'a> Translator {
                         }
                     }
                 }
+                GoalKind::Exists(v, g) => {
+                    // mode inference guarantees that the body does not bind
+                    // any variable used in cont
+                    let body =
+                        Expr::mk_app(self.translate_goalm(g, Expr::mk_unit()), Expr::mk_var(p));
+                    match self.mk_exists(F_EXISTS, v, g, body) {
+                        Some(e) => Expr::mk_sequential(e, cont),
+                        None => Expr::mk_raise(),
+                    }
+                }
                 GoalKind::ITE(c, g1, g2) => {
                     let g1 = self.translate_goalm(g1, cont.clone());
                     let g2 = self.translate_goalm(g2, cont.clone());
@@ -689,6 +864,8 @@ impl<'a> Translator {
             functions,
             main,
             ctx: &self.config.context,
+            seed: self.config.seed,
+            schedule: self.config.schedule.clone(),
         }
     }
 }
@@ -798,6 +975,38 @@ fn gen_fml_for_test() -> GoalM {
     g8
 }
 
+#[test]
+fn test_config_schedule() {
+    use crate::ml::syntax::MAX_INT_RANGE;
+    let ctx = Context::empty();
+    let config = || Config::new(&ctx, false, false, false, false);
+    let schedule = |ranges: Vec<u32>| Schedule {
+        ranges,
+        ..Schedule::default()
+    };
+    assert!(config().schedule(schedule(vec![1, MAX_INT_RANGE])).is_ok());
+    // Random.int fails on 2^30
+    assert_eq!(
+        config()
+            .schedule(schedule(vec![1, MAX_INT_RANGE + 1]))
+            .err(),
+        Some(ScheduleError::IntRange(MAX_INT_RANGE + 1))
+    );
+    assert_eq!(
+        config().schedule(schedule(vec![0])).err(),
+        Some(ScheduleError::IntRange(0))
+    );
+    assert_eq!(
+        config().schedule(schedule(Vec::new())).err(),
+        Some(ScheduleError::Empty)
+    );
+    let s = Schedule {
+        retries: 0,
+        ..Schedule::default()
+    };
+    assert_eq!(config().schedule(s).err(), Some(ScheduleError::Retries));
+}
+
 #[test]
 fn test_translate_predicate() {
     let g8 = gen_fml_for_test();
@@ -908,6 +1117,95 @@ fn test_translate_predicate_trace() {
 }
 
 pub async fn run(problem: Problem<Constraint>, config: Config) -> executor::ExecResult {
+    match config.stat.clone() {
+        Some(s) => s.scope_async(run_in_scope(problem, config)).await,
+        None => run_in_scope(problem, config).await,
+    }
+}
+
+/// runs `f` with the solvers of `config`. Since the scope of the solvers is bound to the
+/// thread, it must not contain any `await`.
+fn with_solvers<T, F: FnOnce() -> T>(config: &Config, f: F) -> T {
+    match &config.solvers {
+        Some(solvers) => solvers.scope(f),
+        None => f(),
+    }
+}
+
+async fn run_in_scope(problem: Problem<Constraint>, config: Config) -> executor::ExecResult {
+    if config.schedule.is_empty() {
+        return ExecResult::Fail("the schedule of the random testing is empty".to_string());
+    }
+    let original = if config.validate_trace && config.track_trace {
+        Some(problem.clone())
+    } else {
+        None
+    };
+    let prog = with_solvers(&config, || translate_to_ml(problem, &config));
+    let run_timeout = config.remaining_run_time();
+    let (r, stats) = match (prog.source, &prog.machine) {
+        (Some(s), _) => stat("execute", || executor::executor(s, run_timeout)).await,
+        (None, Some(m)) => stat("execute", || interpreter::interpret(m.clone(), run_timeout)).await,
+        (None, None) => panic!("program error"),
+    };
+    match stats {
+        Some(stats) if config.print_stat => {
+            println!("[[Random Testing Stats]]");
+            println!("{}", stats);
+        }
+        _ => (),
+    }
+    let r = match (r, prog.machine) {
+        (ExecResult::Invalid(Some(s)), Some(m)) if config.shrinks() => {
+            match s.parse::<trace::Trace>() {
+                Ok(t) => {
+                    let t = stat("shrink", || interpreter::shrink(m, t)).await;
+                    info!("shrunk trace: {}", t);
+                    ExecResult::Invalid(Some(t.to_string()))
+                }
+                Err(e) => ExecResult::Fail(format!("failed to shrink {}: {}", s, e)),
+            }
+        }
+        (r, _) => r,
+    };
+    match (r, original) {
+        (ExecResult::Invalid(Some(s)), Some(problem)) => {
+            // the validation also asks the solvers whether the constraints on the trace hold
+            let validated = s.parse::<trace::Trace>().and_then(|t| {
+                stat("validate", || {
+                    with_solvers(&config, || trace::validate(&problem, &config.context, &t))
+                })
+            });
+            match validated {
+                Ok(()) => ExecResult::Invalid(Some(s)),
+                Err(e) => ExecResult::Fail(format!("the trace {} is not validated: {}", s, e)),
+            }
+        }
+        (r, _) => r,
+    }
+}
+
+/// the translated program in the forms that the run needs
+struct Translated {
+    /// the source run by `Backend::OCaml`
+    source: Option<String>,
+    /// the program run by `Backend::Interpreter`, which also shrinks counterexamples
+    machine: Option<Arc<interpreter::Machine>>,
+}
+
+/// translates the problem to the program for random testing
+fn translate_to_ml(problem: Problem<Constraint>, config: &Config) -> Translated {
+    // A refutation of the problem where least fixpoints are read as greatest ones is
+    // also a refutation of the original problem since μX.φ ≤ νX.φ, so ModeTrans can
+    // safely work on the greatest fixpoint reading. This is only an over-approximation:
+    // non-termination is not detected, so the violation of a termination or liveness
+    // property is never found.
+    let problem = if problem.has_least_fixpoint() {
+        info!("least fixpoint equations are checked as greatest ones");
+        problem.over_approximate_least_fixpoints()
+    } else {
+        problem
+    };
     if config.print_check_log {
         println!("translated nu hflz");
         println!("{}", problem.pretty_display_with_context(&config.context));
@@ -919,20 +1217,29 @@ pub async fn run(problem: Problem<Constraint>, config: Config) -> executor::Exec
     let prog = stat("translate", || trans.translate(problem_with_mode));
 
     let prog = stat("optimize", || optimize(prog));
-    let s = stat("dump_ml", || prog.dump_ml());
-    if config.print_check_log {
-        println!("(* Generated Program *)");
-        println!("{s}");
-    }
-    let (r, stats) = stat("execute", || executor::executor(s)).await;
-    match stats {
-        Some(stats) if config.print_stat => {
-            println!("[[Random Testing Stats]]");
-            println!("{}", stats);
+    let source = match config.backend {
+        Backend::OCaml => {
+            let s = stat("dump_ml", || prog.dump_ml());
+            if config.print_check_log {
+                println!("(* Generated Program *)");
+                println!("{s}");
+            }
+            Some(s)
         }
-        _ => (),
-    }
-    r
+        Backend::Interpreter => {
+            if config.print_check_log {
+                println!("(* Generated Program *)");
+                println!("{}", prog.dump_ml());
+            }
+            None
+        }
+    };
+    let machine = if config.backend == Backend::Interpreter || config.shrinks() {
+        Some(Arc::new(interpreter::Machine::new(&prog)))
+    } else {
+        None
+    };
+    Translated { source, machine }
 }
 
 /// This function is used to calculate the difficulty score of the problem.
@@ -7,7 +7,7 @@ use super::mode::*;
 use super::Aux;
 use super::DisjInfo;
 
-use crate::formula::hes::{ClauseBase, GoalBase, GoalKind, ProblemBase};
+use crate::formula::hes::{ClauseBase, Fixpoint, GoalBase, GoalKind, ProblemBase};
 use crate::formula::{Constraint, Fv, Ident, Logic, Op, OpExpr, PredKind, Subst, Top, Variable};
 
 use core::fmt;
@@ -23,6 +23,7 @@ struct Clause {
     head: Variable,
     mode: Mode,
     body: Goal,
+    fixpoint: Fixpoint,
 }
 struct Problem {
     clauses: Vec<Clause>,
@@ -68,6 +69,11 @@ fn translate_to_goal(goal: &GoalBase<Constraint, ()>, env: ModeEnv) -> Goal {
             let g = translate_to_goal(g, env.insert(x.id, mode.clone()));
             GoalBase::mk_univ_t(x.clone(), g, Aux::new_univ(env.clone(), mode))
         }
+        GoalKind::Exists(x, g) => {
+            let mode = Mode::from_hflty(&x.ty);
+            let g = translate_to_goal(g, env.insert(x.id, mode.clone()));
+            GoalBase::mk_exists_t(x.clone(), g, Aux::new_univ(env.clone(), mode))
+        }
         GoalKind::ITE(c, g1, g2) => {
             let g1 = translate_to_goal(g1, env.clone());
             let g2 = translate_to_goal(g2, env.clone());
@@ -77,10 +83,19 @@ fn translate_to_goal(goal: &GoalBase<Constraint, ()>, env: ModeEnv) -> Goal {
 }
 
 fn translate_to_clause(clause: ClauseBase<Constraint, ()>, env: ModeEnv) -> Clause {
-    let ClauseBase { head, body } = clause;
+    let ClauseBase {
+        head,
+        body,
+        fixpoint,
+    } = clause;
     let body = translate_to_goal(&body, env.clone());
     let mode = Mode::from_hflty(&head.ty);
-    Clause { head, mode, body }
+    Clause {
+        head,
+        mode,
+        body,
+        fixpoint,
+    }
 }
 
 /// translates the given problem to the intermediate representation for mode inference
@@ -107,6 +122,7 @@ fn output_problem(problem: Problem) -> Output {
         .map(|c| ClauseBase {
             head: c.head,
             body: c.body,
+            fixpoint: c.fixpoint,
         })
         .collect();
     ProblemBase { clauses, top }
@@ -578,6 +594,23 @@ fn gen_template_goal(
             debug!("univ: {}: {}", x.id, aux.introduced_mode.as_ref().unwrap());
             GoalBase::mk_univ_t(x.clone(), g, aux)
         }
+        GoalKind::Exists(x, g) => {
+            // the witness is enumerated by the checker, so it is always given,
+            // and the body must not produce any outer integer variable.
+            for y in g.fv() {
+                if y == x.id {
+                    continue;
+                }
+                match env.get(&y) {
+                    Some(m) if m.is_int() => constraints.push(ModeConstraint::mode_in(m)),
+                    _ => (),
+                }
+            }
+            let mode = Mode::from_hflty(&x.ty);
+            let g = gen_template_goal(g, env.insert(x.id, mode.clone()), constraints, coarse);
+            let aux = f(Mode::mk_prop()).introduced_mode(mode);
+            GoalBase::mk_exists_t(x.clone(), g, aux)
+        }
         GoalKind::ITE(c, g1, g2) => {
             let fv = c.fv();
             for x in fv {
@@ -637,6 +670,7 @@ fn gen_template_clause(
         head,
         body,
         mode: _,
+        fixpoint,
     } = c;
     let body = gen_template_goal(body, env, constraints, coarse);
     let m = &body.aux.mode;
@@ -645,6 +679,7 @@ fn gen_template_clause(
         head: head.clone(),
         body,
         mode,
+        fixpoint: *fixpoint,
     }
 }
 
@@ -811,6 +846,10 @@ fn apply_model_to_goal(g: &Goal, model: &HashMap<Ident, Mode>) -> Option<Goal> {
             let body = apply_model_to_goal(body, model)?;
             GoalBase::mk_univ_t(x.clone(), body, apply_model_to_aux(&g.aux, model))
         }
+        GoalKind::Exists(x, body) => {
+            let body = apply_model_to_goal(body, model)?;
+            GoalBase::mk_exists_t(x.clone(), body, apply_model_to_aux(&g.aux, model))
+        }
         GoalKind::ITE(c, g1, g2) => {
             let g1 = apply_model_to_goal(g1, model)?;
             let g2 = apply_model_to_goal(g2, model)?;
@@ -821,13 +860,19 @@ fn apply_model_to_goal(g: &Goal, model: &HashMap<Ident, Mode>) -> Option<Goal> {
 }
 
 fn apply_model_to_clause(c: &Clause, model: &HashMap<Ident, Mode>) -> Option<Clause> {
-    let Clause { head, body, mode } = c;
+    let Clause {
+        head,
+        body,
+        mode,
+        fixpoint,
+    } = c;
     let body = apply_model_to_goal(body, model)?;
     let mode = apply_model_to_mode(mode, model);
     Clause {
         head: head.clone(),
         body,
         mode,
+        fixpoint: *fixpoint,
     }
     .into()
 }
@@ -914,6 +959,7 @@ fn test_generate_template() {
             ),
         ),
         body: g,
+        fixpoint: Fixpoint::Greatest,
     };
 
     // top: ∀x. ∀y. x = 91 \/ y > 101 \/ P y x.
@@ -0,0 +1,615 @@
+//! Traces of the counterexamples found by the checker, and their validation.
+//!
+//! A trace is what `print_trace` of `library.ml` prints when the program is refuted with
+//! tracking enabled (`Config::new(.., track_trace, ..)`). It records how the formula is
+//! falsified: which conjunct (or branch of `if`) is false, the instance of each universal
+//! quantifier, and the integer arguments of each application of a named predicate.
+//! `validate` replays it on the problem given to the checker, so an Invalid answer can be
+//! confirmed without trusting the translation.
+//!
+//! The replay computes with the arithmetic of `library.ml` (see `interpreter::arith`).
+//! The instances of the quantifiers inside a constraint are not recorded, so such a
+//! constraint is decided by the SMT solver once the integers along the trace are
+//! substituted.
+use super::interpreter::{arith, compare, ArithError};
+use super::{ai, TRACE_CONJ_LEFT, TRACE_CONJ_RIGHT};
+use crate::formula::hes::{Goal, GoalKind, Problem};
+use crate::formula::{Constraint, ConstraintExpr, Fv, Ident, Op, OpExpr, Subst};
+use crate::ml::Range;
+use crate::preprocess::Context;
+use crate::solver::{smt, SolverResult};
+
+use rpds::HashTrieMap;
+use thiserror::Error;
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Trace {
+    /// the constraint is false
+    Empty,
+    /// the conjunct (0: left, 1: right) is false
+    Conj(i64, Box<Trace>),
+    /// both of the disjuncts are false
+    Disj(Box<Trace>, Box<Trace>),
+    /// the instance of the universal quantifier is false
+    Univ(i64, Box<Trace>),
+    /// the application of the predicate to the integer arguments is false
+    App(String, Vec<i64>, Box<Trace>),
+}
+
+impl Trace {
+    /// the number of the nodes
+    pub fn size(&self) -> usize {
+        match self {
+            Trace::Empty => 1,
+            Trace::Conj(_, t) | Trace::Univ(_, t) | Trace::App(_, _, t) => 1 + t.size(),
+            Trace::Disj(t1, t2) => 1 + t1.size() + t2.size(),
+        }
+    }
+
+    /// the sum of the absolute values of the integers in the trace
+    pub fn magnitude(&self) -> u64 {
+        match self {
+            Trace::Empty => 0,
+            // the choice of a conjunct is not an integer of the counterexample
+            Trace::Conj(_, t) => t.magnitude(),
+            Trace::Disj(t1, t2) => t1.magnitude().saturating_add(t2.magnitude()),
+            Trace::Univ(v, t) => v.unsigned_abs().saturating_add(t.magnitude()),
+            Trace::App(_, vs, t) => vs
+                .iter()
+                .fold(t.magnitude(), |m, v| m.saturating_add(v.unsigned_abs())),
+        }
+    }
+
+    /// compares the traces by the size, and then by the magnitude
+    pub fn is_smaller_than(&self, other: &Trace) -> bool {
+        (self.size(), self.magnitude()) < (other.size(), other.magnitude())
+    }
+}
+
+/// the format of `print_trace`
+impl fmt::Display for Trace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Trace::Empty => write!(f, "()"),
+            Trace::Conj(x, t) => write!(f, "(conj {} {})", x, t),
+            Trace::Disj(t1, t2) => write!(f, "(disj {} {})", t1, t2),
+            Trace::Univ(v, t) => write!(f, "(univ {} {})", v, t),
+            Trace::App(name, vs, t) => {
+                write!(f, "(app {} (", name)?;
+                for v in vs.iter() {
+                    write!(f, "{} ", v)?;
+                }
+                write!(f, ") {})", t)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum TraceError {
+    #[error("failed to parse the trace: {0}")]
+    Parse(String),
+    #[error("{0} holds along the trace")]
+    Holds(Constraint),
+    #[error("trace {trace} does not follow {goal}")]
+    Mismatch { trace: String, goal: String },
+    #[error("the witnesses of ∃{0} are not bounded")]
+    UnboundedWitness(Ident),
+    #[error("the trace of {0} is not recorded")]
+    Untracked(Ident),
+    #[error("{0} is not bound along the trace")]
+    Unbound(Ident),
+    #[error("{1} in {0} along the trace")]
+    Arith(Op, ArithError),
+    #[error("failed to decide {0} along the trace")]
+    Undecided(Constraint),
+}
+
+fn tokenize(s: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    for (i, c) in s.char_indices() {
+        if c == '(' || c == ')' || c.is_whitespace() {
+            if let Some(st) = start.take() {
+                tokens.push(&s[st..i]);
+            }
+            if !c.is_whitespace() {
+                tokens.push(&s[i..i + 1]);
+            }
+        } else if start.is_none() {
+            start = Some(i);
+        }
+    }
+    if let Some(st) = start {
+        tokens.push(&s[st..]);
+    }
+    tokens
+}
+
+struct Parser<'a> {
+    tokens: Vec<&'a str>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn next(&mut self) -> Result<&'a str, TraceError> {
+        let t = *self
+            .tokens
+            .get(self.pos)
+            .ok_or_else(|| TraceError::Parse("unexpected end".to_string()))?;
+        self.pos += 1;
+        Ok(t)
+    }
+    fn expect(&mut self, s: &str) -> Result<(), TraceError> {
+        let t = self.next()?;
+        if t == s {
+            Ok(())
+        } else {
+            Err(TraceError::Parse(format!(
+                "expected `{}`, found `{}`",
+                s, t
+            )))
+        }
+    }
+    fn int(&mut self) -> Result<i64, TraceError> {
+        let t = self.next()?;
+        t.parse()
+            .map_err(|_| TraceError::Parse(format!("expected an integer, found `{}`", t)))
+    }
+    fn trace(&mut self) -> Result<Trace, TraceError> {
+        self.expect("(")?;
+        let t = match self.next()? {
+            ")" => return Ok(Trace::Empty),
+            "conj" => {
+                let x = self.int()?;
+                Trace::Conj(x, Box::new(self.trace()?))
+            }
+            "disj" => {
+                let t1 = self.trace()?;
+                Trace::Disj(Box::new(t1), Box::new(self.trace()?))
+            }
+            "univ" => {
+                let v = self.int()?;
+                Trace::Univ(v, Box::new(self.trace()?))
+            }
+            "app" => {
+                let name = self.next()?.to_string();
+                self.expect("(")?;
+                let mut vs = Vec::new();
+                while self.tokens.get(self.pos) != Some(&")") {
+                    vs.push(self.int()?);
+                }
+                self.pos += 1;
+                Trace::App(name, vs, Box::new(self.trace()?))
+            }
+            t => return Err(TraceError::Parse(format!("unknown node `{}`", t))),
+        };
+        self.expect(")")?;
+        Ok(t)
+    }
+}
+
+impl FromStr for Trace {
+    type Err = TraceError;
+
+    fn from_str(s: &str) -> Result<Trace, TraceError> {
+        let mut p = Parser {
+            tokens: tokenize(s),
+            pos: 0,
+        };
+        let t = p.trace()?;
+        if p.pos < p.tokens.len() {
+            return Err(TraceError::Parse(format!(
+                "unexpected `{}`",
+                p.tokens[p.pos]
+            )));
+        }
+        Ok(t)
+    }
+}
+
+#[derive(Clone)]
+enum Value<'a> {
+    Int(i64),
+    /// the predicate given by the goal under the environment
+    Pred(&'a Goal<Constraint>, Env<'a>),
+}
+
+type Env<'a> = HashTrieMap<Ident, Value<'a>>;
+
+struct Replayer<'a> {
+    clauses: HashMap<Ident, &'a Goal<Constraint>>,
+    ctx: &'a Context,
+}
+
+impl<'a> Replayer<'a> {
+    fn mismatch(&self, t: &Trace, g: &Goal<Constraint>) -> TraceError {
+        TraceError::Mismatch {
+            trace: t.to_string(),
+            goal: g.to_string(),
+        }
+    }
+
+    fn lookup(&self, x: &Ident, env: &Env<'a>) -> Result<Value<'a>, TraceError> {
+        match env.get(x) {
+            Some(v) => Ok(v.clone()),
+            None => match self.clauses.get(x) {
+                Some(g) => Ok(Value::Pred(g, Env::new())),
+                None => Err(TraceError::Unbound(*x)),
+            },
+        }
+    }
+
+    fn lookup_int(&self, x: &Ident, env: &Env<'a>) -> Result<i64, TraceError> {
+        match self.lookup(x, env)? {
+            Value::Int(v) => Ok(v),
+            Value::Pred(_, _) => Err(TraceError::Unbound(*x)),
+        }
+    }
+
+    fn eval_op(&self, o: &Op, env: &Env<'a>) -> Result<i64, TraceError> {
+        match o.kind() {
+            OpExpr::Op(k, x, y) => {
+                let x = self.eval_op(x, env)?;
+                let y = self.eval_op(y, env)?;
+                arith(k, x, y).map_err(|e| TraceError::Arith(o.clone(), e))
+            }
+            OpExpr::Var(x) => self.lookup_int(x, env),
+            OpExpr::Const(c) => Ok(*c),
+            OpExpr::ITE(c, x, y) => {
+                if self.eval_constraint(c, env)? {
+                    self.eval_op(x, env)
+                } else {
+                    self.eval_op(y, env)
+                }
+            }
+            OpExpr::Ptr(_, o) => self.eval_op(o, env),
+        }
+    }
+
+    fn eval_constraint(&self, c: &Constraint, env: &Env<'a>) -> Result<bool, TraceError> {
+        match c.kind() {
+            ConstraintExpr::True => Ok(true),
+            ConstraintExpr::False => Ok(false),
+            ConstraintExpr::Pred(p, l) => match l.as_slice() {
+                [x, y] => {
+                    let x = self.eval_op(x, env)?;
+                    let y = self.eval_op(y, env)?;
+                    Ok(compare(p, x, y))
+                }
+                _ => Err(TraceError::Undecided(c.clone())),
+            },
+            ConstraintExpr::Conj(c1, c2) => {
+                Ok(self.eval_constraint(c1, env)? && self.eval_constraint(c2, env)?)
+            }
+            ConstraintExpr::Disj(c1, c2) => {
+                Ok(self.eval_constraint(c1, env)? || self.eval_constraint(c2, env)?)
+            }
+            ConstraintExpr::Quantifier(_, _, _) => self.decide(c, env),
+        }
+    }
+
+    /// decides the quantified constraint `c` by the SMT solver
+    fn decide(&self, c: &Constraint, env: &Env<'a>) -> Result<bool, TraceError> {
+        let mut closed = c.clone();
+        for x in c.fv() {
+            let v = self.lookup_int(&x, env)?;
+            closed = closed.subst(&x, &Op::mk_const(v));
+        }
+        match smt::default_solver().solve(&closed, &HashSet::new()) {
+            SolverResult::Sat => Ok(true),
+            SolverResult::Unsat => Ok(false),
+            SolverResult::Unknown | SolverResult::Timeout => Err(TraceError::Undecided(closed)),
+        }
+    }
+
+    fn eval_arg(&self, g: &'a Goal<Constraint>, env: &Env<'a>) -> Result<Value<'a>, TraceError> {
+        match g.kind() {
+            GoalKind::Op(o) => Ok(Value::Int(self.eval_op(o, env)?)),
+            GoalKind::Var(x) => self.lookup(x, env),
+            _ => Ok(Value::Pred(g, env.clone())),
+        }
+    }
+
+    /// checks that `g` applied to `args` is false along `t`
+    fn refute(
+        &self,
+        g: &'a Goal<Constraint>,
+        env: &Env<'a>,
+        mut args: Vec<Value<'a>>,
+        t: &Trace,
+    ) -> Result<(), TraceError> {
+        match (g.kind(), t) {
+            (GoalKind::Abs(v, g2), _) if !args.is_empty() => {
+                let env = env.insert(v.id, args.remove(0));
+                self.refute(g2, &env, args, t)
+            }
+            (GoalKind::App(_, _), _) => {
+                let mut head = g;
+                let mut spine = Vec::new();
+                while let GoalKind::App(g1, g2) = head.kind() {
+                    spine.push(self.eval_arg(g2, env)?);
+                    head = g1;
+                }
+                spine.reverse();
+                // applications of named predicates are recorded with their integer
+                // arguments (see `handle_app2`)
+                let name = match head.kind() {
+                    GoalKind::Var(x) => self.ctx.inverse_map.get(x),
+                    _ => None,
+                };
+                let t = match (name, t) {
+                    (None, _) => t,
+                    (Some(name), Trace::App(f, vs, t2)) if f == name => {
+                        let ints: Vec<_> = spine
+                            .iter()
+                            .filter_map(|v| match v {
+                                Value::Int(x) => Some(*x),
+                                Value::Pred(_, _) => None,
+                            })
+                            .collect();
+                        if &ints != vs {
+                            return Err(self.mismatch(t, g));
+                        }
+                        t2
+                    }
+                    (Some(_), _) => return Err(self.mismatch(t, g)),
+                };
+                spine.extend(args);
+                match head.kind() {
+                    GoalKind::Var(x) => match self.lookup(x, env)? {
+                        Value::Pred(g2, env2) => self.refute(g2, &env2, spine, t),
+                        Value::Int(_) => Err(self.mismatch(t, g)),
+                    },
+                    _ => self.refute(head, env, spine, t),
+                }
+            }
+            (GoalKind::Var(x), _) if !args.is_empty() => match self.lookup(x, env)? {
+                Value::Pred(g2, env2) => self.refute(g2, &env2, args, t),
+                Value::Int(_) => Err(self.mismatch(t, g)),
+            },
+            // the program calls `x ()`, whose trace is discarded
+            (GoalKind::Var(x), _) => Err(TraceError::Untracked(*x)),
+            (_, _) if !args.is_empty() => Err(self.mismatch(t, g)),
+            (GoalKind::Constr(c), Trace::Empty) => {
+                if self.eval_constraint(c, env)? {
+                    Err(TraceError::Holds(c.clone()))
+                } else {
+                    Ok(())
+                }
+            }
+            (GoalKind::Conj(g1, _), Trace::Conj(TRACE_CONJ_LEFT, t2)) => {
+                self.refute(g1, env, Vec::new(), t2)
+            }
+            (GoalKind::Conj(_, g2), Trace::Conj(TRACE_CONJ_RIGHT, t2)) => {
+                self.refute(g2, env, Vec::new(), t2)
+            }
+            (GoalKind::Disj(g1, g2), Trace::Disj(t1, t2)) => {
+                self.refute(g1, env, Vec::new(), t1)?;
+                self.refute(g2, env, Vec::new(), t2)
+            }
+            (GoalKind::Univ(v, g2), Trace::Univ(x, t2)) => {
+                self.refute(g2, &env.insert(v.id, Value::Int(*x)), Vec::new(), t2)
+            }
+            (GoalKind::Exists(v, g2), _) => {
+                // the same witnesses as the translation tries (see `mk_exists`)
+                let mut range = ai::analyze_witness(v.id, g2);
+                if v.ty.is_bit() {
+                    range = range.meet(Range::boolean())
+                }
+                let (lb, ub) = match (range.lb, range.ub) {
+                    (Some(lb), Some(ub)) => (lb, ub),
+                    _ => return Err(TraceError::UnboundedWitness(v.id)),
+                };
+                // (disj .. (disj (disj () t_lb) t_lb+1) .. t_ub-1); a run that does not try all
+                // of them gives up without a trace (`check_witnesses` of `library.ml`)
+                let mut ts = Vec::new();
+                let mut cur = t;
+                while let Trace::Disj(t1, t2) = cur {
+                    ts.push(t2);
+                    cur = t1;
+                }
+                if cur != &Trace::Empty || ts.len() as i64 != (ub - lb).max(0) {
+                    return Err(self.mismatch(t, g));
+                }
+                for (x, t2) in (lb..ub).zip(ts.into_iter().rev()) {
+                    self.refute(g2, &env.insert(v.id, Value::Int(x)), Vec::new(), t2)?;
+                }
+                Ok(())
+            }
+            (GoalKind::ITE(c, g1, g2), Trace::Conj(i, t2)) => {
+                match (self.eval_constraint(c, env)?, *i) {
+                    (true, TRACE_CONJ_LEFT) => self.refute(g1, env, Vec::new(), t2),
+                    (false, TRACE_CONJ_RIGHT) => self.refute(g2, env, Vec::new(), t2),
+                    _ => Err(self.mismatch(t, g)),
+                }
+            }
+            (_, _) => Err(self.mismatch(t, g)),
+        }
+    }
+}
+
+/// checks that the top formula of `problem` is false along `trace`
+pub fn validate(
+    problem: &Problem<Constraint>,
+    ctx: &Context,
+    trace: &Trace,
+) -> Result<(), TraceError> {
+    let replayer = Replayer {
+        clauses: problem
+            .clauses
+            .iter()
+            .map(|c| (c.head.id, &c.body))
+            .collect(),
+        ctx,
+    };
+    replayer.refute(&problem.top, &Env::new(), Vec::new(), trace)
+}
+
+#[test]
+fn test_parse_trace() {
+    let s = "(conj 0 (univ -3 (app F (1 2 ) (disj () (app G () ())))))";
+    let t: Trace = s.parse().unwrap();
+    let expected = Trace::Conj(
+        0,
+        Box::new(Trace::Univ(
+            -3,
+            Box::new(Trace::App(
+                "F".to_string(),
+                vec![1, 2],
+                Box::new(Trace::Disj(
+                    Box::new(Trace::Empty),
+                    Box::new(Trace::App(
+                        "G".to_string(),
+                        Vec::new(),
+                        Box::new(Trace::Empty),
+                    )),
+                )),
+            )),
+        )),
+    );
+    assert_eq!(t, expected);
+    assert_eq!(t.to_string(), s);
+    assert!("(conj 0 ()".parse::<Trace>().is_err());
+    assert!("(conj 0 ()))".parse::<Trace>().is_err());
+}
+
+#[test]
+fn test_validate_trace() {
+    use crate::formula::hes::{Clause, Fixpoint};
+    use crate::formula::{Logic, Type, Variable};
+    // F x =v x > 0 /\ ∀y. y < x \/ F (x - 1)
+    // top = ∀z. F z
+    let f = Ident::fresh();
+    let x = Ident::fresh();
+    let y = Ident::fresh();
+    let z = Ident::fresh();
+    let fty = Type::mk_type_arrow(Type::mk_type_int(), Type::mk_type_prop());
+    let xv = Variable::mk(x, Type::mk_type_int());
+    let app = Goal::mk_app(
+        Goal::mk_var(f),
+        Goal::mk_op(Op::mk_sub(Op::mk_var(x), Op::mk_const(1))),
+    );
+    let body = Goal::mk_conj(
+        Goal::mk_constr(Constraint::mk_gt(Op::mk_var(x), Op::mk_const(0))),
+        Goal::mk_univ(
+            Variable::mk(y, Type::mk_type_int()),
+            Goal::mk_disj(
+                Goal::mk_constr(Constraint::mk_lt(Op::mk_var(y), Op::mk_var(x))),
+                app,
+            ),
+        ),
+    );
+    let clause = Clause {
+        head: Variable::mk(f, fty),
+        body: Goal::mk_abs(xv, body),
+        fixpoint: Fixpoint::Greatest,
+    };
+    let top = Goal::mk_univ(
+        Variable::mk(z, Type::mk_type_int()),
+        Goal::mk_app(Goal::mk_var(f), Goal::mk_op(Op::mk_var(z))),
+    );
+    let problem = Problem {
+        clauses: vec![clause],
+        top,
+    };
+    let mut ctx = Context::empty();
+    ctx.inverse_map.insert(f, "F".to_string());
+
+    // z = 0: F 0 is false since 0 > 0 is false
+    let t: Trace = "(univ 0 (app F (0 ) (conj 0 ())))".parse().unwrap();
+    validate(&problem, &ctx, &t).unwrap();
+
+    // z = 1: 1 > 0 holds
+    let t: Trace = "(univ 1 (app F (1 ) (conj 0 ())))".parse().unwrap();
+    assert!(matches!(
+        validate(&problem, &ctx, &t),
+        Err(TraceError::Holds(_))
+    ));
+
+    // z = 1, y = 5: 5 < 1 is false, and F 0 is false
+    let t: Trace = "(univ 1 (app F (1 ) (conj 1 (univ 5 (disj () (app F (0 ) (conj 0 ())))))))"
+        .parse()
+        .unwrap();
+    validate(&problem, &ctx, &t).unwrap();
+
+    // the argument of F does not match
+    let t: Trace = "(univ 1 (app F (2 ) (conj 0 ())))".parse().unwrap();
+    assert!(matches!(
+        validate(&problem, &ctx, &t),
+        Err(TraceError::Mismatch { .. })
+    ));
+}
+
+#[test]
+fn test_validate_trace_arith() {
+    use crate::formula::{QuantifierKind, Type, Variable};
+    let z = Ident::fresh();
+    let zv = Variable::mk(z, Type::mk_type_int());
+    let problem_of = |c| Problem {
+        clauses: Vec::new(),
+        top: Goal::mk_univ(zv.clone(), Goal::mk_constr(c)),
+    };
+    let ctx = Context::empty();
+
+    // top = ∀z. z mod 3 != 2, where -1 mod 3 = 2 in the library
+    let c = Constraint::mk_neq(Op::mk_mod(Op::mk_var(z), Op::mk_const(3)), Op::mk_const(2));
+    let problem = problem_of(c);
+    let t: Trace = "(univ -1 ())".parse().unwrap();
+    validate(&problem, &ctx, &t).unwrap();
+    let t: Trace = "(univ -2 ())".parse().unwrap();
+    assert!(matches!(
+        validate(&problem, &ctx, &t),
+        Err(TraceError::Holds(_))
+    ));
+
+    // top = ∀z. 1 / z = 0
+    let c = Constraint::mk_eq(Op::mk_div(Op::mk_const(1), Op::mk_var(z)), Op::mk_const(0));
+    let problem = problem_of(c);
+    let t: Trace = "(univ 0 ())".parse().unwrap();
+    assert!(matches!(
+        validate(&problem, &ctx, &t),
+        Err(TraceError::Arith(_, ArithError::DivisionByZero))
+    ));
+
+    // F x =v ∀y. 2 * y != x
+    // top = ∀z. F z
+    let f = Ident::fresh();
+    let x = Ident::fresh();
+    let y = Ident::fresh();
+    let c = Constraint::mk_quantifier(
+        QuantifierKind::Universal,
+        Variable::mk(y, Type::mk_type_int()),
+        Constraint::mk_neq(Op::mk_mul(Op::mk_const(2), Op::mk_var(y)), Op::mk_var(x)),
+    );
+    let clause = crate::formula::hes::Clause {
+        head: Variable::mk(
+            f,
+            Type::mk_type_arrow(Type::mk_type_int(), Type::mk_type_prop()),
+        ),
+        body: Goal::mk_abs(Variable::mk(x, Type::mk_type_int()), Goal::mk_constr(c)),
+        fixpoint: crate::formula::hes::Fixpoint::Greatest,
+    };
+    let problem = Problem {
+        clauses: vec![clause],
+        top: Goal::mk_univ(
+            zv,
+            Goal::mk_app(Goal::mk_var(f), Goal::mk_op(Op::mk_var(z))),
+        ),
+    };
+    let mut ctx = Context::empty();
+    ctx.inverse_map.insert(f, "F".to_string());
+    // F 4 is false (y = 2)
+    let t: Trace = "(univ 4 (app F (4 ) ()))".parse().unwrap();
+    validate(&problem, &ctx, &t).unwrap();
+    // F 3 holds
+    let t: Trace = "(univ 3 (app F (3 ) ()))".parse().unwrap();
+    assert!(matches!(
+        validate(&problem, &ctx, &t),
+        Err(TraceError::Holds(_))
+    ));
+}
@@ -835,7 +835,7 @@ fn simplify_by_qe(fvs: &Vec<Variable>, constraint: &Constraint, predicates: &[At
         return constraint;
     }
     let ue = solver::qe::qe_solver(solver::SMTSolverType::UltimateEliminator);
-    let c = ue.solve(&constraint);
+    let c = solver::qe::native_solver_with_fallback(ue).solve(&constraint);
     remove_quantifier(&c)
 }
 
@@ -968,10 +968,7 @@ fn gen_clause(
     for x in args.into_iter().rev() {
         form = Goal::mk_abs(Variable::mk(x, Type::mk_type_int()), form);
     }
-    Clause {
-        head: Variable::mk(pred_name, ty),
-        body: form,
-    }
+    Clause::new(form, Variable::mk(pred_name, ty))
 }
 
 pub fn translate_to_hes(
@@ -4,7 +4,10 @@ use std::collections::HashMap;
 /// returns
 ///
 ///
-use super::{Constraint, Fv, Ident, Logic, Negation, Op, PredKind, Top};
+use super::{
+    Constraint, ConstraintExpr, Fv, Ident, Logic, Negation, Op, PredKind, QuantifierKind, Subst,
+    Top,
+};
 
 /// transform all the predicates to another constraint that only use `<`.
 fn transform_predicate(c: &Constraint) -> Constraint {
@@ -103,15 +106,46 @@ fn pred_to_vec(constr: &Constraint, m: &HashMap<Ident, usize>) -> Vec<Op> {
     z.normalize(&variables).unwrap()
 }
 
+/// Replaces each existentially quantified variable with a linear template over the
+/// universally quantified variables in its scope, whose coefficients are fresh free
+/// variables. The result implies `c` for any values of the coefficients.
+///
+/// e.g. ∀x. ∃y. x = y  ---> ∀x. x = c₀ + c₁x
+fn instantiate_existentials(c: &Constraint) -> Constraint {
+    fn go(c: &Constraint, univs: &mut Vec<Ident>) -> Constraint {
+        match c.kind() {
+            ConstraintExpr::True | ConstraintExpr::False | ConstraintExpr::Pred(_, _) => c.clone(),
+            ConstraintExpr::Conj(c1, c2) => Constraint::mk_conj(go(c1, univs), go(c2, univs)),
+            ConstraintExpr::Disj(c1, c2) => Constraint::mk_disj(go(c1, univs), go(c2, univs)),
+            ConstraintExpr::Quantifier(QuantifierKind::Universal, x, c) => {
+                univs.push(x.id);
+                let c = go(c, univs);
+                univs.pop();
+                Constraint::mk_quantifier(QuantifierKind::Universal, x.clone(), c)
+            }
+            ConstraintExpr::Quantifier(QuantifierKind::Existential, x, c) => {
+                let witness = univs.iter().fold(Op::mk_var(Ident::fresh()), |o, y| {
+                    Op::mk_add(o, Op::mk_mul(Op::mk_var(Ident::fresh()), Op::mk_var(*y)))
+                });
+                go(&c.subst(&x.id, &witness), univs)
+            }
+        }
+    }
+    go(c, &mut Vec::new())
+}
+
 /// returns a constraint that does not contain universal quantifiers
 /// ### Assumption:
 /// - c's free variables are considered to be bound by existential quantifiers
-/// - c only contains universal quantifiers
 /// - c only contains linear constraints
+///
+/// Existential quantifiers in c are instantiated by linear templates (see
+/// `instantiate_existentials`), so the result is only sufficient for them.
 pub fn farkas_transform(c: &Constraint) -> Constraint {
     // translates the constraint to ∧ θᵢ where θᵢ has the form ¬ (∧ eᵢ ≥ 0).
     // Note that eᵢ is a linear expression.
     debug!("farkas input: {c}");
+    let c = &instantiate_existentials(c);
 
     // 1. prenex normal form of c
     let mut fv = c.fv();
@@ -303,3 +337,17 @@ fn test_farkas_transform3() {
     let mut solver = crate::solver::smt::default_solver();
     assert!(solver.solve(&c2, &HashSet::new()).is_sat());
 }
+#[test]
+fn test_farkas_transform_exists() {
+    // ∀x. ∃y. y = x + 1
+    use crate::formula::*;
+    let x = Ident::fresh();
+    let y = Ident::fresh();
+    let c = Constraint::mk_eq(Op::mk_var(y), Op::mk_add(Op::mk_var(x), Op::mk_const(1)));
+    let c = Constraint::mk_univ_int(x, Constraint::mk_exists_int(y, c));
+    let c2 = farkas_transform(&c);
+    println!("{c2}");
+
+    let mut solver = crate::solver::smt::default_solver();
+    assert!(solver.solve(&c2, &HashSet::new()).is_sat());
+}
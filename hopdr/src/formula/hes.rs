@@ -5,12 +5,14 @@ use crate::formula::{
 };
 use crate::pdr::rtype::Refinement;
 use crate::util::{Pretty, P};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use std::fmt;
 
 use super::{fofml, Negation, Subst, TeXFormat};
 
+pub use crate::parse::Fixpoint;
+
 #[derive(Debug)]
 pub enum ConstKind {
     Int(i64),
@@ -55,6 +57,7 @@ pub enum GoalKind<C, T> {
     Conj(GoalBase<C, T>, GoalBase<C, T>),
     Disj(GoalBase<C, T>, GoalBase<C, T>),
     Univ(Variable, GoalBase<C, T>),
+    Exists(Variable, GoalBase<C, T>),
     ITE(C, GoalBase<C, T>, GoalBase<C, T>),
 }
 
@@ -123,6 +126,9 @@ impl<C: TeXFormat, T> TeXFormat for GoalBase<C, T> {
             GoalKind::Univ(x, g) => {
                 write!(f, "(\\forall {}. {})", TeXPrinter(x), TeXPrinter(g))
             }
+            GoalKind::Exists(x, g) => {
+                write!(f, "(\\exists {}. {})", TeXPrinter(x), TeXPrinter(g))
+            }
             GoalKind::ITE(x, g1, g2) => {
                 write!(
                     f,
@@ -180,6 +186,10 @@ impl<T: Clone> From<GoalBase<Constraint, T>> for Constraint {
                 let c = g.clone().into();
                 Constraint::mk_quantifier_int(super::QuantifierKind::Universal, x.id, c)
             }
+            GoalKind::Exists(x, g) => {
+                let c = g.clone().into();
+                Constraint::mk_quantifier_int(super::QuantifierKind::Existential, x.id, c)
+            }
             GoalKind::Op(_) | GoalKind::Var(_) | GoalKind::Abs(_, _) | GoalKind::App(_, _) => {
                 panic!("program error: {} cannot be translated to Constraint", g)
             }
@@ -203,6 +213,7 @@ impl<C, T> From<GoalBase<C, T>> for Op {
             | GoalKind::Conj(_, _)
             | GoalKind::Disj(_, _)
             | GoalKind::Univ(_, _)
+            | GoalKind::Exists(_, _)
             | GoalKind::ITE(_, _, _) => panic!("program error"),
         }
     }
@@ -221,6 +232,9 @@ impl<C, T: Default> GoalBase<C, T> {
     pub fn mk_univ(x: Variable, g: GoalBase<C, T>) -> GoalBase<C, T> {
         GoalBase::mk_univ_t(x, g, T::default())
     }
+    pub fn mk_exists(x: Variable, g: GoalBase<C, T>) -> GoalBase<C, T> {
+        GoalBase::mk_exists_t(x, g, T::default())
+    }
     pub fn mk_abs(x: Variable, g: GoalBase<C, T>) -> GoalBase<C, T> {
         GoalBase::mk_abs_t(x, g, T::default())
     }
@@ -244,7 +258,7 @@ impl<C, T: Default> GoalBase<C, T> {
             GoalKind::Conj(g1, g2) | GoalKind::Disj(g1, g2) | GoalKind::ITE(_, g1, g2) => {
                 g1.is_constr() && g2.is_constr()
             }
-            GoalKind::Univ(_, g) => g.is_constr(),
+            GoalKind::Univ(_, g) | GoalKind::Exists(_, g) => g.is_constr(),
         }
     }
 }
@@ -297,6 +311,12 @@ impl<C, T> GoalBase<C, T> {
             aux,
         }
     }
+    pub fn mk_exists_t(x: Variable, g: GoalBase<C, T>, aux: T) -> GoalBase<C, T> {
+        GoalBase {
+            ptr: P::new(GoalKind::Exists(x, g)),
+            aux,
+        }
+    }
     pub fn mk_abs_t(x: Variable, g: GoalBase<C, T>, aux: T) -> GoalBase<C, T> {
         GoalBase {
             ptr: P::new(GoalKind::Abs(x, g)),
@@ -333,6 +353,9 @@ impl<C, T> GoalBase<C, T> {
     pub fn is_univ(&self) -> bool {
         matches!(self.kind(), GoalKind::Univ(_, _))
     }
+    pub fn is_exists(&self) -> bool {
+        matches!(self.kind(), GoalKind::Exists(_, _))
+    }
     pub fn conj<'a>(&'a self) -> (&'a Self, &'a Self) {
         match self.kind() {
             GoalKind::Conj(g1, g2) => (g1, g2),
@@ -364,6 +387,12 @@ impl<C, T> GoalBase<C, T> {
             _ => panic!("the given expr is not univ"),
         }
     }
+    pub fn exists<'a>(&'a self) -> (&'a Variable, &'a Self) {
+        match self.kind() {
+            GoalKind::Exists(x, g) => (x, g),
+            _ => panic!("the given expr is not exists"),
+        }
+    }
     pub fn abs<'a>(&'a self) -> (&'a Variable, &'a Self) {
         match self.kind() {
             GoalKind::Abs(x, g) => (x, g),
@@ -421,6 +450,7 @@ impl<C, T> GoalBase<C, T> {
             | GoalKind::App(_, _)
             | GoalKind::Disj(_, _)
             | GoalKind::Univ(_, _)
+            | GoalKind::Exists(_, _)
             | GoalKind::ITE(_, _, _) => either::Left(g),
             GoalKind::Conj(g1, g2) => either::Right((g1, g2)),
         });
@@ -436,6 +466,7 @@ impl<C, T> GoalBase<C, T> {
             | GoalKind::App(_, _)
             | GoalKind::Conj(_, _)
             | GoalKind::Univ(_, _)
+            | GoalKind::Exists(_, _)
             | GoalKind::ITE(_, _, _) => either::Left(g),
             GoalKind::Disj(g1, g2) => either::Right((g1, g2)),
         });
@@ -468,7 +499,7 @@ impl<C: super::FormulaSize, T> super::FormulaSize for GoalBase<C, T> {
             GoalKind::App(g1, g2) => g1.formula_size() + g2.formula_size(),
             GoalKind::Conj(g1, g2) => g1.formula_size() + g2.formula_size(),
             GoalKind::Disj(g1, g2) => g1.formula_size() + g2.formula_size(),
-            GoalKind::Univ(_, g) => 1 + g.formula_size(),
+            GoalKind::Univ(_, g) | GoalKind::Exists(_, g) => 1 + g.formula_size(),
             GoalKind::ITE(_, g1, g2) => 1 + g1.formula_size() + g2.formula_size(),
         }
     }
@@ -506,7 +537,7 @@ impl<C: Bot + Top + Logic> Goal<C> {
             GoalKind::Constr(c) => c.is_true(),
             GoalKind::Conj(c1, c2) => c1.is_true() && c2.is_true(),
             GoalKind::Disj(c1, c2) => c1.is_true() || c2.is_true(),
-            GoalKind::Univ(_, c) => c.is_true(),
+            GoalKind::Univ(_, c) | GoalKind::Exists(_, c) => c.is_true(),
             _ => false,
         }
     }
@@ -518,7 +549,7 @@ impl<C: Bot + Top + Logic> Goal<C> {
             GoalKind::Constr(c) => c.is_false(),
             GoalKind::Conj(c1, c2) => c1.is_false() || c2.is_false(),
             GoalKind::Disj(c1, c2) => c1.is_false() && c2.is_false(),
-            GoalKind::Univ(_, c) => c.is_false(),
+            GoalKind::Univ(_, c) | GoalKind::Exists(_, c) => c.is_false(),
             _ => false,
         }
     }
@@ -559,6 +590,17 @@ impl<C: Bot + Top + Logic> Goal<C> {
             GoalBase::mk_univ(x, g)
         }
     }
+
+    /// This returns a formula that is equivalent to the existential quantification of `x` in `g`.
+    pub fn mk_exists_opt(x: Variable, g: Goal<C>) -> Goal<C> {
+        if g.is_true() {
+            GoalBase::mk_true()
+        } else if g.is_false() {
+            GoalBase::mk_false()
+        } else {
+            GoalBase::mk_exists(x, g)
+        }
+    }
     pub fn mk_ite_opt(c: C, g1: Self, g2: Self) -> Self {
         if c.is_true() {
             g1
@@ -587,7 +629,7 @@ impl<C: Fv<Id = Ident>, T> Fv for GoalBase<C, T> {
             GoalKind::Var(x) => {
                 fvs.insert(*x);
             }
-            GoalKind::Univ(x, g) | GoalKind::Abs(x, g) => {
+            GoalKind::Univ(x, g) | GoalKind::Exists(x, g) | GoalKind::Abs(x, g) => {
                 g.fv_with_vec(fvs);
                 fvs.remove(&x.id);
             }
@@ -629,6 +671,14 @@ impl<C: Rename, T: Clone> Rename for GoalBase<C, T> {
                 };
                 GoalBase::mk_univ_t(id.clone(), g, self.aux.clone())
             }
+            GoalKind::Exists(id, g) => {
+                let g = if &id.id != x {
+                    g.rename(x, y)
+                } else {
+                    g.clone()
+                };
+                GoalBase::mk_exists_t(id.clone(), g, self.aux.clone())
+            }
             GoalKind::App(g1, g2) => {
                 GoalBase::mk_app_t(g1.rename(x, y), g2.rename(x, y), self.aux.clone())
             }
@@ -684,6 +734,11 @@ impl<
                 let g = g.isubst(x, v);
                 Self::mk_univ_t(y.clone(), g, self.aux.clone())
             }
+            GoalKind::Exists(y, _) if &y.id == x => self.clone(),
+            GoalKind::Exists(y, g) => {
+                let g = g.isubst(x, v);
+                Self::mk_exists_t(y.clone(), g, self.aux.clone())
+            }
             GoalKind::ITE(c, g1, g2) => {
                 let c = c.subst(x, v);
                 let g1 = g1.isubst(x, v);
@@ -775,6 +830,22 @@ impl<C: Subst<Item = Op, Id = Ident> + Rename + Fv<Id = Ident> + Precedence + Pr
                         GoalBase::mk_univ_t(y.clone(), subst_inner(g, x, v, fv), target.aux.clone())
                     }
                 }
+                GoalKind::Exists(y, g) => {
+                    if y.id == x.id {
+                        target.clone()
+                    } else if fv.contains(&y.id) {
+                        let y2_ident = Ident::fresh();
+                        let y2 = Variable::mk(y2_ident, y.ty.clone());
+                        let g = g.rename(&y.id, &y2_ident);
+                        GoalBase::mk_exists_t(y2, subst_inner(&g, x, v, fv), target.aux.clone())
+                    } else {
+                        GoalBase::mk_exists_t(
+                            y.clone(),
+                            subst_inner(g, x, v, fv),
+                            target.aux.clone(),
+                        )
+                    }
+                }
                 GoalKind::ITE(c, g1, g2) => {
                     let g1 = subst_inner(g1, x, v, fv);
                     let g2 = subst_inner(g2, x, v, fv);
@@ -820,6 +891,11 @@ impl<C: Rename + Fv<Id = Ident> + Clone, T: Clone> GoalBase<C, T> {
                 let g = g.alpha_renaming();
                 GoalBase::mk_univ_t(v, g, self.aux.clone())
             }
+            GoalKind::Exists(v, g) => {
+                let (v, g) = aux(v, g);
+                let g = g.alpha_renaming();
+                GoalBase::mk_exists_t(v, g, self.aux.clone())
+            }
             GoalKind::App(g1, g2) => {
                 let g1 = g1.alpha_renaming();
                 let g2 = g2.alpha_renaming();
@@ -851,7 +927,8 @@ impl<C: Refinement> PartialEq for GoalKind<C, ()> {
             (GoalKind::Op(o), GoalKind::Op(o2)) => o == o2,
             (GoalKind::Var(x), GoalKind::Var(y)) => x == y,
             (GoalKind::Abs(v, g), GoalKind::Abs(v2, g2))
-            | (GoalKind::Univ(v, g), GoalKind::Univ(v2, g2)) => v == v2 && g == g2,
+            | (GoalKind::Univ(v, g), GoalKind::Univ(v2, g2))
+            | (GoalKind::Exists(v, g), GoalKind::Exists(v2, g2)) => v == v2 && g == g2,
             (GoalKind::App(x1, y1), GoalKind::App(x2, y2))
             | (GoalKind::Conj(x1, y1), GoalKind::Conj(x2, y2))
             | (GoalKind::Disj(x1, y1), GoalKind::Disj(x2, y2)) => x1 == x2 && y1 == y2,
@@ -872,7 +949,9 @@ impl<T, C: Precedence> Precedence for GoalBase<C, T> {
             GoalKind::Constr(c) => c.precedence(),
             GoalKind::Op(o) => o.precedence(),
             GoalKind::Var(_) => PrecedenceKind::Atom,
-            GoalKind::Univ(_, _) | GoalKind::Abs(_, _) => PrecedenceKind::Abs,
+            GoalKind::Univ(_, _) | GoalKind::Exists(_, _) | GoalKind::Abs(_, _) => {
+                PrecedenceKind::Abs
+            }
             GoalKind::App(_, _) => PrecedenceKind::App,
             GoalKind::Conj(_, _) => PrecedenceKind::Conj,
             GoalKind::Disj(_, _) => PrecedenceKind::Disj,
@@ -908,6 +987,10 @@ impl<C: Refinement> Goal<C> {
                 let g = g.reduce_inner();
                 GoalBase::mk_univ(x.clone(), g)
             }
+            GoalKind::Exists(x, g) => {
+                let g = g.reduce_inner();
+                GoalBase::mk_exists(x.clone(), g)
+            }
             GoalKind::Abs(x, g) => {
                 let g = g.reduce_inner();
                 GoalBase::mk_abs(x.clone(), g)
@@ -966,7 +1049,7 @@ impl Goal<Constraint> {
             GoalKind::Constr(c) => c.count_quantifier(),
             GoalKind::Op(_) | GoalKind::Var(_) => 0,
             GoalKind::Abs(_, g) => g.count_quantifier(),
-            GoalKind::Univ(_, g) => 1 + g.count_quantifier(),
+            GoalKind::Univ(_, g) | GoalKind::Exists(_, g) => 1 + g.count_quantifier(),
             GoalKind::App(g1, g2) | GoalKind::Conj(g1, g2) | GoalKind::Disj(g1, g2) => {
                 g1.count_quantifier() + g2.count_quantifier()
             }
@@ -1006,6 +1089,10 @@ impl Goal<Constraint> {
                 let g = g.simplify();
                 Goal::mk_univ_opt(v.clone(), g)
             }
+            GoalKind::Exists(v, g) => {
+                let g = g.simplify();
+                Goal::mk_exists_opt(v.clone(), g)
+            }
             GoalKind::ITE(c, g1, g2) => {
                 let c = c.simplify();
                 let g1 = g1.simplify();
@@ -1025,7 +1112,9 @@ impl<C: Refinement, T: Clone + Default> GoalBase<C, T> {
             | GoalKind::Var(_)
             | GoalKind::Abs(_, _)
             | GoalKind::App(_, _)
-            | GoalKind::Constr(_) => (Vec::new(), self.clone()),
+            | GoalKind::Constr(_)
+            // existential quantifiers cannot be pulled out together with universal ones
+            | GoalKind::Exists(_, _) => (Vec::new(), self.clone()),
             GoalKind::Conj(a1, a2) => {
                 let (mut v1, a1) = a1.prenex_normal_form_raw(env);
                 let (mut v2, a2) = a2.prenex_normal_form_raw(env);
@@ -1102,7 +1191,8 @@ impl<C: Refinement, T: Clone + Default> GoalBase<C, T> {
             | GoalKind::Var(_)
             | GoalKind::Abs(_, _)
             | GoalKind::App(_, _)
-            | GoalKind::Univ(_, _) => vec![self.clone()],
+            | GoalKind::Univ(_, _)
+            | GoalKind::Exists(_, _) => vec![self.clone()],
         }
     }
 }
@@ -1117,7 +1207,7 @@ impl<C, T> GoalBase<C, T> {
             | GoalKind::Conj(x, y)
             | GoalKind::Disj(x, y)
             | GoalKind::ITE(_, x, y) => std::cmp::max(x.order(), y.order()),
-            GoalKind::Univ(_, y) => y.order(),
+            GoalKind::Univ(_, y) | GoalKind::Exists(_, y) => y.order(),
         }
     }
     // returns ident of Abs(ident, x). If self is not Abs(_), abs_var panics.
@@ -1138,6 +1228,7 @@ impl<C, T> GoalBase<C, T> {
             | GoalKind::Conj(_, _)
             | GoalKind::Disj(_, _)
             | GoalKind::Univ(_, _)
+            | GoalKind::Exists(_, _)
             | GoalKind::ITE(_, _, _) => None,
         }
     }
@@ -1145,7 +1236,9 @@ impl<C, T> GoalBase<C, T> {
         match self.kind() {
             GoalKind::Constr(_) | GoalKind::Op(_) => 0,
             GoalKind::Var(_) => 1,
-            GoalKind::Abs(_, g) | GoalKind::Univ(_, g) => std::cmp::max(1, g.disjunctive_degree()),
+            GoalKind::Abs(_, g) | GoalKind::Univ(_, g) | GoalKind::Exists(_, g) => {
+                std::cmp::max(1, g.disjunctive_degree())
+            }
             GoalKind::App(g1, g2) | GoalKind::Conj(g1, g2) | GoalKind::ITE(_, g1, g2) => {
                 std::cmp::max(
                     1,
@@ -1184,6 +1277,9 @@ impl<C: Refinement, T: Clone> Into<Option<C>> for GoalBase<C, T> {
             GoalKind::Univ(v, g) if v.ty.is_int() => Into::<Option<C>>::into(g.clone())
                 .map(|c| C::mk_quantifier_int(super::QuantifierKind::Universal, v.id, c)),
             GoalKind::Univ(_, _) => None,
+            GoalKind::Exists(v, g) if v.ty.is_int() => Into::<Option<C>>::into(g.clone())
+                .map(|c| C::mk_quantifier_int(super::QuantifierKind::Existential, v.id, c)),
+            GoalKind::Exists(_, _) => None,
             GoalKind::ITE(c, g1, g2) => {
                 let c = c.clone();
                 Into::<Option<C>>::into(g1.clone()).and_then(|c1| {
@@ -1198,7 +1294,7 @@ impl<C: Refinement, T: Clone> Into<Option<C>> for GoalBase<C, T> {
 pub struct ClauseBase<C, Aux> {
     pub body: GoalBase<C, Aux>,
     pub head: Variable,
-    // pub fixpoint: Fixpoint
+    pub fixpoint: Fixpoint,
 }
 
 pub type Clause<C> = ClauseBase<C, ()>;
@@ -1208,13 +1304,14 @@ impl From<Clause<Constraint>> for Clause<fofml::Atom> {
         Clause {
             body: c.body.into(),
             head: c.head,
+            fixpoint: c.fixpoint,
         }
     }
 }
 
 impl PartialEq for Clause<Constraint> {
     fn eq(&self, other: &Self) -> bool {
-        self.body == other.body && self.head == other.head
+        self.body == other.body && self.head == other.head && self.fixpoint == other.fixpoint
     }
 }
 
@@ -1235,17 +1332,30 @@ impl<C: Pretty + Precedence> fmt::Display for Clause<C> {
 impl<C: TeXFormat> TeXFormat for Clause<C> {
     fn tex_fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
         write!(f, "{} ", TeXPrinter(&self.head))?;
-        write!(f, "= {}", TeXPrinter(&self.body))
+        match self.fixpoint {
+            Fixpoint::Greatest => write!(f, "= {}", TeXPrinter(&self.body)),
+            Fixpoint::Least => write!(f, "=_\\mu {}", TeXPrinter(&self.body)),
+        }
     }
 }
 
 impl<C> Clause<C> {
     pub fn new(body: Goal<C>, head: Variable) -> Clause<C> {
-        Clause { body, head }
+        Clause::new_with_fixpoint(body, head, Fixpoint::Greatest)
+    }
+    pub fn new_with_fixpoint(body: Goal<C>, head: Variable, fixpoint: Fixpoint) -> Clause<C> {
+        Clause {
+            body,
+            head,
+            fixpoint,
+        }
     }
     pub fn new_top_clause(body: Goal<C>) -> Clause<C> {
         let head = Variable::fresh_prop();
-        Clause { body, head }
+        Clause::new(body, head)
+    }
+    pub fn is_least(&self) -> bool {
+        self.fixpoint == Fixpoint::Least
     }
     pub fn order(&self) -> usize {
         self.body.order()
@@ -1337,6 +1447,16 @@ impl<C: Rename + Bot + Top + Clone> Problem<C> {
                     Goal::mk_univ(y.clone(), self.eval(g))
                 }
             }
+            GoalKind::Exists(y, g) => {
+                if self.get_clause(&y.id).is_some() {
+                    let y2_ident = Ident::fresh();
+                    let y2 = Variable::mk(y2_ident, y.ty.clone());
+                    let g = g.rename(&y.id, &y2_ident);
+                    GoalBase::mk_exists(y2, self.eval(&g))
+                } else {
+                    Goal::mk_exists(y.clone(), self.eval(g))
+                }
+            }
             GoalKind::ITE(c, g1, g2) => {
                 let g1 = self.eval(g1);
                 let g2 = self.eval(g2);
@@ -1358,4 +1478,179 @@ impl<C> Problem<C> {
     pub fn get_clause<'a>(&'a self, id: &Ident) -> Option<&'a Clause<C>> {
         self.clauses.iter().find(|&c| c.head.id == *id)
     }
+
+    pub fn has_least_fixpoint(&self) -> bool {
+        self.clauses.iter().any(|c| c.is_least())
+    }
+
+    /// Reinterprets every least fixpoint equation as a greatest one.
+    ///
+    /// Since μX.φ ≤ νX.φ and the formula is monotone in each predicate, the result is
+    /// implied by the original problem: if it is invalid, so is the original one.
+    pub fn over_approximate_least_fixpoints(self) -> Problem<C> {
+        let clauses = self
+            .clauses
+            .into_iter()
+            .map(|c| Clause {
+                fixpoint: Fixpoint::Greatest,
+                ..c
+            })
+            .collect();
+        Problem {
+            clauses,
+            top: self.top,
+        }
+    }
+}
+
+impl<C: Refinement> Problem<C> {
+    /// returns the map from each predicate to the predicates occurring in its body
+    pub(crate) fn dependency_graph(&self) -> HashMap<Ident, HashSet<Ident>> {
+        let preds: HashSet<Ident> = self.clauses.iter().map(|c| c.head.id).collect();
+        self.clauses
+            .iter()
+            .map(|c| {
+                let deps = c.body.fv().intersection(&preds).cloned().collect();
+                (c.head.id, deps)
+            })
+            .collect()
+    }
+
+    /// returns the map from each predicate to the identifier of its strongly connected
+    /// component in the dependency graph (the smallest predicate in the component)
+    fn strongly_connected_components(&self) -> HashMap<Ident, Ident> {
+        let graph = self.dependency_graph();
+        let reachable_from = |start: Ident| {
+            let mut visited = HashSet::new();
+            let mut stack = vec![start];
+            while let Some(x) = stack.pop() {
+                for y in graph.get(&x).into_iter().flatten() {
+                    if visited.insert(*y) {
+                        stack.push(*y);
+                    }
+                }
+            }
+            visited
+        };
+        let reachability: HashMap<Ident, HashSet<Ident>> = self
+            .clauses
+            .iter()
+            .map(|c| (c.head.id, reachable_from(c.head.id)))
+            .collect();
+        let mut sccs = HashMap::new();
+        for c in self.clauses.iter() {
+            let x = c.head.id;
+            let mut repr = x;
+            for (y, ys) in reachability.iter() {
+                if reachability[&x].contains(y) && ys.contains(&x) && *y < repr {
+                    repr = *y;
+                }
+            }
+            sccs.insert(x, repr);
+        }
+        sccs
+    }
+
+    /// Replaces each strongly connected component that contains a least fixpoint
+    /// equation by its `depth`-th Kleene approximant from ⊥.
+    ///
+    /// Every solution of the original problem is a fixpoint of the equations in the
+    /// component, so the approximant is below it. Therefore, the validity of the result
+    /// implies the validity of the original problem. All the equations of the resulting
+    /// problem are greatest fixpoints, and the approximated components are not recursive.
+    pub fn under_approximate_least_fixpoints(&self, depth: usize) -> Problem<C> {
+        let sccs = self.strongly_connected_components();
+        let unrolled: HashSet<Ident> = self
+            .clauses
+            .iter()
+            .filter(|c| c.is_least())
+            .map(|c| sccs[&c.head.id])
+            .collect();
+        let is_unrolled = |x: &Ident| unrolled.contains(&sccs[x]);
+
+        // copies[x][i] is the name of the i-th approximant of x
+        let copies: HashMap<Ident, Vec<Ident>> = self
+            .clauses
+            .iter()
+            .filter(|c| is_unrolled(&c.head.id))
+            .map(|c| {
+                let names = (0..=depth).map(|_| Ident::fresh()).collect();
+                (c.head.id, names)
+            })
+            .collect();
+
+        fn rename_to_level<C: Refinement, F: Fn(&Ident) -> usize>(
+            g: &Goal<C>,
+            copies: &HashMap<Ident, Vec<Ident>>,
+            level: F,
+        ) -> Goal<C> {
+            let fvs = g.fv();
+            copies
+                .iter()
+                .filter(|(x, _)| fvs.contains(*x))
+                .fold(g.clone(), |g, (x, names)| g.rename(x, &names[level(x)]))
+        }
+
+        let mut clauses = Vec::new();
+        for c in self.clauses.iter() {
+            let x = c.head.id;
+            if !is_unrolled(&x) {
+                let body = rename_to_level(&c.body, &copies, |_| depth);
+                clauses.push(Clause::new(body, c.head.clone()));
+                continue;
+            }
+            let names = &copies[&x];
+            let bot = Goal::mk_ho_disj(&[], c.head.ty.clone());
+            clauses.push(Clause::new(bot, Variable::mk(names[0], c.head.ty.clone())));
+            for i in 1..=depth {
+                // references inside the same component go down to the previous level
+                let body = rename_to_level(&c.body, &copies, |y| {
+                    if sccs[y] == sccs[&x] {
+                        i - 1
+                    } else {
+                        depth
+                    }
+                });
+                clauses.push(Clause::new(body, Variable::mk(names[i], c.head.ty.clone())));
+            }
+        }
+        let top = rename_to_level(&self.top, &copies, |_| depth);
+        Problem { clauses, top }
+    }
+}
+
+#[test]
+fn test_under_approximate_least_fixpoints() {
+    // X =μ λx. X x, Y =ν λx. Y x ∧ X x, top = Y 0
+    let ty = Type::mk_type_arrow(Type::mk_type_int(), Type::mk_type_prop());
+    let x = Variable::mk(Ident::fresh(), ty.clone());
+    let y = Variable::mk(Ident::fresh(), ty.clone());
+    let arg = Variable::fresh_int();
+    let call = |p: &Variable| Goal::mk_app(Goal::mk_var(p.id), Goal::mk_var(arg.id));
+    let body_x = Goal::mk_abs(arg.clone(), call(&x));
+    let body_y = Goal::mk_abs(arg.clone(), Goal::mk_conj(call(&y), call(&x)));
+    let top: Goal<Constraint> = Goal::mk_app(Goal::mk_var(y.id), Goal::mk_op(Op::mk_const(0)));
+    let problem = Problem {
+        clauses: vec![
+            Clause::new_with_fixpoint(body_x, x.clone(), Fixpoint::Least),
+            Clause::new(body_y, y.clone()),
+        ],
+        top,
+    };
+    assert!(problem.has_least_fixpoint());
+
+    let depth = 3;
+    let approx = problem.under_approximate_least_fixpoints(depth);
+    assert!(!approx.has_least_fixpoint());
+    // X is replaced by its approximants X_0, ..., X_3, and Y is kept
+    assert_eq!(approx.clauses.len(), depth + 2);
+    assert!(approx.get_clause(&x.id).is_none());
+    let y_clause = approx.get_clause(&y.id).unwrap();
+    let fvs = y_clause.body.fv();
+    assert!(fvs.contains(&y.id));
+    assert!(!fvs.contains(&x.id));
+    // no approximant refers to itself
+    for c in approx.clauses.iter().filter(|c| c.head.id != y.id) {
+        assert!(!c.body.fv().contains(&c.head.id));
+    }
 }
@@ -9,6 +9,7 @@ use std::collections::{HashMap, HashSet};
 use std::fmt;
 
 use rpds::Stack;
+use serde::{Deserialize, Serialize};
 
 pub use crate::formula::ty::*;
 use crate::parse::ExprKind;
@@ -17,7 +18,7 @@ use crate::util::global_counter;
 use crate::util::Pretty;
 pub use crate::util::P;
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum PredKind {
     Eq,
     Neq,
@@ -45,7 +46,7 @@ impl PredKind {
         }
     }
 }
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum OpKind {
     Add,
     Sub,
@@ -60,7 +61,7 @@ impl fmt::Display for OpKind {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum QuantifierKind {
     Universal,
     Existential,
@@ -2073,7 +2074,9 @@ impl Constraint {
     pub fn simplify_with_smt(&self) -> Self {
         let c = self.simplify();
         let ue = solver::qe::qe_solver(solver::SMTSolverType::UltimateEliminator);
-        let c = ue.try_solve(&c).unwrap_or(c);
+        let c = solver::qe::native_solver_with_fallback(ue)
+            .try_solve(&c)
+            .unwrap_or(c);
         c
     }
 }
@@ -2402,7 +2405,16 @@ impl From<crate::parse::Expr> for Constraint {
                     }
                     c
                 }
-                ExprKind::Exist(_, _) => unimplemented!(),
+                ExprKind::Exist(x, e) => {
+                    let id = Ident::fresh();
+                    let old = env.insert(x, id);
+                    let c = Constraint::mk_exists_int(id, go(e, env));
+                    match old {
+                        Some(old) => assert!(env.insert(x, old).is_some()),
+                        None => (),
+                    }
+                    c
+                }
                 _ => panic!("fatal"),
             }
         }
@@ -56,6 +56,11 @@ impl Type {
     pub fn is_bit(&self) -> bool {
         matches!(self.kind(), TypeKind::Bit)
     }
+    /// int or bit (e.g. the disjunct selectors bound by `remove_disj`), whose variables
+    /// range over integers
+    pub fn is_integral(&self) -> bool {
+        self.is_int() || self.is_bit()
+    }
     pub fn is_prop(&self) -> bool {
         matches!(self.kind(), TypeKind::Proposition)
     }
@@ -2,12 +2,15 @@ extern crate lazy_static;
 #[macro_use]
 extern crate log;
 
+pub mod api;
 pub mod checker;
 pub mod formula;
 pub mod ml;
 pub mod parse;
 pub mod pdr;
+pub mod portfolio;
 pub mod preprocess;
+pub mod report;
 pub mod solver;
 pub mod stat;
 #[macro_use]
@@ -23,12 +26,15 @@ fn init() {
         .init();
 }
 
+#[derive(Clone)]
 pub struct Configuration {
     pub inlining: bool,
     pub remove_disjunction: bool,
     pub wait_every_step: bool,
     pub ultimate: bool,
     pub trace: bool,
+    /// backends, binaries and time limits of the solvers
+    pub solvers: solver::SolverRegistry,
 }
 
 impl Default for Configuration {
@@ -39,6 +45,7 @@ impl Default for Configuration {
             wait_every_step: false,
             ultimate: true,
             trace: false,
+            solvers: solver::SolverRegistry::default(),
         }
     }
 }
@@ -77,4 +84,16 @@ impl Configuration {
         self.trace = trace;
         self
     }
+
+    /// set the solvers used in the run
+    pub fn solvers(mut self, solvers: solver::SolverRegistry) -> Self {
+        self.solvers = solvers;
+        self
+    }
+
+    /// set time limits of external solvers
+    pub fn solver_timeouts(mut self, solver_timeouts: solver::SolverTimeouts) -> Self {
+        self.solvers.timeouts = solver_timeouts;
+        self
+    }
 }
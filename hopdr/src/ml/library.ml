@@ -69,21 +69,10 @@ let print_counter_stats () =
 let check_mx = ref 100000
 let check_mn = ref (-100000)
 
-let set_min () =
-  check_mx := 2;
-  check_mn := -1
-
-let set_small () =
-  check_mx := 6;
-  check_mn := -5
-
-let set_med () =
-  check_mx := 151;
-  check_mn := -150
-
-let set_large () =
-  check_mx := 100000;
-  check_mn := -100000
+(* the integers are drawn from [-r, r] *)
+let set_range r =
+  check_mx := r + 1;
+  check_mn := -r
 
 let n_recursion = ref 0
 let n_recursion_limit = ref 1000
@@ -114,6 +103,28 @@ let rand_int (x, y) =
   in
   Random.int (mx - mn) + mn
 
+(* existential quantifiers are angelic: every candidate witness is tried,
+   and one of them raising TrueExc makes the whole formula true.
+   When there are more candidates than the integers of the current range,
+   the epoch gives up by raising TrueExc, as trying only some of them may
+   refute a valid formula *)
+let check_witnesses (mn, mx) =
+  if mx - mn > !check_mx - !check_mn then raise TrueExc
+
+let hopdr_exists (mn, mx) f =
+  check_witnesses (mn, mx);
+  let rec go v =
+    if v < mx then (
+      f v;
+      go (v + 1))
+  in
+  go mn
+
+let hopdr_exists_trace (mn, mx) f =
+  check_witnesses (mn, mx);
+  let rec go v t = if v < mx then go (v + 1) (mk_disj t (f v)) else t in
+  go mn (mk_empty_trace ())
+
 let check_overflow f x y =
   try f x y with Invalid_argument _ -> raise IntegerOverflow
 
@@ -161,21 +172,20 @@ let loop f n =
     | TrueExc -> ()
   done
 
-let rec hopdr_main f fail =
-  let n_recs = [ 1000; 10000; 100000; 1000000; 10000000000 ] in
-  let configs = [ set_min; set_small; set_med; set_large ] in
+(* the schedule is given by the program (see `ml::Schedule`) *)
+let rec hopdr_main (n_recs, ranges, n_retries) f fail =
   try
     List.iter
       (fun n_rec ->
         List.iter
-          (fun config ->
+          (fun r ->
             set_n_recursion_limit n_rec;
-            config ();
-            loop f 1000)
-          configs)
+            set_range r;
+            loop f n_retries)
+          ranges)
       n_recs;
     print_counter_stats ();
-    if fail () then hopdr_main f fail
+    if fail () then hopdr_main (n_recs, ranges, n_retries) f fail
   with e ->
     print_counter_stats ();
     raise e
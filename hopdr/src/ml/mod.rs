@@ -5,7 +5,7 @@ pub mod ty;
 
 use crate::formula::Ident;
 pub use printer::FAIL_STRING;
-pub use syntax::{Expr, ExprKind, Function, Program, Range};
+pub use syntax::{Expr, ExprKind, Function, Program, Range, Schedule, ScheduleError};
 pub use ty::{Type, TypeKind};
 
 #[derive(Clone, Debug, PartialEq)]
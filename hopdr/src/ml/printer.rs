@@ -2,7 +2,7 @@
 ///
 /// Our algorithm highly depends on `ocamlformat` to pretty-print things.
 /// We only care about here whether we insert parentheses or not.
-use super::syntax::{Expr, ExprKind, Function, Program, Range};
+use super::syntax::{Expr, ExprKind, Function, Program, Range, Schedule};
 use crate::formula::{Constraint, Ident, Op, OpKind, Precedence, PrecedenceKind, PredKind};
 use crate::preprocess::Context;
 use crate::solver::util;
@@ -25,12 +25,15 @@ pub fn do_format(input: &str) -> String {
     // --enable-outside-detected-project is required for removing the warning
     let args = vec!["--impl", "--enable-outside-detected-project", "-"];
     debug!("filename: {}", &args[0]);
-    let out = util::exec_input_with_timeout(
-        "ocamlformat",
-        &args,
-        input.as_bytes(),
-        Duration::from_secs(1),
-    );
+    let timeout = Some(Duration::from_secs(1));
+    let out = match util::exec_input_with_timeout("ocamlformat", &args, input.as_bytes(), timeout) {
+        Ok(out) => out,
+        Err(util::ExecError::Timeout) => {
+            // formatting is only for readability
+            warn!("ocamlformat timed out; the program is printed as it is");
+            return input.to_string();
+        }
+    };
     let s = String::from_utf8(out).unwrap();
     debug!("result: {s}");
     s
@@ -46,6 +49,18 @@ fn test_do_format() {
     assert_eq!(r, &r2);
 }
 
+#[test]
+fn test_dump_schedule() {
+    let schedule = Schedule {
+        recursion_limits: vec![10, 100],
+        ranges: vec![1],
+        retries: 5,
+    };
+    let mut s = String::new();
+    schedule.dump_ml(&mut s, &Context::empty()).unwrap();
+    assert_eq!(s, "([10; 100], [1], 5)");
+}
+
 trait DumpML {
     fn dump_ml<W: Write>(&self, f: &mut W, ctx: &Context) -> Result<(), fmt::Error>;
 }
@@ -112,6 +127,25 @@ impl PredKind {
     }
 }
 
+/// the argument `(n_recs, ranges, n_retries)` of `hopdr_main`
+impl DumpML for Schedule {
+    fn dump_ml<W: Write>(&self, f: &mut W, _ctx: &Context) -> Result<(), fmt::Error> {
+        let list = |l: Vec<String>| format!("[{}]", l.join("; "));
+        write!(
+            f,
+            "({}, {}, {})",
+            list(
+                self.recursion_limits
+                    .iter()
+                    .map(|n| n.to_string())
+                    .collect()
+            ),
+            list(self.ranges.iter().map(|r| r.to_string()).collect()),
+            self.retries
+        )
+    }
+}
+
 impl DumpML for Constraint {
     fn dump_ml<W: Write>(&self, f: &mut W, ctx: &Context) -> Result<(), fmt::Error> {
         match self.kind() {
@@ -380,9 +414,21 @@ impl<'a> Program<'a> {
             FAIL_STRING
         )
     }
+    fn dump_seed<W: Write>(&self, f: &mut W) -> Result<(), fmt::Error> {
+        // OCaml's integers are 63-bit, so the seed is split into two halves
+        writeln!(
+            f,
+            "let () = Random.full_init [| {}; {} |]",
+            self.seed & 0xffff_ffff,
+            self.seed >> 32
+        )
+    }
     fn dump_main_ml<W: Write>(&self, f: &mut W) -> Result<(), fmt::Error> {
         self.dump_fail_func(f)?;
-        write!(f, "let () = hopdr_main (fun () -> ")?;
+        self.dump_seed(f)?;
+        write!(f, "let () = hopdr_main ")?;
+        self.schedule.dump_ml(f, &self.ctx)?;
+        write!(f, " (fun () -> ")?;
         self.main.dump_ml(f, &self.ctx)?;
         writeln!(f, ") hopdr_fail")
     }
@@ -4,6 +4,8 @@ use super::{Type as SType, Variable};
 use crate::formula::{Constraint, Fv, Ident, Op, Precedence, PrecedenceKind, Subst};
 use crate::util::P;
 
+use thiserror::Error;
+
 /// Represents a half-open interval [lb, ub).
 ///
 /// lb or ub can be None, which means there is no ends.
@@ -489,9 +491,98 @@ pub struct Function {
     pub body: Expr,
 }
 
+/// the budget of the random testing, which `hopdr_main` of `library.ml` follows
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    /// the bounds of the number of recursive calls in an epoch, tried in order
+    pub recursion_limits: Vec<u64>,
+    /// for each bound, the integers are drawn from [-r, r] for each r in order
+    pub ranges: Vec<u32>,
+    /// the number of epochs for each pair of a bound and a range
+    pub retries: usize,
+}
+
+/// the largest radius in `Schedule::ranges`: `Random.int` of OCaml takes bounds below 2^30,
+/// and [-r, r] has 2r + 1 integers
+pub const MAX_INT_RANGE: u32 = ((1 << 30) - 2) / 2;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ScheduleError {
+    #[error(
+        "the range of the random integers must be between 1 and {}: {0}",
+        MAX_INT_RANGE
+    )]
+    IntRange(u32),
+    #[error("the bound of the number of recursive calls must be positive")]
+    RecursionLimit,
+    #[error("the number of retries must be positive")]
+    Retries,
+    #[error("the schedule of the random testing is empty")]
+    Empty,
+}
+
+impl Schedule {
+    /// no epoch is run
+    pub fn is_empty(&self) -> bool {
+        self.recursion_limits.is_empty() || self.ranges.is_empty() || self.retries == 0
+    }
+
+    pub fn check_int_range(r: u32) -> Result<u32, ScheduleError> {
+        if r == 0 || MAX_INT_RANGE < r {
+            Err(ScheduleError::IntRange(r))
+        } else {
+            Ok(r)
+        }
+    }
+
+    pub fn check_recursion_limit(n_rec: u64) -> Result<u64, ScheduleError> {
+        if n_rec == 0 {
+            Err(ScheduleError::RecursionLimit)
+        } else {
+            Ok(n_rec)
+        }
+    }
+
+    pub fn check_retries(retries: usize) -> Result<usize, ScheduleError> {
+        if retries == 0 {
+            Err(ScheduleError::Retries)
+        } else {
+            Ok(retries)
+        }
+    }
+
+    /// checks that the program can run the schedule
+    pub fn validate(&self) -> Result<(), ScheduleError> {
+        if self.recursion_limits.is_empty() || self.ranges.is_empty() {
+            return Err(ScheduleError::Empty);
+        }
+        for r in self.ranges.iter() {
+            Self::check_int_range(*r)?;
+        }
+        for n_rec in self.recursion_limits.iter() {
+            Self::check_recursion_limit(*n_rec)?;
+        }
+        Self::check_retries(self.retries)?;
+        Ok(())
+    }
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Schedule {
+            recursion_limits: vec![1000, 10000, 100000, 1000000, 10000000000],
+            ranges: vec![1, 5, 150, 100000],
+            retries: 1000,
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Program<'a> {
     pub functions: Vec<Function>,
     pub main: Expr,
     pub ctx: &'a crate::preprocess::Context,
+    /// the seed of the random generator, with which the program runs the same way
+    pub seed: u64,
+    pub schedule: Schedule,
 }
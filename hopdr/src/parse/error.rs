@@ -0,0 +1,131 @@
+//! Errors of the HES parser with the location in the source.
+use super::hes::Problem;
+use super::parse::{parse, parse_hes, sp};
+use super::rtype::parse_type_environment;
+
+use nom::bytes::complete::tag;
+use nom::error::{VerboseError, VerboseErrorKind};
+use nom::sequence::preceded;
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// 1-origin
+    pub line: usize,
+    /// 1-origin, counted in characters
+    pub column: usize,
+    /// description of the token expected at the location
+    pub expected: String,
+    /// the line of the source where the error occurs
+    pub source_line: String,
+}
+
+impl ParseError {
+    fn new(input: &str, rest: &str, expected: String) -> ParseError {
+        let offset = input.len() - rest.len();
+        let before = &input[..offset];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let column = input[line_start..offset].chars().count() + 1;
+        let source_line = input[line_start..].lines().next().unwrap_or("").to_string();
+        ParseError {
+            line,
+            column,
+            expected,
+            source_line,
+        }
+    }
+
+    fn from_nom(input: &str, e: nom::Err<VerboseError<&str>>) -> ParseError {
+        let e = match e {
+            nom::Err::Error(e) | nom::Err::Failure(e) => e,
+            nom::Err::Incomplete(_) => return ParseError::new(input, "", "more input".to_string()),
+        };
+        // the first one is the innermost
+        let (rest, _) = match e.errors.first() {
+            Some(x) => x,
+            None => return ParseError::new(input, input, "%HES".to_string()),
+        };
+        // the most specific description at the location
+        let expected = e
+            .errors
+            .iter()
+            .filter(|(r, _)| r == rest)
+            .map(|(_, kind)| match kind {
+                VerboseErrorKind::Char(c) => format!("'{}'", c),
+                VerboseErrorKind::Context(s) => s.to_string(),
+                VerboseErrorKind::Nom(k) => k.description().to_lowercase(),
+            })
+            .next()
+            .unwrap();
+        ParseError::new(input, rest, expected)
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let line = self.line.to_string();
+        let margin = " ".repeat(line.len());
+        writeln!(
+            f,
+            "parse error at line {}, column {}: expected {}",
+            self.line, self.column, self.expected
+        )?;
+        writeln!(f, "{} |", margin)?;
+        writeln!(f, "{} | {}", line, self.source_line)?;
+        write!(f, "{} | {}^", margin, " ".repeat(self.column - 1))
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parses a whole νHFL(Z) validity checking problem.
+///
+/// Unlike `parse`, it fails if some input is left, which happens when an equation
+/// (or a type hint) is malformed.
+pub fn parse_problem(input: &str) -> Result<Problem, ParseError> {
+    let (rest, problem) =
+        parse::<VerboseError<&str>>(input).map_err(|e| ParseError::from_nom(input, e))?;
+    let (rest, _) = sp::<VerboseError<&str>>(rest).unwrap();
+    if rest.is_empty() {
+        return Ok(problem);
+    }
+    // parse the rest again to find why it has been left
+    let r = if rest.starts_with("%TYPES") {
+        preceded(tag("%TYPES"), parse_type_environment)(rest).map(|_| ())
+    } else {
+        parse_hes(rest).map(|_| ())
+    };
+    Err(match r {
+        Err(e) => ParseError::from_nom(input, e),
+        // e.g. an equation after %TYPES
+        Ok(()) => ParseError::new(input, rest, "end of input".to_string()),
+    })
+}
+
+#[test]
+fn test_parse_problem() {
+    let s = "%HES
+M =v ∀x. S x.
+S n =v (n > 0 || S (n - 1).
+";
+    let e = parse_problem(s).unwrap_err();
+    println!("{}", e);
+    assert_eq!(e.line, 3);
+
+    let s = "%HES
+M =v ∀x. S x.
+S n =v n > 0.
+T n := n > 0.
+";
+    let e = parse_problem(s).unwrap_err();
+    println!("{}", e);
+    assert_eq!(e.line, 4);
+
+    let s = "%HES
+M =v ∀x. S x.
+S n =v n > 0.
+";
+    assert!(parse_problem(s).is_ok());
+}
@@ -5,7 +5,7 @@ use std::fmt;
 
 type Ident = String;
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Fixpoint {
     Greatest,
     Least,
@@ -127,8 +127,8 @@ impl fmt::Display for Expr {
             ExprKind::False => write!(f, "false"),
             ExprKind::Fix(op, id, e) => write!(f, "{}{}. {}", op, id, e),
             ExprKind::Abs(id, e) => write!(f, "λ{}. {}", id, e),
-            ExprKind::Univ(id, e) => write!(f, "∀{}. {}", id, e),
-            ExprKind::Exist(id, e) => write!(f, "∃{}. {}", id, e),
+            ExprKind::Univ(id, e) => write!(f, "(∀{}. {})", id, e),
+            ExprKind::Exist(id, e) => write!(f, "(∃{}. {})", id, e),
         }
     }
 }
@@ -149,7 +149,7 @@ impl fmt::Display for Clause {
         for arg in self.args.iter() {
             write!(f, " {}", arg)?;
         }
-        write!(f, " = {}", self.expr)
+        write!(f, " ={} {}", self.fixpoint, self.expr)
     }
 }
 
@@ -157,6 +157,8 @@ impl fmt::Display for Clause {
 pub struct NuHFLzValidityChecking {
     pub formulas: Vec<Clause>,
     pub toplevel: Expr,
+    /// type hints given in the `%TYPES` section
+    pub types: Vec<super::TypeAssignment>,
 }
 
 #[derive(Debug)]
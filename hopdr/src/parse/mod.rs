@@ -1,9 +1,13 @@
 mod chc_parse;
+mod error;
 mod hes;
 mod parse;
+mod rtype;
 
 pub use chc_parse::*;
+pub use error::*;
 pub use hes::*;
 pub use parse::*;
+pub use rtype::*;
 
 pub type Ident = String;
@@ -1,4 +1,5 @@
 use super::hes::{Clause, Expr, Fixpoint, NuHFLzValidityChecking, Problem};
+use super::rtype::parse_type_environment;
 use crate::formula::{OpKind, PredKind};
 use nom::{
     branch::alt,
@@ -12,7 +13,7 @@ use nom::{
 };
 use std::str::FromStr;
 
-fn sp<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, &'a str, E> {
+pub(super) fn sp<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, &'a str, E> {
     let chars = " \t\r\n";
     take_while(move |c| chars.contains(c))(input)
 }
@@ -21,7 +22,7 @@ fn sp1<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, char, E>
     one_of(" \t\r\n")(input)
 }
 
-fn ident<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, String, E> {
+pub(super) fn ident<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, String, E> {
     let (input, (i1, i2)) = pair(
         alt((alpha1, tag("_"))),
         many0(alt((alphanumeric1, tag("_"), tag("!")))),
@@ -170,17 +171,38 @@ fn parse_forall<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str,
     Ok((input, Expr::mk_univ(id, e)))
 }
 
+fn parse_exists<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, Expr, E> {
+    let (input, _) = preceded(sp, char('∃'))(input)?;
+    let (input, id) = preceded(sp, map(ident, String::from))(input)?;
+    let (input, _) = preceded(sp, char('.'))(input)?;
+    let (input, e) = preceded(sp, parse_expr)(input)?;
+    Ok((input, Expr::mk_exist(id, e)))
+}
+
 pub fn parse_expr<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, Expr, E> {
-    alt((parse_lambda, parse_forall, parse_or, parse_var))(input)
+    alt((
+        parse_lambda,
+        parse_forall,
+        parse_exists,
+        parse_or,
+        parse_var,
+    ))(input)
+}
+
+/// `=v` (or `=ν`) for greatest fixpoints and `=u` (or `=μ`) for least fixpoints
+fn parse_fixpoint<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, Fixpoint, E> {
+    alt((
+        map(alt((tag("=v"), tag("=ν"))), |_| Fixpoint::Greatest),
+        map(alt((tag("=u"), tag("=μ"))), |_| Fixpoint::Least),
+    ))(input)
 }
 
 pub fn parse_hes<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, Clause, E> {
     let (input, id) = preceded(sp, map(ident, String::from))(input)?;
     let (input, args) = separated_list(sp1, preceded(sp, map(ident, String::from)))(input)?;
-    let (input, _) = preceded(sp, tag("=v"))(input)?;
+    let (input, fixpoint) = preceded(sp, parse_fixpoint)(input)?;
     let (input, expr) = preceded(sp, parse_expr)(input)?;
     let (input, _) = preceded(sp, char('.'))(input)?;
-    let fixpoint = Fixpoint::Greatest;
     Ok((
         input,
         Clause {
@@ -211,10 +233,28 @@ fn test_parse_hes_2() {
     assert_eq!(c.fixpoint, Fixpoint::Greatest);
 }
 #[test]
+fn test_parse_hes_least() {
+    use nom::error::VerboseError;
+    let s = "Term x =μ x <= 0 || Term (x - 1).";
+    let (s, c) = parse_hes::<VerboseError<&str>>(s).unwrap();
+    assert_eq!(s, "");
+    assert_eq!(c.args.len(), 1);
+    assert_eq!(c.fixpoint, Fixpoint::Least);
+
+    let s = "Term x =u x <= 0 || Term (x - 1).";
+    let (_, c) = parse_hes::<VerboseError<&str>>(s).unwrap();
+    assert_eq!(c.fixpoint, Fixpoint::Least);
+
+    let s = "Safe x =ν x >= 0 && Safe (x + 1).";
+    let (_, c) = parse_hes::<VerboseError<&str>>(s).unwrap();
+    assert_eq!(c.fixpoint, Fixpoint::Greatest);
+}
+#[test]
 fn test_parse_expr() {
     use nom::error::VerboseError;
     let table = vec![
         ("∀x.true", Expr::mk_univ("x".to_string(), Expr::mk_true())),
+        ("∃x.true", Expr::mk_exist("x".to_string(), Expr::mk_true())),
         ("\\x.true", Expr::mk_abs("x".to_string(), Expr::mk_true())),
         (
             "true || false && false",
@@ -261,9 +301,20 @@ pub fn parse<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, Pro
     })(input)?;
     let toplevel = toplevel.expr;
 
+    // type hints for HoPDR
+    let (input, types) = opt(preceded(
+        preceded(sp, tag("%TYPES")),
+        parse_type_environment,
+    ))(input)?;
+    let types = types.unwrap_or_default();
+
     Ok((
         input,
-        Problem::NuHFLZValidityChecking(NuHFLzValidityChecking { formulas, toplevel }),
+        Problem::NuHFLZValidityChecking(NuHFLzValidityChecking {
+            formulas,
+            toplevel,
+            types,
+        }),
     ))
 }
 #[test]
@@ -294,6 +345,28 @@ fn test_parse() {
     }
 }
 
+#[test]
+fn test_parse_types() {
+    use nom::error::VerboseError;
+    let (rest, f) = parse::<VerboseError<&str>>(
+        "
+        %HES
+        M =v ∀ x. S x (K x).
+        S n k =v (n > 0 || k 0) && (n <= 0 || S (n - 1) (L n k)).
+        K m n =v m <= n.
+        L n k m =v k (n + m).
+        %TYPES
+        K : m:int -> n:int -> *[m <= n].
+         ",
+    )
+    .unwrap();
+    assert_eq!(rest, "");
+    let Problem::NuHFLZValidityChecking(vc) = f;
+    assert_eq!(vc.formulas.len(), 3);
+    assert_eq!(vc.types.len(), 1);
+    assert_eq!(vc.types[0].id, "K");
+}
+
 #[test]
 fn test_edge_case() {
     let s = "
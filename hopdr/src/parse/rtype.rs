@@ -0,0 +1,223 @@
+//! Parser for refinement type environments (certificates).
+//!
+//! ```text
+//! env  := (X ':' τ '.')*
+//! τ    := x ':' 'int' '->' τ | arg '->' τ | '*[' φ ']' | '∀' x '.' τ
+//! arg  := 'T' | atom ('/\' atom)*
+//! atom := '(' τ ')' | '*[' φ ']'
+//! ```
+//! where `φ` is a constraint in the same syntax as the HES, `T` is the empty
+//! intersection, and `∀x. τ` is a type polymorphic on the integer variable `x`.
+use super::hes::Expr;
+use super::parse::{ident, parse_expr, sp};
+use crate::util::Unique;
+
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::char,
+    combinator::map,
+    error::ParseError,
+    multi::{many0, separated_nonempty_list},
+    sequence::preceded,
+    IResult,
+};
+
+use std::fmt;
+
+type Ident = String;
+
+#[derive(Debug, PartialEq)]
+pub enum RTypeKind {
+    Prop(Expr),
+    IArrow(Ident, RType),
+    Arrow(Vec<RType>, RType),
+    PTy(Ident, RType),
+}
+
+pub type RType = Unique<RTypeKind>;
+
+impl RType {
+    pub fn mk_prop(e: Expr) -> RType {
+        RType::new(RTypeKind::Prop(e))
+    }
+    pub fn mk_iarrow(x: Ident, t: RType) -> RType {
+        RType::new(RTypeKind::IArrow(x, t))
+    }
+    pub fn mk_arrow(ts: Vec<RType>, t: RType) -> RType {
+        RType::new(RTypeKind::Arrow(ts, t))
+    }
+    pub fn mk_poly_ty(x: Ident, t: RType) -> RType {
+        RType::new(RTypeKind::PTy(x, t))
+    }
+}
+
+impl fmt::Display for RType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind() {
+            RTypeKind::Prop(e) => write!(f, "*[{}]", e),
+            RTypeKind::IArrow(x, t) => write!(f, "{}:int -> {}", x, t),
+            RTypeKind::Arrow(ts, t) => {
+                if ts.is_empty() {
+                    write!(f, "T")?;
+                }
+                for (i, s) in ts.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " /\\ ")?;
+                    }
+                    write!(f, "({})", s)?;
+                }
+                write!(f, " -> {}", t)
+            }
+            RTypeKind::PTy(x, t) => write!(f, "∀{}. {}", x, t),
+        }
+    }
+}
+
+/// `id : ty.`
+#[derive(Debug, PartialEq)]
+pub struct TypeAssignment {
+    pub id: Ident,
+    pub ty: RType,
+}
+
+fn parse_prop<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, RType, E> {
+    let (input, _) = preceded(sp, tag("*["))(input)?;
+    let (input, e) = parse_expr(input)?;
+    let (input, _) = preceded(sp, char(']'))(input)?;
+    Ok((input, RType::mk_prop(e)))
+}
+
+fn parse_rtype_par<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, RType, E> {
+    let (input, _) = preceded(sp, char('('))(input)?;
+    let (input, t) = parse_rtype(input)?;
+    let (input, _) = preceded(sp, char(')'))(input)?;
+    Ok((input, t))
+}
+
+fn parse_rtype_atom<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, RType, E> {
+    alt((parse_rtype_par, parse_prop))(input)
+}
+
+fn parse_args<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, Vec<RType>, E> {
+    alt((
+        map(preceded(sp, char('T')), |_| Vec::new()),
+        separated_nonempty_list(preceded(sp, tag("/\\")), parse_rtype_atom),
+    ))(input)
+}
+
+fn parse_iarrow<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, RType, E> {
+    let (input, x) = preceded(sp, ident)(input)?;
+    let (input, _) = preceded(sp, char(':'))(input)?;
+    let (input, _) = preceded(sp, tag("int"))(input)?;
+    let (input, _) = preceded(sp, tag("->"))(input)?;
+    let (input, t) = parse_rtype(input)?;
+    Ok((input, RType::mk_iarrow(x, t)))
+}
+
+fn parse_arrow<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, RType, E> {
+    let (input, ts) = parse_args(input)?;
+    let (input, _) = preceded(sp, tag("->"))(input)?;
+    let (input, t) = parse_rtype(input)?;
+    Ok((input, RType::mk_arrow(ts, t)))
+}
+
+fn parse_poly_ty<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, RType, E> {
+    let (input, _) = preceded(sp, char('∀'))(input)?;
+    let (input, x) = preceded(sp, ident)(input)?;
+    let (input, _) = preceded(sp, char('.'))(input)?;
+    let (input, t) = parse_rtype(input)?;
+    Ok((input, RType::mk_poly_ty(x, t)))
+}
+
+pub fn parse_rtype<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, RType, E> {
+    alt((parse_poly_ty, parse_iarrow, parse_arrow, parse_prop))(input)
+}
+
+fn parse_type_assignment<'a, E: ParseError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, TypeAssignment, E> {
+    let (input, id) = preceded(sp, ident)(input)?;
+    let (input, _) = preceded(sp, char(':'))(input)?;
+    let (input, ty) = parse_rtype(input)?;
+    let (input, _) = preceded(sp, char('.'))(input)?;
+    Ok((input, TypeAssignment { id, ty }))
+}
+
+pub fn parse_type_environment<'a, E: ParseError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, Vec<TypeAssignment>, E> {
+    let (input, env) = many0(parse_type_assignment)(input)?;
+    let (input, _) = sp(input)?;
+    Ok((input, env))
+}
+
+#[test]
+fn test_parse_rtype() {
+    use crate::formula::PredKind;
+    use nom::error::VerboseError;
+    let var = |x: &str| Expr::mk_var(x.to_string());
+    let leq = Expr::mk_pred(PredKind::Leq, var("n"), var("m"));
+    let table = vec![
+        ("*[true]", RType::mk_prop(Expr::mk_true())),
+        (
+            "n:int -> m:int -> *[n <= m]",
+            RType::mk_iarrow(
+                "n".to_string(),
+                RType::mk_iarrow("m".to_string(), RType::mk_prop(leq.clone())),
+            ),
+        ),
+        (
+            "T -> *[false]",
+            RType::mk_arrow(Vec::new(), RType::mk_prop(Expr::mk_false())),
+        ),
+        (
+            "(m:int -> *[n <= m]) /\\ *[true] -> *[true]",
+            RType::mk_arrow(
+                vec![
+                    RType::mk_iarrow("m".to_string(), RType::mk_prop(leq.clone())),
+                    RType::mk_prop(Expr::mk_true()),
+                ],
+                RType::mk_prop(Expr::mk_true()),
+            ),
+        ),
+        (
+            "∀n. (m:int -> *[n <= m]) -> *[true]",
+            RType::mk_poly_ty(
+                "n".to_string(),
+                RType::mk_arrow(
+                    vec![RType::mk_iarrow(
+                        "m".to_string(),
+                        RType::mk_prop(leq.clone()),
+                    )],
+                    RType::mk_prop(Expr::mk_true()),
+                ),
+            ),
+        ),
+    ];
+    for (s, r) in table.into_iter() {
+        let (rest, t) = parse_rtype::<VerboseError<&str>>(s).unwrap();
+        assert_eq!(rest, "");
+        assert_eq!(r, t);
+        // printing is the inverse of parsing
+        let printed = t.to_string();
+        let (rest, t) = parse_rtype::<VerboseError<&str>>(&printed).unwrap();
+        assert_eq!(rest, "");
+        assert_eq!(r, t);
+    }
+}
+
+#[test]
+fn test_parse_type_environment() {
+    use nom::error::VerboseError;
+    let s = "
+        S : n:int -> (m:int -> *[n <= m]) -> *[true].
+        K : m:int -> n:int -> *[m <= n].
+        K : m:int -> n:int -> *[true].
+    ";
+    let (rest, env) = parse_type_environment::<VerboseError<&str>>(s).unwrap();
+    assert_eq!(rest, "");
+    assert_eq!(env.len(), 3);
+    assert_eq!(env[0].id, "S");
+    assert_eq!(env[2].id, "K");
+}
@@ -0,0 +1,262 @@
+//! JSON format of certificates.
+//!
+//! ```text
+//! {"version": 1, "types": [{"predicate": "X", "type": τ}, ...]}
+//! ```
+//! where types, constraints and integer expressions are objects tagged with `kind`,
+//! e.g. `{"kind": "iarrow", "var": "x0", "body": {"kind": "prop", "constraint": {"kind": "true"}}}`.
+use crate::formula::{OpKind, PredKind};
+use crate::parse::{Expr, ExprKind, RType, RTypeKind, TypeAssignment};
+
+use serde::{Deserialize, Serialize};
+
+const VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct Certificate {
+    version: u32,
+    types: Vec<Assignment>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Assignment {
+    predicate: String,
+    #[serde(rename = "type")]
+    ty: Type,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+enum Type {
+    Prop { constraint: Formula },
+    IArrow { var: String, body: Box<Type> },
+    Arrow { args: Vec<Type>, body: Box<Type> },
+    Poly { var: String, body: Box<Type> },
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+enum Formula {
+    True,
+    False,
+    Pred {
+        pred: String,
+        left: Term,
+        right: Term,
+    },
+    And {
+        left: Box<Formula>,
+        right: Box<Formula>,
+    },
+    Or {
+        left: Box<Formula>,
+        right: Box<Formula>,
+    },
+    Forall {
+        var: String,
+        body: Box<Formula>,
+    },
+    Exists {
+        var: String,
+        body: Box<Formula>,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+enum Term {
+    Var {
+        name: String,
+    },
+    Const {
+        value: i64,
+    },
+    Op {
+        op: String,
+        left: Box<Term>,
+        right: Box<Term>,
+    },
+}
+
+fn pred_kind(s: &str) -> Result<PredKind, String> {
+    let p = match s {
+        "=" => PredKind::Eq,
+        "!=" => PredKind::Neq,
+        "<" => PredKind::Lt,
+        "<=" => PredKind::Leq,
+        ">" => PredKind::Gt,
+        ">=" => PredKind::Geq,
+        _ => return Err(format!("unknown predicate symbol: {}", s)),
+    };
+    Ok(p)
+}
+
+fn op_kind(s: &str) -> Result<OpKind, String> {
+    let o = match s {
+        "+" => OpKind::Add,
+        "-" => OpKind::Sub,
+        "*" => OpKind::Mul,
+        "/" => OpKind::Div,
+        "%" => OpKind::Mod,
+        _ => return Err(format!("unknown operator: {}", s)),
+    };
+    Ok(o)
+}
+
+impl Term {
+    fn from_expr(e: &Expr) -> Term {
+        match e.kind() {
+            ExprKind::Var(x) => Term::Var { name: x.clone() },
+            ExprKind::Num(c) => Term::Const { value: *c },
+            ExprKind::Op(o, e1, e2) => Term::Op {
+                op: o.to_str().to_string(),
+                left: Box::new(Term::from_expr(e1)),
+                right: Box::new(Term::from_expr(e2)),
+            },
+            _ => panic!("program error: {} is not an integer expression", e),
+        }
+    }
+
+    fn to_expr(&self) -> Result<Expr, String> {
+        let e = match self {
+            Term::Var { name } => Expr::mk_var(name.clone()),
+            Term::Const { value } => Expr::mk_num(*value),
+            Term::Op { op, left, right } => {
+                Expr::mk_op(op_kind(op)?, left.to_expr()?, right.to_expr()?)
+            }
+        };
+        Ok(e)
+    }
+}
+
+impl Formula {
+    fn from_expr(e: &Expr) -> Formula {
+        let bin = |e1: &Expr, e2: &Expr| {
+            (
+                Box::new(Formula::from_expr(e1)),
+                Box::new(Formula::from_expr(e2)),
+            )
+        };
+        match e.kind() {
+            ExprKind::True => Formula::True,
+            ExprKind::False => Formula::False,
+            ExprKind::Pred(p, e1, e2) => Formula::Pred {
+                pred: p.to_str().to_string(),
+                left: Term::from_expr(e1),
+                right: Term::from_expr(e2),
+            },
+            ExprKind::And(e1, e2) => {
+                let (left, right) = bin(e1, e2);
+                Formula::And { left, right }
+            }
+            ExprKind::Or(e1, e2) => {
+                let (left, right) = bin(e1, e2);
+                Formula::Or { left, right }
+            }
+            ExprKind::Univ(x, e) => Formula::Forall {
+                var: x.clone(),
+                body: Box::new(Formula::from_expr(e)),
+            },
+            ExprKind::Exist(x, e) => Formula::Exists {
+                var: x.clone(),
+                body: Box::new(Formula::from_expr(e)),
+            },
+            _ => panic!("program error: {} is not a constraint", e),
+        }
+    }
+
+    fn to_expr(&self) -> Result<Expr, String> {
+        let e = match self {
+            Formula::True => Expr::mk_true(),
+            Formula::False => Expr::mk_false(),
+            Formula::Pred { pred, left, right } => {
+                Expr::mk_pred(pred_kind(pred)?, left.to_expr()?, right.to_expr()?)
+            }
+            Formula::And { left, right } => Expr::mk_and(left.to_expr()?, right.to_expr()?),
+            Formula::Or { left, right } => Expr::mk_or(left.to_expr()?, right.to_expr()?),
+            Formula::Forall { var, body } => Expr::mk_univ(var.clone(), body.to_expr()?),
+            Formula::Exists { var, body } => Expr::mk_exist(var.clone(), body.to_expr()?),
+        };
+        Ok(e)
+    }
+}
+
+impl Type {
+    fn from_rtype(t: &RType) -> Type {
+        match t.kind() {
+            RTypeKind::Prop(e) => Type::Prop {
+                constraint: Formula::from_expr(e),
+            },
+            RTypeKind::IArrow(x, t) => Type::IArrow {
+                var: x.clone(),
+                body: Box::new(Type::from_rtype(t)),
+            },
+            RTypeKind::Arrow(ts, t) => Type::Arrow {
+                args: ts.iter().map(Type::from_rtype).collect(),
+                body: Box::new(Type::from_rtype(t)),
+            },
+            RTypeKind::PTy(x, t) => Type::Poly {
+                var: x.clone(),
+                body: Box::new(Type::from_rtype(t)),
+            },
+        }
+    }
+
+    fn to_rtype(&self) -> Result<RType, String> {
+        let t = match self {
+            Type::Prop { constraint } => RType::mk_prop(constraint.to_expr()?),
+            Type::IArrow { var, body } => RType::mk_iarrow(var.clone(), body.to_rtype()?),
+            Type::Arrow { args, body } => {
+                let args = args.iter().map(Type::to_rtype).collect::<Result<_, _>>()?;
+                RType::mk_arrow(args, body.to_rtype()?)
+            }
+            Type::Poly { var, body } => RType::mk_poly_ty(var.clone(), body.to_rtype()?),
+        };
+        Ok(t)
+    }
+}
+
+pub(super) fn to_string(assignments: &[TypeAssignment]) -> String {
+    let types = assignments
+        .iter()
+        .map(|a| Assignment {
+            predicate: a.id.clone(),
+            ty: Type::from_rtype(&a.ty),
+        })
+        .collect();
+    let c = Certificate {
+        version: VERSION,
+        types,
+    };
+    serde_json::to_string_pretty(&c).unwrap()
+}
+
+pub(super) fn from_str(s: &str) -> Result<Vec<TypeAssignment>, String> {
+    let c: Certificate = serde_json::from_str(s).map_err(|e| e.to_string())?;
+    if c.version != VERSION {
+        return Err(format!("unsupported version: {}", c.version));
+    }
+    c.types
+        .iter()
+        .map(|a| {
+            Ok(TypeAssignment {
+                id: a.predicate.clone(),
+                ty: a.ty.to_rtype()?,
+            })
+        })
+        .collect()
+}
+
+#[test]
+fn test_json_roundtrip() {
+    use crate::parse::parse_type_environment;
+    use nom::error::VerboseError;
+    let s = "
+        S : ∀x0. x1:int -> (x2:int -> *[x1 <= x2 /\\ (∃x3. x3 = x2 + 1)]) -> *[true].
+        K : T -> *[false].
+    ";
+    let (_, env) = parse_type_environment::<VerboseError<&str>>(s).unwrap();
+    let json = to_string(&env);
+    let env2 = from_str(&json).unwrap();
+    assert_eq!(env, env2);
+}
@@ -0,0 +1,412 @@
+//! Independent checker of certificates (refinement type environments) for valid problems.
+//!
+//! A certificate Γ is accepted when Γ ⊢ D : Γ (each type is derivable from the body
+//! of its clause) and Γ ⊢ φ : *[true] for the toplevel formula φ.
+//!
+//! Certificates are stored either in the textual format of `parse::parse_type_environment`
+//! or in the JSON format of `json`. Both refer to predicates by their names in the source,
+//! and name the bound integer variables of each type x0, x1, ... in order, so that the
+//! output is stable across runs.
+mod json;
+
+use super::derivation;
+use super::rtype::{Tau, TauKind, Ty, TyEnv, TypeEnvironment};
+use crate::formula::hes::Problem;
+use crate::formula::{
+    expand_ite_constr_once, Bot, Constraint, ConstraintExpr, ExpandITEState, FirstOrderLogic,
+    Ident, Logic, Op, OpExpr, QuantifierKind, Top, Type as SType, TypeKind,
+};
+use crate::parse;
+use crate::preprocess::Context;
+
+use nom::error::VerboseError;
+use thiserror::Error;
+
+use std::collections::HashMap;
+
+#[derive(Debug, Error)]
+pub enum CertificateError {
+    #[error("failed to parse the certificate: {0}")]
+    Parse(String),
+    #[error("unknown predicate: {0}")]
+    UnknownPredicate(String),
+    #[error("unbound variable {1} in the type of {0}")]
+    UnboundVariable(String, String),
+    #[error("the type of {0} does not match its simple type {1}")]
+    IllFormed(String, SType),
+    #[error("problems with least fixpoint equations are not supported")]
+    LeastFixpoint,
+    #[error("{predicate}: {ty} is not derivable from the body of the clause")]
+    NotInductive { predicate: Ident, ty: Ty },
+    #[error("the certificate is not inductive")]
+    NotInductiveEnvironment,
+    #[error("the toplevel formula is not typable")]
+    TopLevel,
+}
+
+struct Converter<'a> {
+    /// name of the predicate whose type is being converted (for error messages)
+    name: &'a str,
+    env: HashMap<String, Ident>,
+}
+
+impl<'a> Converter<'a> {
+    fn unbound(&self, x: &str) -> CertificateError {
+        CertificateError::UnboundVariable(self.name.to_string(), x.to_string())
+    }
+
+    fn op(&self, e: &parse::Expr) -> Result<Op, CertificateError> {
+        use parse::ExprKind;
+        match e.kind() {
+            ExprKind::Var(x) => match self.env.get(x) {
+                Some(id) => Ok(Op::mk_var(*id)),
+                None => Err(self.unbound(x)),
+            },
+            ExprKind::Num(n) => Ok(Op::mk_const(*n)),
+            ExprKind::Op(o, x, y) => Ok(Op::mk_bin_op(*o, self.op(x)?, self.op(y)?)),
+            _ => Err(CertificateError::Parse(format!(
+                "{} is not an integer expression",
+                e
+            ))),
+        }
+    }
+
+    fn quantifier(
+        &mut self,
+        x: &str,
+        e: &parse::Expr,
+        f: fn(Ident, Constraint) -> Constraint,
+    ) -> Result<Constraint, CertificateError> {
+        let id = Ident::fresh();
+        let old = self.env.insert(x.to_string(), id);
+        let c = self.constraint(e);
+        match old {
+            Some(old) => self.env.insert(x.to_string(), old),
+            None => self.env.remove(x),
+        };
+        Ok(f(id, c?))
+    }
+
+    fn constraint(&mut self, e: &parse::Expr) -> Result<Constraint, CertificateError> {
+        use parse::ExprKind;
+        match e.kind() {
+            ExprKind::True => Ok(Constraint::mk_true()),
+            ExprKind::False => Ok(Constraint::mk_false()),
+            ExprKind::Pred(p, x, y) => Ok(Constraint::mk_pred(*p, vec![self.op(x)?, self.op(y)?])),
+            ExprKind::And(x, y) => Ok(Constraint::mk_conj(
+                self.constraint(x)?,
+                self.constraint(y)?,
+            )),
+            ExprKind::Or(x, y) => Ok(Constraint::mk_disj(
+                self.constraint(x)?,
+                self.constraint(y)?,
+            )),
+            ExprKind::Univ(x, e) => self.quantifier(x, e, Constraint::mk_univ_int),
+            ExprKind::Exist(x, e) => self.quantifier(x, e, Constraint::mk_exists_int),
+            _ => Err(CertificateError::Parse(format!(
+                "{} is not a constraint",
+                e
+            ))),
+        }
+    }
+
+    fn ill_formed(&self, sty: &SType) -> CertificateError {
+        CertificateError::IllFormed(self.name.to_string(), sty.clone())
+    }
+
+    fn rtype(&mut self, t: &parse::RType, sty: &SType) -> Result<Ty, CertificateError> {
+        use parse::RTypeKind;
+        match (t.kind(), sty.kind()) {
+            (RTypeKind::Prop(e), TypeKind::Proposition) => Ok(Tau::mk_prop_ty(self.constraint(e)?)),
+            (RTypeKind::IArrow(x, t), TypeKind::Arrow(s1, s2))
+                if matches!(s1.kind(), TypeKind::Integer | TypeKind::Bit) =>
+            {
+                let id = Ident::fresh();
+                let old = self.env.insert(x.clone(), id);
+                let t = self.rtype(t, s2);
+                match old {
+                    Some(old) => self.env.insert(x.clone(), old),
+                    None => self.env.remove(x),
+                };
+                Ok(Tau::mk_iarrow(id, t?))
+            }
+            (RTypeKind::Arrow(ts, t), TypeKind::Arrow(s1, s2)) if !s1.is_int() && !s1.is_bit() => {
+                let ts = ts
+                    .iter()
+                    .map(|t| self.rtype(t, s1))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let t = self.rtype(t, s2)?;
+                Ok(Tau::mk_arrow(ts, t))
+            }
+            (RTypeKind::PTy(x, t), _) => {
+                let id = Ident::fresh();
+                let old = self.env.insert(x.clone(), id);
+                let t = self.rtype(t, sty);
+                match old {
+                    Some(old) => self.env.insert(x.clone(), old),
+                    None => self.env.remove(x),
+                };
+                Ok(Tau::mk_poly_ty(id, t?))
+            }
+            _ => Err(self.ill_formed(sty)),
+        }
+    }
+}
+
+/// Converts type assignments of the parser to a type environment.
+///
+/// Predicates are resolved by their names in the source through `ctx`. Types of
+/// predicates eliminated by preprocessing are ignored.
+pub fn from_assignments(
+    assignments: &[parse::TypeAssignment],
+    ctx: &Context,
+    problem: &Problem<Constraint>,
+) -> Result<TyEnv, CertificateError> {
+    let mut env = TypeEnvironment::new();
+    for parse::TypeAssignment { id, ty } in assignments {
+        let pred = match ctx.ident_map.get(id) {
+            Some(pred) => *pred,
+            None => return Err(CertificateError::UnknownPredicate(id.clone())),
+        };
+        let clause = match problem.get_clause(&pred) {
+            Some(clause) => clause,
+            None => {
+                warn!("{} has been removed by preprocessing", id);
+                continue;
+            }
+        };
+        let mut converter = Converter {
+            name: id,
+            env: HashMap::new(),
+        };
+        let t = converter.rtype(ty, &clause.head.ty)?;
+        env.add(pred, t);
+    }
+    Ok(env)
+}
+
+/// Parses a certificate written in the format of `parse::parse_type_environment`.
+pub fn parse_certificate(
+    s: &str,
+    ctx: &Context,
+    problem: &Problem<Constraint>,
+) -> Result<TyEnv, CertificateError> {
+    let assignments = match parse::parse_type_environment::<VerboseError<&str>>(s) {
+        Ok(("", assignments)) => assignments,
+        Ok((rest, _)) => {
+            let rest: String = rest.chars().take(40).collect();
+            return Err(CertificateError::Parse(format!(
+                "unexpected input: {}",
+                rest
+            )));
+        }
+        Err(e) => return Err(CertificateError::Parse(format!("{:?}", e))),
+    };
+    from_assignments(&assignments, ctx, problem)
+}
+
+/// Parses a certificate written in the JSON format.
+pub fn parse_certificate_json(
+    s: &str,
+    ctx: &Context,
+    problem: &Problem<Constraint>,
+) -> Result<TyEnv, CertificateError> {
+    let assignments = json::from_str(s).map_err(|e| CertificateError::Parse(e.to_string()))?;
+    from_assignments(&assignments, ctx, problem)
+}
+
+/// Assigns x0, x1, ... to bound variables in the order of their binders.
+#[derive(Default)]
+struct Namer {
+    names: HashMap<Ident, String>,
+}
+
+impl Namer {
+    fn bind(&mut self, x: Ident) -> String {
+        let name = format!("x{}", self.names.len());
+        self.names.insert(x, name.clone());
+        name
+    }
+
+    fn name(&self, x: &Ident) -> String {
+        // free variables do not appear in certificates, but we print them anyway
+        self.names
+            .get(x)
+            .cloned()
+            .unwrap_or_else(|| format!("free_{}", x.get_id()))
+    }
+
+    fn op(&self, o: &Op) -> parse::Expr {
+        match o.kind() {
+            OpExpr::Op(k, o1, o2) => parse::Expr::mk_op(*k, self.op(o1), self.op(o2)),
+            OpExpr::Var(x) => parse::Expr::mk_var(self.name(x)),
+            OpExpr::Const(c) => parse::Expr::mk_num(*c),
+            OpExpr::Ptr(_, o) => self.op(o),
+            OpExpr::ITE(_, _, _) => panic!("program error: ite must be expanded"),
+        }
+    }
+
+    fn constraint_inner(&mut self, c: &Constraint) -> parse::Expr {
+        match c.kind() {
+            ConstraintExpr::True => parse::Expr::mk_true(),
+            ConstraintExpr::False => parse::Expr::mk_false(),
+            ConstraintExpr::Pred(p, l) => {
+                assert_eq!(l.len(), 2);
+                parse::Expr::mk_pred(*p, self.op(&l[0]), self.op(&l[1]))
+            }
+            ConstraintExpr::Conj(c1, c2) => {
+                parse::Expr::mk_and(self.constraint_inner(c1), self.constraint_inner(c2))
+            }
+            ConstraintExpr::Disj(c1, c2) => {
+                parse::Expr::mk_or(self.constraint_inner(c1), self.constraint_inner(c2))
+            }
+            ConstraintExpr::Quantifier(q, x, c) => {
+                let name = self.bind(x.id);
+                let e = self.constraint_inner(c);
+                match q {
+                    QuantifierKind::Universal => parse::Expr::mk_univ(name, e),
+                    QuantifierKind::Existential => parse::Expr::mk_exist(name, e),
+                }
+            }
+        }
+    }
+
+    fn constraint(&mut self, c: &Constraint) -> parse::Expr {
+        // the syntax has no conditional expressions
+        let mut c = c.clone();
+        loop {
+            match expand_ite_constr_once(&c) {
+                ExpandITEState::NotModified(c) => return self.constraint_inner(&c),
+                x => c = x.finalize_constraint(),
+            }
+        }
+    }
+
+    fn rtype(&mut self, t: &Ty) -> parse::RType {
+        match t.kind() {
+            TauKind::Proposition(c) => parse::RType::mk_prop(self.constraint(c)),
+            TauKind::IArrow(x, t) => {
+                let name = self.bind(*x);
+                parse::RType::mk_iarrow(name, self.rtype(t))
+            }
+            TauKind::Arrow(ts, t) => {
+                let ts = ts.iter().map(|t| self.rtype(t)).collect();
+                parse::RType::mk_arrow(ts, self.rtype(t))
+            }
+            TauKind::PTy(x, t) => {
+                let name = self.bind(*x);
+                parse::RType::mk_poly_ty(name, self.rtype(t))
+            }
+        }
+    }
+}
+
+/// Returns the type assignments of `certificate` sorted by the names of predicates.
+fn to_assignments(certificate: &TyEnv, ctx: &Context) -> Vec<parse::TypeAssignment> {
+    let mut assignments = Vec::new();
+    for (pred, ts) in certificate.map.iter() {
+        let id = match ctx.inverse_map.get(pred) {
+            Some(id) => id.clone(),
+            None => format!("P_{}", pred.get_id()),
+        };
+        for t in ts.iter() {
+            let ty = Namer::default().rtype(t);
+            assignments.push(parse::TypeAssignment { id: id.clone(), ty });
+        }
+    }
+    // sort_by is stable, so the order of intersection types is kept
+    assignments.sort_by(|x, y| x.id.cmp(&y.id));
+    assignments
+}
+
+/// Prints `certificate` in the format of `parse::parse_type_environment`.
+pub fn certificate_to_string(certificate: &TyEnv, ctx: &Context) -> String {
+    let mut s = String::new();
+    for parse::TypeAssignment { id, ty } in to_assignments(certificate, ctx) {
+        s += &format!("{} : {}.\n", id, ty);
+    }
+    s
+}
+
+/// Prints `certificate` in the JSON format.
+pub fn certificate_to_json(certificate: &TyEnv, ctx: &Context) -> String {
+    json::to_string(&to_assignments(certificate, ctx))
+}
+
+/// Checks that `certificate` is a valid refinement type environment for `problem`.
+///
+/// Each failure is reported with the predicate and the type that caused it.
+pub fn verify(problem: &Problem<Constraint>, certificate: &TyEnv) -> Result<(), CertificateError> {
+    if problem.has_least_fixpoint() {
+        return Err(CertificateError::LeastFixpoint);
+    }
+    if let Some(pred) = certificate
+        .map
+        .keys()
+        .find(|pred| problem.get_clause(pred).is_none())
+    {
+        return Err(CertificateError::UnknownPredicate(pred.to_string()));
+    }
+    if !derivation::check_inductive(certificate, problem) {
+        // find the type that is not derivable
+        for (pred, ts) in certificate.map.iter() {
+            for t in ts.iter() {
+                if !derivation::check_inductive_type(certificate, problem, pred, t) {
+                    return Err(CertificateError::NotInductive {
+                        predicate: *pred,
+                        ty: t.clone(),
+                    });
+                }
+            }
+        }
+        // each type is derivable on its own but not all of them together
+        return Err(CertificateError::NotInductiveEnvironment);
+    }
+    if !derivation::type_check_top(&problem.top, certificate) {
+        return Err(CertificateError::TopLevel);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_verify_certificate() {
+    let input = "
+        %HES
+        M =v ∀x. x < 0 || F x.
+        F x =v x >= 0 && F (x + 1).
+    ";
+    let f = parse::parse_problem(input).unwrap();
+    let (problem, ctx) = crate::preprocess::hes::preprocess_with_default_config(f);
+    let check = |s: &str| parse_certificate(s, &ctx, &problem).and_then(|c| verify(&problem, &c));
+
+    // valid
+    check("F : x0:int -> *[x0 >= 0].").unwrap();
+
+    // invalid
+    match check("F : x0:int -> *[true].") {
+        Err(CertificateError::NotInductive { .. }) => (),
+        r => panic!("unexpected result: {:?}", r),
+    }
+    match check("F : x0:int -> *[x0 >= 1].") {
+        Err(CertificateError::TopLevel) => (),
+        r => panic!("unexpected result: {:?}", r),
+    }
+
+    // malformed
+    match check("F : x0:int -> *[x0 >= 0]") {
+        Err(CertificateError::Parse(_)) => (),
+        r => panic!("unexpected result: {:?}", r),
+    }
+    match check("F : x0:int -> *[x1 >= 0].") {
+        Err(CertificateError::UnboundVariable(_, _)) => (),
+        r => panic!("unexpected result: {:?}", r),
+    }
+    match check("F : *[true].") {
+        Err(CertificateError::IllFormed(_, _)) => (),
+        r => panic!("unexpected result: {:?}", r),
+    }
+    match check("G : *[true].") {
+        Err(CertificateError::UnknownPredicate(_)) => (),
+        r => panic!("unexpected result: {:?}", r),
+    }
+}
@@ -0,0 +1,339 @@
+//! Refutation returned by HoPDR when the given problem is invalid.
+//!
+//! A counterexample is the chain of candidates (`HoPDR::models`) refuted at each
+//! approximation level: the candidate at step k + 1 is obtained by unfolding the
+//! predicate applications in the candidate at step k. The candidate of the last
+//! step is false at the approximation level 0 (where all predicates are ⊤), and
+//! the witnesses are the integers for its universally quantified variables that
+//! make it false.
+use crate::formula::hes::{Goal, GoalKind, Problem};
+use crate::formula::{
+    Bot, Constraint, FirstOrderLogic, Fv, Ident, Logic, Negation, Op, QuantifierKind, Subst,
+    Variable,
+};
+use crate::solver::{smt, Model, SolverResult};
+use crate::util::Pretty;
+
+use std::collections::HashSet;
+
+/// Predicate application `predicate args[0] ... args[n-1]` that is unfolded
+#[derive(Clone, Debug)]
+pub struct Application {
+    pub predicate: Ident,
+    pub args: Vec<Goal<Constraint>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Step {
+    /// index of the approximation under which `candidate` is not typable
+    pub level: usize,
+    pub candidate: Goal<Constraint>,
+    /// applications in `candidate` unfolded to obtain the next step
+    pub applications: Vec<Application>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Counterexample {
+    pub steps: Vec<Step>,
+    /// assignments to the universally quantified variables of the last candidate (except
+    /// those under existential quantifiers, whose values depend on the existential ones).
+    /// This is empty when the SMT solver fails to find them.
+    pub witnesses: Vec<(Variable, i64)>,
+}
+
+fn collect_applications(
+    problem: &Problem<Constraint>,
+    g: &Goal<Constraint>,
+    apps: &mut Vec<Application>,
+) {
+    match g.kind() {
+        GoalKind::Var(x) => match problem.get_clause(x) {
+            Some(c) if c.head.ty.is_prop() => apps.push(Application {
+                predicate: *x,
+                args: Vec::new(),
+            }),
+            _ => (),
+        },
+        GoalKind::App(_, _) => {
+            let mut args = Vec::new();
+            let mut head = g;
+            while let GoalKind::App(g1, g2) = head.kind() {
+                args.push(g2.clone());
+                head = g1;
+            }
+            args.reverse();
+            match head.kind() {
+                GoalKind::Var(x) if problem.get_clause(x).is_some() => {
+                    apps.push(Application {
+                        predicate: *x,
+                        args: args.clone(),
+                    });
+                }
+                _ => collect_applications(problem, head, apps),
+            }
+            for arg in args.iter() {
+                collect_applications(problem, arg, apps);
+            }
+        }
+        GoalKind::Constr(_) | GoalKind::Op(_) => (),
+        GoalKind::Conj(g1, g2) | GoalKind::Disj(g1, g2) | GoalKind::ITE(_, g1, g2) => {
+            collect_applications(problem, g1, apps);
+            collect_applications(problem, g2, apps);
+        }
+        GoalKind::Abs(_, g) | GoalKind::Univ(_, g) | GoalKind::Exists(_, g) => {
+            collect_applications(problem, g, apps)
+        }
+    }
+}
+
+/// Returns the condition on the universally quantified integer variables (collected to `univs`)
+/// under which `g` is false, assuming that all the predicates are ⊤.
+///
+/// Those under an existential quantifier may depend on its variable, so they are not
+/// collected but quantified in the condition (∃y. ¬g for ∀y. g).
+fn refutation_condition(g: &Goal<Constraint>, univs: &mut Vec<Variable>) -> Constraint {
+    fn go(g: &Goal<Constraint>, univs: &mut Vec<Variable>, under_exists: bool) -> Constraint {
+        match g.kind() {
+            GoalKind::Constr(c) => c.negate().unwrap(),
+            GoalKind::Var(_) | GoalKind::App(_, _) => Constraint::mk_false(),
+            GoalKind::Conj(g1, g2) => {
+                let c1 = go(g1, univs, under_exists);
+                let c2 = go(g2, univs, under_exists);
+                Constraint::mk_disj(c1, c2)
+            }
+            GoalKind::Disj(g1, g2) => {
+                let c1 = go(g1, univs, under_exists);
+                let c2 = go(g2, univs, under_exists);
+                Constraint::mk_conj(c1, c2)
+            }
+            GoalKind::Univ(x, g) if x.ty.is_int() && under_exists => {
+                let c = go(g, univs, under_exists);
+                Constraint::mk_quantifier_int(QuantifierKind::Existential, x.id, c)
+            }
+            GoalKind::Univ(x, g) => {
+                if x.ty.is_int() {
+                    univs.push(x.clone());
+                }
+                go(g, univs, under_exists)
+            }
+            GoalKind::Exists(x, g) => {
+                let c = go(g, univs, true);
+                if x.ty.is_integral() {
+                    Constraint::mk_quantifier_int(QuantifierKind::Universal, x.id, c)
+                } else {
+                    c
+                }
+            }
+            GoalKind::ITE(c, g1, g2) => {
+                let c1 = go(g1, univs, under_exists);
+                let c2 = go(g2, univs, under_exists);
+                Constraint::mk_disj(
+                    Constraint::mk_conj(c.clone(), c1),
+                    Constraint::mk_conj(c.negate().unwrap(), c2),
+                )
+            }
+            GoalKind::Abs(_, _) | GoalKind::Op(_) => panic!("program error: {}", g),
+        }
+    }
+    go(g, univs, false)
+}
+
+/// Reads the witnesses for `univs` from `model`.
+///
+/// Variables that `model` does not assign are irrelevant to `c` in the solver's view
+/// and set to 0. The assignment is checked again against `c` since the solver may
+/// have omitted them for another reason.
+fn witnesses_from_model(
+    c: &Constraint,
+    univs: Vec<Variable>,
+    model: &Model,
+) -> Option<Vec<(Variable, i64)>> {
+    let mut fvs = c.fv();
+    fvs.extend(univs.iter().map(|x| x.id));
+    let value = |x: &Ident| model.model.get(x).cloned().unwrap_or(0);
+    let closed = fvs
+        .iter()
+        .fold(c.clone(), |c, x| c.subst(x, &Op::mk_const(value(x))));
+    match smt::default_solver().solve(&closed, &HashSet::new()) {
+        SolverResult::Sat => (),
+        r => {
+            warn!(
+                "the witnesses of the counterexample do not refute it: {:?}",
+                r
+            );
+            return None;
+        }
+    }
+    Some(
+        univs
+            .into_iter()
+            .map(|x| {
+                let v = value(&x.id);
+                (x, v)
+            })
+            .collect(),
+    )
+}
+
+fn find_witnesses(g: &Goal<Constraint>) -> Vec<(Variable, i64)> {
+    let mut univs = Vec::new();
+    let c = refutation_condition(g, &mut univs);
+    let mut fvs = c.fv();
+    fvs.extend(univs.iter().map(|x| x.id));
+    let mut solver = smt::default_solver();
+    match solver.solve_with_model(&c, &HashSet::new(), &fvs) {
+        Ok(model) => witnesses_from_model(&c, univs, &model).unwrap_or_default(),
+        Err(r) => {
+            warn!("failed to find witnesses of the counterexample: {:?}", r);
+            Vec::new()
+        }
+    }
+}
+
+impl Counterexample {
+    /// `models` is the chain of candidates in the order they were found;
+    /// the first one is a clause of the toplevel formula.
+    pub(super) fn new(problem: &Problem<Constraint>, models: &[Goal<Constraint>]) -> Self {
+        let n = models.len();
+        let steps = models
+            .iter()
+            .enumerate()
+            .map(|(k, candidate)| {
+                let mut applications = Vec::new();
+                if k + 1 < n {
+                    collect_applications(problem, candidate, &mut applications);
+                }
+                Step {
+                    level: n - 1 - k,
+                    candidate: candidate.clone(),
+                    applications,
+                }
+            })
+            .collect();
+        let witnesses = models.last().map_or_else(Vec::new, find_witnesses);
+        Counterexample { steps, witnesses }
+    }
+}
+
+impl Pretty for Application {
+    fn pretty<'b, D, A>(
+        &'b self,
+        al: &'b D,
+        config: &mut crate::util::printer::Config,
+    ) -> pretty::DocBuilder<'b, D, A>
+    where
+        D: pretty::DocAllocator<'b, A>,
+        D::Doc: Clone,
+        A: Clone,
+    {
+        let head = self.predicate.pretty(al, config);
+        let args: Vec<_> = self
+            .args
+            .iter()
+            .map(|arg| al.space().append(arg.pretty(al, config).parens()))
+            .collect();
+        head.append(al.concat(args)).hang(2).group()
+    }
+}
+
+impl Pretty for Counterexample {
+    fn pretty<'b, D, A>(
+        &'b self,
+        al: &'b D,
+        config: &mut crate::util::printer::Config,
+    ) -> pretty::DocBuilder<'b, D, A>
+    where
+        D: pretty::DocAllocator<'b, A>,
+        D::Doc: Clone,
+        A: Clone,
+    {
+        let mut docs = Vec::new();
+        for step in self.steps.iter() {
+            let mut doc = al
+                .text(format!("[level {}]", step.level))
+                .append(al.line())
+                .append(step.candidate.pretty(al, config))
+                .hang(2)
+                .group();
+            if !step.applications.is_empty() {
+                let apps = step
+                    .applications
+                    .iter()
+                    .map(|app| al.text("unfold ").append(app.pretty(al, config)));
+                doc = doc.append(
+                    al.hardline()
+                        .append(al.intersperse(apps, al.hardline()))
+                        .nest(2),
+                );
+            }
+            docs.push(doc);
+        }
+        let witnesses = self
+            .witnesses
+            .iter()
+            .map(|(x, v)| x.id.pretty(al, config).append(al.text(format!(" = {}", v))));
+        let docs = docs.into_iter().chain(std::iter::once(
+            al.text("[witnesses]")
+                .append(al.line())
+                .append(al.intersperse(witnesses, al.text(",").append(al.line())))
+                .hang(2)
+                .group(),
+        ));
+        al.intersperse(docs, al.hardline())
+    }
+}
+
+#[test]
+fn test_witnesses_from_model() {
+    use crate::formula::PredKind;
+    use std::collections::HashMap;
+    // ∀x. ∀y. x > 0, where y does not occur in the body
+    let x = Variable::fresh_int();
+    let y = Variable::fresh_int();
+    let g = Goal::mk_constr(Constraint::mk_pred(
+        PredKind::Gt,
+        vec![Op::mk_var(x.id), Op::mk_const(0)],
+    ));
+    let g = Goal::mk_univ(x.clone(), Goal::mk_univ(y.clone(), g));
+    let mut univs = Vec::new();
+    let c = refutation_condition(&g, &mut univs);
+
+    // the model omits y
+    let model = Model {
+        model: HashMap::from([(x.id, -1)]),
+    };
+    let witnesses = witnesses_from_model(&c, univs.clone(), &model).unwrap();
+    assert_eq!(witnesses, vec![(x.clone(), -1), (y, 0)]);
+
+    // the model omits x, and x = 0 still refutes g
+    let model = Model {
+        model: HashMap::new(),
+    };
+    assert_eq!(
+        witnesses_from_model(&c, univs.clone(), &model).unwrap()[0].1,
+        0
+    );
+    // x = 1 does not refute g
+    let model = Model {
+        model: HashMap::from([(x.id, 1)]),
+    };
+    assert!(witnesses_from_model(&c, univs, &model).is_none());
+}
+
+#[test]
+fn test_refutation_condition_under_exists() {
+    use crate::formula::PredKind;
+    // ∃x. ∀y. x > y is false, and y (which depends on x) is not a witness
+    let x = Variable::fresh_int();
+    let y = Variable::fresh_int();
+    let g = Goal::mk_constr(Constraint::mk_pred(
+        PredKind::Gt,
+        vec![Op::mk_var(x.id), Op::mk_var(y.id)],
+    ));
+    let g = Goal::mk_exists(x, Goal::mk_univ(y, g));
+    let mut univs = Vec::new();
+    let c = refutation_condition(&g, &mut univs);
+    assert!(univs.is_empty());
+    assert!(c.fv().is_empty());
+    assert!(smt::default_solver().solve(&c, &HashSet::new()).is_sat());
+}
@@ -33,6 +33,7 @@ pub enum Rule {
     // of ty with the instantiations
     Var(Stack<Instantiation>, Ty),
     Univ,
+    Exists,
     IAbs,
     Abs(Vec<Ty>),
     IApp(Op),
@@ -49,6 +50,7 @@ impl std::fmt::Display for Rule {
             Rule::Disjoin => "Disj",
             Rule::Var(_, _) => "Var",
             Rule::Univ => "Univ",
+            Rule::Exists => "Exists",
             Rule::IAbs => "IAbs",
             Rule::Abs(_) => "Abs",
             Rule::IApp(_) => "IApp",
@@ -69,6 +71,7 @@ impl Subst for Rule {
             Rule::Conjoin
             | Rule::Disjoin
             | Rule::Univ
+            | Rule::Exists
             | Rule::IAbs
             | Rule::Subsumption
             | Rule::App
@@ -256,6 +259,23 @@ impl DeriveNode {
             ty,
         }
     }
+    fn exists(context: Stack<Atom>, expr: G, node: &Self, ident: &Ident) -> Self {
+        let rule = Rule::Exists;
+        let ty = match node.ty.kind() {
+            TauKind::Proposition(c1) => Ty::mk_prop_ty(Atom::mk_quantifier_int(
+                crate::formula::QuantifierKind::Existential,
+                *ident,
+                c1.clone(),
+            )),
+            TauKind::PTy(_, _) | TauKind::IArrow(_, _) | TauKind::Arrow(_, _) => panic!("fatal"),
+        };
+        DeriveNode {
+            context,
+            rule,
+            expr,
+            ty,
+        }
+    }
     fn iarrow(context: Stack<Atom>, expr: G, node: &Self, ident: &Ident) -> Self {
         let rule = Rule::IAbs;
         let ty = Ty::mk_iarrow(*ident, node.ty.clone());
@@ -561,6 +581,10 @@ impl Derivation {
         let root = DeriveNode::quantify(context, expr, d.tree.root().item, x);
         Self::rule_one_arg_inner(root, d)
     }
+    pub fn rule_exists(context: Stack<Atom>, expr: G, d: Self, x: &Ident) -> Self {
+        let root = DeriveNode::exists(context, expr, d.tree.root().item, x);
+        Self::rule_one_arg_inner(root, d)
+    }
     pub fn rule_iarrow(context: Stack<Atom>, expr: G, d: Self, x: &Ident) -> Self {
         let root = DeriveNode::iarrow(context, expr, d.tree.root().item, x);
         Self::rule_one_arg_inner(root, d)
@@ -730,6 +754,21 @@ fn handle_rule_in_update_parents(
             let x = n.expr.univ().0;
             Ty::mk_prop_ty(Atom::mk_univ_int(x.id, cnstr))
         }
+        Rule::Exists => {
+            let children: Vec<_> = t
+                .get_children(t.get_node_by_id(cur))
+                .map(|child| child.item)
+                .collect();
+            assert_eq!(children.len(), 1);
+            let cnstr = match children[0].ty.kind() {
+                TauKind::Proposition(c) => c.clone(),
+                TauKind::PTy(_, _) | TauKind::IArrow(_, _) | TauKind::Arrow(_, _) => {
+                    panic!("not exists")
+                }
+            };
+            let x = n.expr.exists().0;
+            Ty::mk_prop_ty(Atom::mk_existq(x.id, cnstr))
+        }
         Rule::IAbs => {
             let children: Vec<_> = t
                 .get_children(t.get_node_by_id(cur))
@@ -907,6 +946,17 @@ impl Derivation {
                 self.update_expr_inner(children[0], g, alpha_renaming_map.clone());
                 Rule::Univ
             }
+            Rule::Exists => {
+                let (v, _) = old_expr.exists();
+                let (w, g) = expr.exists();
+                if v.ty.is_integral() && v.id != w.id {
+                    alpha_renaming_map.push_mut((v.id, w.id));
+                }
+
+                assert_eq!(children.len(), 1);
+                self.update_expr_inner(children[0], g, alpha_renaming_map.clone());
+                Rule::Exists
+            }
             Rule::IAbs => {
                 let (w, g) = expr.abs();
                 debug_assert!(w.ty.is_int());
@@ -1454,6 +1504,24 @@ impl Derivation {
                 );
                 Self::rule_quantifier(empty_ctx, expr, d, &x)
             }
+            Rule::Exists => {
+                let v = expr.exists().0;
+                let univ_ints = if v.ty.is_integral() {
+                    univ_ints.push(v.id)
+                } else {
+                    univ_ints
+                };
+                let x = v.id;
+                let child = self.tree.get_one_child(n);
+                let d = self.clone_with_template_inner(
+                    child.id,
+                    env,
+                    configuration,
+                    ints.clone(),
+                    univ_ints.clone(),
+                );
+                Self::rule_exists(empty_ctx, expr, d, &x)
+            }
             Rule::IAbs => {
                 let x = expr.abs().0.id;
                 let child = self.tree.get_one_child(n);
@@ -1742,6 +1810,14 @@ impl Derivation {
                 let d = self.prepare_fse_inner(child.id, cfg);
                 Self::rule_quantifier(empty_ctx, expr, d, &x)
             }
+            Rule::Exists => {
+                let v = expr.exists().0;
+                let cfg = cfg.push_univ_int(v);
+                let x = v.id;
+                let child = self.tree.get_one_child(n);
+                let d = self.prepare_fse_inner(child.id, cfg);
+                Self::rule_exists(empty_ctx, expr, d, &x)
+            }
             Rule::Subsumption => {
                 // ignore subsumption now
                 let child = self.tree.get_one_child(n);
@@ -1842,6 +1918,13 @@ impl Derivation {
                     let child = d.tree.get_one_child(n);
                     go(d, child.id, &ints, strict, env)
                 }
+                Rule::Exists => {
+                    let x = n.item.expr.exists().0;
+                    assert!(x.ty.is_integral());
+                    let ints = ints.push(x.id);
+                    let child = d.tree.get_one_child(n);
+                    go(d, child.id, &ints, strict, env)
+                }
                 Rule::Poly(x) => {
                     let ints = ints.push(*x);
                     let child = d.tree.get_one_child(n);
@@ -10,7 +10,8 @@ use crate::formula::chc::Model;
 use crate::formula::hes::{Goal, GoalBase, GoalKind, Problem as ProblemBase};
 use crate::formula::{self, Op, Type as STy};
 use crate::formula::{
-    chc, fofml, Constraint, Fv, Ident, Logic, Negation, Rename, Subst, Top, Variable,
+    chc, fofml, Constraint, FirstOrderLogic, Fv, Ident, Logic, Negation, Rename, Subst, Top,
+    Variable,
 };
 use crate::solver;
 use crate::util::Pretty;
@@ -90,6 +91,10 @@ fn subst_predicate(
             let g = subst_predicate(g, problem, track_idents);
             G::mk_univ_t(v.clone(), g, candidate.aux.clone())
         }
+        formula::hes::GoalKind::Exists(v, g) => {
+            let g = subst_predicate(g, problem, track_idents);
+            G::mk_exists_t(v.clone(), g, candidate.aux.clone())
+        }
         formula::hes::GoalKind::ITE(_, _, _) => todo!(),
     }
 }
@@ -312,6 +317,7 @@ impl From<Candidate> for G {
                 G::mk_disj_t(x.clone().into(), y.clone().into(), l)
             }
             formula::hes::GoalKind::Univ(x, g) => G::mk_univ_t(x.clone(), g.clone().into(), l),
+            formula::hes::GoalKind::Exists(x, g) => G::mk_exists_t(x.clone(), g.clone().into(), l),
             GoalKind::ITE(_, _, _) => todo!(),
         }
     }
@@ -327,6 +333,7 @@ impl From<G> for Goal<Constraint> {
             GoalKind::Conj(x, y) => Goal::mk_conj(x.clone().into(), y.clone().into()),
             GoalKind::Disj(x, y) => Goal::mk_disj(x.clone().into(), y.clone().into()),
             GoalKind::Univ(x, g) => Goal::mk_univ(x.clone(), g.clone().into()),
+            GoalKind::Exists(x, g) => Goal::mk_exists(x.clone(), g.clone().into()),
             GoalKind::ITE(_, _, _) => todo!(),
         }
     }
@@ -381,6 +388,11 @@ impl GoalBase<Constraint, TypeMemory> {
                     let g = go(&g, map);
                     GoalBase::mk_univ_t(v, g, expr.aux.clone())
                 }
+                GoalKind::Exists(v, g) => {
+                    let (v, g) = aux(v, g, map, expr.aux.id);
+                    let g = go(&g, map);
+                    GoalBase::mk_exists_t(v, g, expr.aux.clone())
+                }
                 GoalKind::App(g1, g2) => {
                     let g1 = go(g1, map);
                     let g2 = go(g2, map);
@@ -429,6 +441,10 @@ impl GoalBase<Constraint, TypeMemory> {
                 let x = x.update_ids();
                 Self::mk_univ(v.clone(), x)
             }
+            GoalKind::Exists(v, x) => {
+                let x = x.update_ids();
+                Self::mk_exists(v.clone(), x)
+            }
             GoalKind::ITE(_, _, _) => todo!(),
         };
         expr.aux = self.aux.update_id();
@@ -474,6 +490,16 @@ impl GoalBase<Constraint, TypeMemory> {
                     let g2 = go(g2, ints);
                     G::mk_univ_t(x, g2, g.aux.clone())
                 }
+                GoalKind::Exists(x, g2) => {
+                    let x = x.clone();
+                    let ints = if x.ty.is_integral() {
+                        ints.push(x.id)
+                    } else {
+                        ints.clone()
+                    };
+                    let g2 = go(g2, ints);
+                    G::mk_exists_t(x, g2, g.aux.clone())
+                }
                 GoalKind::ITE(_, _, _) => todo!(),
             };
             g.aux.ints = ints.clone();
@@ -507,6 +533,13 @@ impl GoalBase<Constraint, TypeMemory> {
                     let g = G::mk_univ_t(x, g2, g.aux.clone());
                     (g, t.clone())
                 }
+                GoalKind::Exists(x, g2) => {
+                    let x = x.clone();
+                    let env = env.insert(x.id, x.ty.clone());
+                    let (g2, t) = go(g2, env);
+                    let g = G::mk_exists_t(x, g2, g.aux.clone());
+                    (g, t.clone())
+                }
                 GoalKind::App(g1, g2) => {
                     let (g1, t1) = go(g1, env.clone());
                     let (g2, t2) = go(g2, env.clone());
@@ -597,6 +630,10 @@ impl GoalBase<Constraint, TypeMemory> {
                 let g = g.eta_expand();
                 G::mk_univ_t(x.clone(), g, self.aux.clone())
             }
+            GoalKind::Exists(x, g) => {
+                let g = g.eta_expand();
+                G::mk_exists_t(x.clone(), g, self.aux.clone())
+            }
             GoalKind::ITE(_, _, _) => todo!(),
         }
     }
@@ -801,6 +838,20 @@ fn generate_reduction_sequence(goal: &G, optimizer: &mut dyn Optimizer) -> (Vec<
                 }
                 r
             }
+            GoalKind::Exists(x, g) => {
+                let is_int = x.ty.is_integral();
+                let mut saved = false;
+                if is_int && !fvints.insert(x.id) {
+                    // x is type int and fvints already has x.id
+                    saved = true;
+                }
+                let r = go_(opt, g, lv, fvints, argints, cnstr, Stack::new())
+                    .map(|(g, p, _)| (G::mk_exists_t(x.clone(), g, goal.aux.clone()), p, false));
+                if is_int && !saved {
+                    fvints.remove(&x.id);
+                }
+                r
+            }
             GoalKind::Abs(x, g) => {
                 let mut saved = false;
                 let mut saved_arg = false;
@@ -976,14 +1027,12 @@ impl Context {
                 // this should not happen?
                 panic!("fail!")
             }
-            solver::chc::CHCResult::Unknown => {
-                panic!(
-                    "PDR fails to infer a refinement type due to the background CHC solver's error"
-                )
+            solver::chc::CHCResult::Unknown | solver::chc::CHCResult::Timeout => {
+                warn!(
+                    "PDR fails to infer a refinement type since the background CHC solver gave up"
+                );
+                None
             }
-            solver::chc::CHCResult::Timeout => panic!(
-                "PDR fails to infer a refinement type due to timeout of the background CHC solver"
-            ),
         }
     }
     fn infer_type(&mut self, mut derivation: Derivation) -> Option<TyEnv> {
@@ -1017,7 +1066,7 @@ impl Context {
             debug!("{}", c);
         }
         let config = solver::interpolation::InterpolationConfig::new().use_chc_if_requied();
-        let model = solver::interpolation::solve(&clauses, &config);
+        let model = solver::interpolation::solve(&clauses, &config)?;
         pdebug!("interpolated"; title);
         pdebug!(model);
 
@@ -1243,7 +1292,8 @@ fn handle_app(
             | GoalKind::Abs(_, _)
             | GoalKind::Conj(_, _)
             | GoalKind::Disj(_, _)
-            | GoalKind::Univ(_, _) => panic!("fatal: {}", pred_expr),
+            | GoalKind::Univ(_, _)
+            | GoalKind::Exists(_, _) => panic!("fatal: {}", pred_expr),
             GoalKind::ITE(_, _, _) => todo!(),
         }
     }
@@ -1375,6 +1425,16 @@ fn type_check_body(
             pt.quantify(context_ty.clone(), expr.clone(), &x.id);
             pt
         }
+        formula::hes::GoalKind::Exists(x, g) => {
+            let b = ienv.insert(x.id);
+            let mut pt = type_check_body(config, tenv, ienv, all_coefs, &g, context_ty);
+            if b {
+                ienv.remove(&x.id);
+            }
+            // if θ ⇒ ψ for some x, then ∃x. θ ⇒ ∃x. ψ
+            pt.exists(context_ty.clone(), expr.clone(), &x.id);
+            pt
+        }
         formula::hes::GoalKind::App(_, _) => {
             handle_app(config, tenv, ienv, all_coefs, expr, context_ty)
         }
@@ -1621,6 +1681,16 @@ impl PossibleDerivation {
             .collect();
     }
 
+    /// Introduces Exists Rule to each derivation in the `PossibleDerivation`
+    fn exists(&mut self, context: Stack<Atom>, expr: G, x: &Ident) {
+        self.types = self
+            .types
+            .iter()
+            .cloned()
+            .map(|d| Derivation::rule_exists(context.clone(), expr.clone(), d, x))
+            .collect();
+    }
+
     /// Introduces IApp rule to each derivation in the `PossibleDerivation`
     fn iarrow(self, context: Stack<Atom>, expr: G, x: &Ident) -> Self {
         let types = self
@@ -1781,6 +1851,23 @@ pub fn search_for_type(
     }
 }
 
+/// Checks if Γ ⊢ ψ : t where ψ is the body of the clause of `pred`.
+pub fn check_inductive_type(
+    env: &TyEnv,
+    problem: &Problem,
+    pred: &Ident,
+    t: &super::rtype::Ty,
+) -> bool {
+    let clause = problem.get_clause(pred).unwrap();
+    let mut tenv: Env = env.into();
+    type_check(
+        &mut tenv,
+        &mut HashSet::new(),
+        &clause.body.clone().into(),
+        &t.clone().into(),
+    )
+}
+
 /// Takes a type environment Γ and a problem (D, φ), and check if Γ |- D: Γ.
 pub fn check_inductive(env: &TyEnv, problem: &Problem) -> bool {
     let tenv: Env = env.into();
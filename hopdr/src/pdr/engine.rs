@@ -1,5 +1,5 @@
 use super::rtype::{Refinement, Tau, TyEnv, TypeEnvironment};
-use super::{PDRConfig, ValidCertificate, VerificationResult};
+use super::{Counterexample, PDRConfig, ValidCertificate, VerificationResult};
 use crate::formula::hes::Problem;
 use crate::formula::{hes, Constraint, TeXPrinter};
 use crate::pdr::derivation;
@@ -12,6 +12,8 @@ use thiserror::Error;
 pub enum PDRError {
     #[error("Type inference from cex failed")]
     TypeInference,
+    #[error("Cancelled")]
+    Cancelled,
 }
 
 pub enum PDRResult {
@@ -28,6 +30,8 @@ pub struct HoPDR {
     problem: Problem<Constraint>,
     loop_cnt: u64,
     config: PDRConfig,
+    /// inductive subset of the type hints, which is added to every new frame
+    hints: TyEnv,
 }
 
 impl<C: Refinement> TypeEnvironment<Tau<C>> {
@@ -48,6 +52,41 @@ impl<C: Refinement> TypeEnvironment<Tau<C>> {
     }
 }
 
+/// Removes the hints that are not derivable from the bodies of their clauses until
+/// the rest Γ satisfies Γ ⊢ D : Γ.
+///
+/// Since such Γ holds for the greatest fixpoint, it also holds for every approximation
+/// of it, so the result can be added to any frame without breaking soundness.
+fn inductive_hints(problem: &Problem<Constraint>, hints: &TyEnv) -> TyEnv {
+    let mut hints = hints.clone();
+    hints
+        .map
+        .retain(|pred, ts| problem.get_clause(pred).is_some() && !ts.is_empty());
+    loop {
+        let mut env = TyEnv::new_bot_env(problem);
+        env.append(&hints);
+        let mut changed = false;
+        for (pred, ts) in hints.map.iter_mut() {
+            ts.retain(|t| {
+                let ok = derivation::check_inductive_type(&env, problem, pred, t);
+                if !ok {
+                    warn!(
+                        "type hint {}: {} is dropped since it is not inductive",
+                        pred, t
+                    );
+                    changed = true;
+                }
+                ok
+            });
+        }
+        hints.map.retain(|_, ts| !ts.is_empty());
+        if !changed {
+            debug!("type hints: {}", hints);
+            return hints;
+        }
+    }
+}
+
 impl HoPDR {
     fn dump_state(&self) {
         debug!("{}", "[PDR STATE]".green().bold());
@@ -90,12 +129,14 @@ impl HoPDR {
     }
 
     fn new(problem: Problem<Constraint>, config: PDRConfig) -> HoPDR {
+        let hints = inductive_hints(&problem, &config.type_hints);
         let mut hopdr = HoPDR {
             models: Vec::new(),
             envs: Vec::new(),
             problem,
             loop_cnt: 0,
             config,
+            hints,
         };
         hopdr.initialize();
         hopdr
@@ -119,7 +160,9 @@ impl HoPDR {
 
     fn unfold(&mut self) {
         info!("{}", "unfold".purple());
-        self.envs.push(TyEnv::new_bot_env(&self.problem));
+        let mut env = TyEnv::new_bot_env(&self.problem);
+        env.append(&self.hints);
+        self.envs.push(env);
         self.induction();
     }
 
@@ -165,6 +208,9 @@ impl HoPDR {
     fn check_feasible(&mut self) -> Result<bool, PDRError> {
         debug!("[PDR]check feasible");
         loop {
+            if self.config.is_cancelled() {
+                return Err(PDRError::Cancelled);
+            }
             if self.config.config.wait_every_step {
                 crate::util::wait_for_line();
             }
@@ -274,6 +320,9 @@ impl HoPDR {
             println!("{}", TeXPrinter(&self.problem));
         }
         loop {
+            if self.config.is_cancelled() {
+                break Err(PDRError::Cancelled);
+            }
             self.dump_state();
             self.loop_cnt += 1;
             if self.config.dump_tex_progress {
@@ -293,14 +342,16 @@ impl HoPDR {
     }
 }
 
-pub fn run(problem: Problem<Constraint>, config: PDRConfig) -> VerificationResult {
+fn run_greatest(problem: Problem<Constraint>, config: PDRConfig) -> VerificationResult {
     let mut pdr = HoPDR::new(problem, config);
     match pdr.run() {
         Ok(PDRResult::Valid) => {
             let certificate = ValidCertificate::new(pdr.envs[pdr.envs.len() - 1].clone());
             VerificationResult::Valid(certificate)
         }
-        Ok(PDRResult::Invalid) => VerificationResult::Invalid,
+        Ok(PDRResult::Invalid) => {
+            VerificationResult::Invalid(Counterexample::new(&pdr.problem, &pdr.models))
+        }
         Err(x) => {
             warn!("{}", "Failed to complete PDR".red());
             warn!("Reason: {}", x);
@@ -308,3 +359,48 @@ pub fn run(problem: Problem<Constraint>, config: PDRConfig) -> VerificationResul
         }
     }
 }
+
+/// HoPDR only handles greatest fixpoints, so a problem with least fixpoint equations is
+/// checked through two approximations of it:
+/// - all the least fixpoints are reinterpreted as greatest ones, which is implied by the
+///   original problem. So, if it is invalid, so is the original.
+/// - each strongly connected component that contains a least fixpoint is replaced by its
+///   finite unrolling, which implies the original problem. The unrolling depth is doubled
+///   until the approximation becomes valid or the depth exceeds the bound.
+///
+/// This is not a decision procedure for μ: there is no ranking function or well-founded
+/// reasoning, so a property that needs unboundedly many unrollings (e.g. `∀x. Term x`
+/// with `Term x =μ x <= 0 \/ Term (x - 1)`) ends up Unknown. Each round runs PDR from
+/// scratch on an unrolling that grows with the depth. A Valid answer has no certificate
+/// since the types are given to the copies of the unrolled predicates.
+fn run_with_least_fixpoint(problem: Problem<Constraint>, config: PDRConfig) -> VerificationResult {
+    info!("{}", "least fixpoint equations found".purple());
+    match run_greatest(
+        problem.clone().over_approximate_least_fixpoints(),
+        config.clone(),
+    ) {
+        VerificationResult::Valid(_) => (),
+        r => return r,
+    }
+    let mut depth = 1;
+    while depth <= config.least_fixpoint_unrolling_bound && !config.is_cancelled() {
+        info!("unrolling least fixpoints: depth {}", depth);
+        let approx = problem.under_approximate_least_fixpoints(depth);
+        if let VerificationResult::Valid(_) = run_greatest(approx, config.clone()) {
+            return VerificationResult::Valid(ValidCertificate::without_certificate());
+        }
+        depth *= 2;
+    }
+    VerificationResult::Unknown
+}
+
+pub fn run(problem: Problem<Constraint>, config: PDRConfig) -> VerificationResult {
+    let solvers = config.config.solvers.clone();
+    solvers.scope(|| {
+        if problem.has_least_fixpoint() {
+            run_with_least_fixpoint(problem, config)
+        } else {
+            run_greatest(problem, config)
+        }
+    })
+}
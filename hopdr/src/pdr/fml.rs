@@ -23,6 +23,7 @@ impl From<Goal<Constraint>> for Goal<fofml::Atom> {
             GoalKind::Conj(x, y) => Goal::mk_conj(x.clone().into(), y.clone().into()),
             GoalKind::Disj(x, y) => Goal::mk_disj(x.clone().into(), y.clone().into()),
             GoalKind::Univ(x, y) => Goal::mk_univ(x.clone(), y.clone().into()),
+            GoalKind::Exists(x, y) => Goal::mk_exists(x.clone(), y.clone().into()),
             GoalKind::ITE(c1, g1, g2) => {
                 let c1 = c1.clone();
                 let g1 = g1.clone().into();
@@ -49,6 +50,7 @@ impl From<Goal<fofml::Atom>> for fofml::Atom {
                 fofml::Atom::mk_disj(c1, c2)
             }
             GoalKind::Univ(x, g) => fofml::Atom::mk_univq(x.id, g.clone().into()),
+            GoalKind::Exists(x, g) => fofml::Atom::mk_existq(x.id, g.clone().into()),
             // the following must not happen
             GoalKind::Abs(_, _) | GoalKind::App(_, _) | GoalKind::Var(_) | GoalKind::Op(_) => {
                 panic!("impossible to transform: {}", frm)
@@ -184,6 +186,15 @@ impl<C: Refinement> Env<C> {
                 g
             }
             GoalKind::Univ(x, y) => Goal::mk_univ(x.clone(), self.go(y.clone(), ints)),
+            GoalKind::Exists(x, y) if x.ty.is_integral() => {
+                let b = ints.insert(x.id);
+                let g = Goal::mk_exists(x.clone(), self.go(y.clone(), ints));
+                if b {
+                    ints.remove(&x.id);
+                }
+                g
+            }
+            GoalKind::Exists(x, y) => Goal::mk_exists(x.clone(), self.go(y.clone(), ints)),
             GoalKind::Constr(_) | GoalKind::Op(_) => g.clone(),
             GoalKind::ITE(c, g1, g2) => {
                 let c = c.clone();
@@ -235,6 +246,7 @@ pub fn env_models_constraint<C: Refinement>(env: &Env<C>, g: &Goal<C>) -> C {
 }
 
 // Γ ⊧ g ⇔ ⊧ θ where Γ(g) ⤳ θ
+// returns false if the solver gives up (or times out)
 pub fn env_models(env: &Env<Constraint>, g: &Goal<Constraint>) -> bool {
     crate::title!("env_models");
     debug!("{}", g);
@@ -242,7 +254,10 @@ pub fn env_models(env: &Env<Constraint>, g: &Goal<Constraint>) -> bool {
     match smt::default_solver().solve(&cnstr, &HashSet::new()) {
         solver::SolverResult::Sat => true,
         solver::SolverResult::Unsat => false,
-        solver::SolverResult::Timeout | solver::SolverResult::Unknown => panic!("smt check fail.."),
+        r @ (solver::SolverResult::Timeout | solver::SolverResult::Unknown) => {
+            warn!("env_models: smt check failed ({:?})", r);
+            false
+        }
     }
 }
 
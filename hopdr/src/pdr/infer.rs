@@ -204,19 +204,17 @@ pub(super) fn infer(
             let m = match solver::chc::default_solver().solve(&clauses) {
                 solver::chc::CHCResult::Sat(m) => m,
                 solver::chc::CHCResult::Unsat => return None,
-                solver::chc::CHCResult::Unknown => panic!(
-                    "PDR fails to infer a refinement type due to the background CHC solver's error"
-                ),
-                solver::chc::CHCResult::Timeout => panic!(
-                "PDR fails to infer a refinement type due to timeout of the background CHC solver"
-            ),
+                solver::chc::CHCResult::Unknown | solver::chc::CHCResult::Timeout => {
+                    warn!("PDR fails to infer a refinement type since the background CHC solver gave up");
+                    return None;
+                }
             };
 
             title!("model from CHC solver");
             // TODO: Display model
             debug!("{}", m);
             let config = solver::interpolation::InterpolationConfig::new().use_chc_if_requied();
-            let m = solver::interpolation::solve(&clauses, &config);
+            let m = solver::interpolation::solve(&clauses, &config)?;
             debug!("interpolated:");
             debug!("{}", m);
             m
@@ -248,7 +246,7 @@ pub(super) fn infer(
             });
 
             let clauses = solver::disj::generate_clauses(clauses);
-            solver::disj::solve(&clauses)
+            solver::disj::solve(&clauses)?
         }
     };
 
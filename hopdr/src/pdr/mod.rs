@@ -1,3 +1,5 @@
+pub mod certificate;
+pub mod counterexample;
 pub mod derivation;
 pub mod engine;
 pub mod fml;
@@ -5,23 +7,44 @@ mod infer;
 mod optimizer;
 pub mod rtype;
 
+pub use counterexample::Counterexample;
 pub use engine::run;
 use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 #[derive(Debug)]
 pub enum VerificationResult {
     Valid(ValidCertificate),
-    Invalid,
+    Invalid(Counterexample),
     Unknown,
 }
 
 #[derive(Debug)]
 pub struct ValidCertificate {
-    pub certificate: rtype::TypeEnvironment<rtype::Ty>,
+    /// `None` if the problem has least fixpoint equations. Their validity is shown by
+    /// unrolling them (see `engine::run`), and the type environment of the unrolled
+    /// problem says nothing about the original predicates.
+    pub certificate: Option<rtype::TypeEnvironment<rtype::Ty>>,
 }
 impl ValidCertificate {
     fn new(certificate: rtype::TypeEnvironment<rtype::Ty>) -> Self {
-        Self { certificate }
+        Self {
+            certificate: Some(certificate),
+        }
+    }
+    fn without_certificate() -> Self {
+        Self { certificate: None }
+    }
+    /// Re-checks the certificate against `problem` independently of the PDR search.
+    pub fn verify(
+        &self,
+        problem: &crate::formula::hes::Problem<crate::formula::Constraint>,
+    ) -> Result<(), certificate::CertificateError> {
+        match &self.certificate {
+            Some(certificate) => certificate::verify(problem, certificate),
+            None => Err(certificate::CertificateError::LeastFixpoint),
+        }
     }
 }
 
@@ -33,16 +56,20 @@ impl fmt::Display for VerificationResult {
             "{}",
             match self {
                 Valid(_) => "valid",
-                Invalid => "invalid",
+                Invalid(_) => "invalid",
                 Unknown => "unknown",
             }
         )
     }
 }
 
+#[derive(Clone)]
 pub struct PDRConfig {
     dump_tex_progress: bool,
     config: crate::Configuration,
+    least_fixpoint_unrolling_bound: usize,
+    type_hints: rtype::TyEnv,
+    cancel: Option<Arc<AtomicBool>>,
 }
 
 impl PDRConfig {
@@ -50,12 +77,44 @@ impl PDRConfig {
         PDRConfig {
             dump_tex_progress: false,
             config: config,
+            least_fixpoint_unrolling_bound: 64,
+            type_hints: rtype::TyEnv::new(),
+            cancel: None,
         }
     }
     pub fn dump_tex_progress(mut self, dump_tex_progress: bool) -> Self {
         self.dump_tex_progress = dump_tex_progress;
         self
     }
+    /// set the maximum depth of the approximants used for least fixpoint equations
+    pub fn least_fixpoint_unrolling_bound(mut self, bound: usize) -> Self {
+        self.least_fixpoint_unrolling_bound = bound;
+        self
+    }
+    /// set candidate types of predicates that seed the frames.
+    /// Only the largest subset of them that is inductive is used.
+    pub fn type_hints(mut self, hints: rtype::TyEnv) -> Self {
+        self.type_hints = hints;
+        self
+    }
+    /// set a flag with which another thread stops the search.
+    /// Once it is set, PDR gives up and returns `Unknown`.
+    pub fn cancel(mut self, cancel: Arc<AtomicBool>) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+    /// returns the flag given by `cancel`, or sets a new one if there is none
+    pub fn cancel_flag(&mut self) -> Arc<AtomicBool> {
+        self.cancel
+            .get_or_insert_with(|| Arc::new(AtomicBool::new(false)))
+            .clone()
+    }
+    fn is_cancelled(&self) -> bool {
+        match &self.cancel {
+            Some(c) => c.load(Ordering::Relaxed),
+            None => false,
+        }
+    }
 }
 
 //fn infer_nu_validity(vc: )
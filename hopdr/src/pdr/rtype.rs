@@ -972,7 +972,10 @@ impl Tau<Constraint> {
                     panic!("panic")
                 }
             };
-            let model = solver::interpolation::solve(&clauses, &Default::default());
+            let model = match solver::interpolation::solve(&clauses, &Default::default()) {
+                Some(model) => model,
+                None => return ts.to_owned(),
+            };
             let t = t.assign(&model.model);
             vec![t]
         }
@@ -2174,6 +2177,7 @@ pub fn ty_check(g: &Goal<Constraint>, t: &Tau<Constraint>) -> bool {
 }
 
 // allow inter section types
+// returns false if the solver gives up (or times out)
 pub fn tys_check(
     //env: &Env<Constraint>,
     g: &Goal<Constraint>,
@@ -2184,6 +2188,9 @@ pub fn tys_check(
     match smt::default_solver().solve(&cnstr, &cnstr.fv()) {
         solver::SolverResult::Sat => true,
         solver::SolverResult::Unsat => false,
-        solver::SolverResult::Timeout | solver::SolverResult::Unknown => panic!("smt check fail.."),
+        r @ (solver::SolverResult::Timeout | solver::SolverResult::Unknown) => {
+            warn!("tys_check: smt check failed ({:?})", r);
+            false
+        }
     }
 }
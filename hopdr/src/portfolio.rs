@@ -0,0 +1,143 @@
+//! Runs HoPDR and the ModeTrans checker concurrently on the same problem.
+//!
+//! HoPDR is good at proving validity while ModeTrans is good at finding
+//! counterexamples, so the first definitive answer of either is returned, and the
+//! other is cancelled. Invalid from HoPDR is also definitive, but Valid from ModeTrans
+//! never happens since it only refutes problems.
+//!
+//! The engines record their statistics (feature `stat`) separately, and those of
+//! ModeTrans are added to the current ones at the end.
+use crate::checker;
+use crate::formula::hes::Problem;
+use crate::formula::Constraint;
+use crate::pdr::{self, PDRConfig, VerificationResult};
+use crate::stat::{self, Stat};
+
+use std::fmt;
+use std::sync::atomic::Ordering;
+
+/// HoPDR searches deep in the recursion of formulas
+const PDR_STACK_SIZE: usize = 256 * 1024 * 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Engine {
+    HoPDR,
+    ModeTrans,
+}
+
+impl fmt::Display for Engine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Engine::HoPDR => write!(f, "HoPDR"),
+            Engine::ModeTrans => write!(f, "ModeTrans"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum PortfolioResult {
+    Valid(pdr::ValidCertificate),
+    InvalidByHoPDR(pdr::Counterexample),
+    /// with the trace of the counterexample if it is tracked
+    InvalidByModeTrans(Option<String>),
+    Unknown,
+}
+
+impl PortfolioResult {
+    /// the engine that gave the answer
+    pub fn winner(&self) -> Option<Engine> {
+        match self {
+            PortfolioResult::Valid(_) | PortfolioResult::InvalidByHoPDR(_) => Some(Engine::HoPDR),
+            PortfolioResult::InvalidByModeTrans(_) => Some(Engine::ModeTrans),
+            PortfolioResult::Unknown => None,
+        }
+    }
+}
+
+impl fmt::Display for PortfolioResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            PortfolioResult::Valid(_) => "valid",
+            PortfolioResult::InvalidByHoPDR(_) | PortfolioResult::InvalidByModeTrans(_) => {
+                "invalid"
+            }
+            PortfolioResult::Unknown => "unknown",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// Races `pdr::run` on `pdr_problem` and `checker::run` on `checker_problem`, and returns
+/// the first definitive answer. The result is `Unknown` only if both engines fail.
+///
+/// Both are the same problem, but each engine has its own preprocessing
+/// (`preprocess::hes::Config::new` and `Config::checker_default`, respectively).
+pub fn run(
+    pdr_problem: Problem<Constraint>,
+    mut pdr_config: PDRConfig,
+    checker_problem: Problem<Constraint>,
+    checker_config: checker::Config,
+) -> PortfolioResult {
+    let cancel = pdr_config.cancel_flag();
+    let pdr_stat = stat::current();
+    let checker_stat = Stat::new();
+    let checker_config = checker_config.stat(checker_stat.clone());
+
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let r = rt.block_on(async {
+        // PDR is not async, so it runs in its own thread and is stopped by `cancel`
+        let (tx, mut pdr_rx) = tokio::sync::oneshot::channel();
+        std::thread::Builder::new()
+            .stack_size(PDR_STACK_SIZE)
+            .spawn(move || {
+                // the receiver has gone if ModeTrans has already won
+                let r = pdr_stat.scope(|| pdr::run(pdr_problem, pdr_config));
+                let _ = tx.send(r);
+            })
+            .unwrap();
+        // the external process of the checker is killed when the task is aborted
+        let mut checker = tokio::spawn(checker::run(checker_problem, checker_config));
+
+        let mut pdr_done = false;
+        let mut checker_done = false;
+        while !(pdr_done && checker_done) {
+            tokio::select! {
+                r = &mut pdr_rx, if !pdr_done => {
+                    pdr_done = true;
+                    match r {
+                        Ok(VerificationResult::Valid(c)) => {
+                            info!("portfolio: HoPDR won");
+                            checker.abort();
+                            return PortfolioResult::Valid(c);
+                        }
+                        Ok(VerificationResult::Invalid(c)) => {
+                            info!("portfolio: HoPDR won");
+                            checker.abort();
+                            return PortfolioResult::InvalidByHoPDR(c);
+                        }
+                        Ok(VerificationResult::Unknown) => info!("portfolio: HoPDR gave up"),
+                        Err(_) => warn!("portfolio: HoPDR panicked"),
+                    }
+                }
+                r = &mut checker, if !checker_done => {
+                    checker_done = true;
+                    match r {
+                        Ok(checker::ExecResult::Invalid(trace)) => {
+                            info!("portfolio: ModeTrans won");
+                            cancel.store(true, Ordering::Relaxed);
+                            return PortfolioResult::InvalidByModeTrans(trace);
+                        }
+                        Ok(checker::ExecResult::Unknown) => info!("portfolio: ModeTrans gave up"),
+                        Ok(checker::ExecResult::Fail(s)) => {
+                            warn!("portfolio: ModeTrans failed: {}", s)
+                        }
+                        Err(e) => warn!("portfolio: ModeTrans panicked: {}", e),
+                    }
+                }
+            }
+        }
+        PortfolioResult::Unknown
+    });
+    stat::current().merge(&checker_stat);
+    r
+}
@@ -49,6 +49,16 @@ fn alpha_rename_expr(
             };
             Expr::mk_univ(v, f(env, e))
         }
+        Exists(x, e) => {
+            let id = formula::Ident::fresh();
+            let mut env = env.clone();
+            env.insert(x.id.clone(), id);
+            let v = VariableS {
+                id,
+                ty: x.ty.clone(),
+            };
+            Expr::mk_exists(v, f(env, e))
+        }
         Abs(x, e) => {
             let id = formula::Ident::fresh();
             let mut env = env.clone();
@@ -74,7 +84,12 @@ fn alpha_rename_clause(mut env: Environment, c: &InClause) -> OutClause {
     let ty = c.id.ty.clone();
     let id = VariableS { id, ty };
     let expr = alpha_rename_expr(env, &c.expr);
-    Clause { args, id, expr }
+    Clause {
+        args,
+        id,
+        expr,
+        fixpoint: c.fixpoint,
+    }
 }
 
 fn alpha_rename_clauses(mut env: Environment, c: &[InClause]) -> Environment {
@@ -33,6 +33,11 @@ fn handle_goal(goal: &Goal, mut fvbools: Vec<Ident>) -> Goal {
             let g = handle_goal(g, fvbools);
             Goal::mk_univ(v.clone(), g)
         }
+        // boolean variables bound outside cannot be expanded inside the existential
+        GoalKind::Exists(v, g) => {
+            let g = handle_goal(g, Vec::new());
+            dfs(Goal::mk_exists(v.clone(), g), &fvbools, 0)
+        }
         GoalKind::Abs(v, g) => {
             let g = handle_goal(g, fvbools);
             Goal::mk_abs(v.clone(), g)
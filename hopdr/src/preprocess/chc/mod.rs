@@ -179,18 +179,17 @@ fn postprocess_smt2_for_hoice(data: &[u8]) -> Result<String, Error> {
     Ok(String::from_utf8(buf)?)
 }
 
-/// Preprocess the given file and returns the HFLz formula string
+/// Preprocess the given file and returns the CHC string
 ///
-/// Before open `filename`, `open_file_with_preprocess` executes command `hfl-preprocessor` (github.com/moratorium08/hfl-preprocessor`,
-/// which transforms hfls with some heuristics without changing the validity (inlining, removing disjunctions, etc)
-/// These functionalities should be implemented in hopdr in the future.
+/// Before open `filename`, `open_file_with_preprocess` executes command `z3`, which simplifies
+/// the CHCs with the preprocessing of Spacer.
 pub fn open_file_with_preprocess(filename: &str) -> Result<String, Error> {
     crate::stat::preprocess::start_clock("spacer-preprocessor");
 
-    const CMD: &str = "z3";
+    let z3 = crate::solver::registry::with(|r| r.paths.z3.clone());
 
     let args = vec!["-in"];
-    let mut child = Command::new(CMD)
+    let mut child = Command::new(z3)
         .args(args)
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
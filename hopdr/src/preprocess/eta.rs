@@ -44,6 +44,12 @@ impl TypedPreprocessor for EtaTransform {
                     env.del(&x.id);
                     (Type::mk_type_prop(), Goal::mk_univ(x.clone(), g))
                 }
+                GoalKind::Exists(x, g) => {
+                    env.add(x.id, x.ty.clone());
+                    let g = translate(g, &Type::mk_type_prop(), env);
+                    env.del(&x.id);
+                    (Type::mk_type_prop(), Goal::mk_exists(x.clone(), g))
+                }
                 GoalKind::Var(x) => {
                     let t = env.get(x).unwrap_or_else(|| panic!("cannot find {}", x));
                     (t, g.clone())
@@ -129,6 +135,7 @@ impl TypedPreprocessor for EtaTransform {
                     append_args(g_app, t)
                 }
                 GoalKind::Univ(x, g) => Goal::mk_univ(x.clone(), translate(g, t, env)),
+                GoalKind::Exists(x, g) => Goal::mk_exists(x.clone(), translate(g, t, env)),
                 GoalKind::Conj(g1, g2) => {
                     Goal::mk_conj(translate(g1, t, env), translate(g2, t, env))
                 }
@@ -71,6 +71,10 @@ impl FindITETransform {
                 let g = self.f(g);
                 Goal::mk_univ(x.clone(), g)
             }
+            GoalKind::Exists(x, g) => {
+                let g = self.f(g);
+                Goal::mk_exists(x.clone(), g)
+            }
             GoalKind::ITE(c, g1, g2) => {
                 let g1 = self.f(g1);
                 let g2 = self.f(g2);
@@ -72,6 +72,7 @@ fn transform_goal(goal: &hes::Goal<formula::Constraint>) -> hes::Goal<formula::C
                 _ => Goal::mk_app(translate(g1), translate(g2)),
             },
             GoalKind::Univ(x, g) => flatten(Goal::mk_univ(x.clone(), translate(g))),
+            GoalKind::Exists(x, g) => Goal::mk_exists(x.clone(), translate(g)),
             GoalKind::Conj(g1, g2) => Goal::mk_conj(translate(g1), translate(g2)),
             GoalKind::Disj(g1, g2) => Goal::mk_disj(translate(g1), translate(g2)),
             GoalKind::ITE(c, g1, g2) => Goal::mk_ite(c.clone(), translate(g1), translate(g2)),
@@ -2,14 +2,16 @@ use super::alpha::alpha_renaming;
 use super::eta;
 use super::find_ite;
 use super::forall_pass;
+use super::inlining;
 use super::prenex_norm;
+use super::remove_disj;
 use super::remove_tmp_var;
 use super::reorder_conj;
 use super::reorder_disj;
 use super::safety;
 use super::simplify_constr_op;
 use super::transform::transform;
-use super::typing::typing;
+use super::typing;
 use super::unpack_constr;
 use super::Context;
 #[allow(unused_imports)]
@@ -37,6 +39,7 @@ pub enum ExprKind<Id, Ty> {
     Or(Expr<Id, Ty>, Expr<Id, Ty>),
     Abs(VariableS<Id, Ty>, Expr<Id, Ty>),
     Univ(VariableS<Id, Ty>, Expr<Id, Ty>),
+    Exists(VariableS<Id, Ty>, Expr<Id, Ty>),
 }
 pub type Expr<Id, Ty> = Unique<ExprKind<Id, Ty>>;
 
@@ -53,6 +56,7 @@ impl<Ty: fmt::Display, Id: fmt::Display> fmt::Display for Expr<Id, Ty> {
             ExprKind::True => write!(f, "true"),
             ExprKind::False => write!(f, "false"),
             ExprKind::Univ(id, e) => write!(f, "∀{}. {}", id, e),
+            ExprKind::Exists(id, e) => write!(f, "∃{}. {}", id, e),
             ExprKind::Abs(x, y) => write!(f, "\\{}. {}", x, y),
         }
     }
@@ -89,6 +93,9 @@ impl<Id, Ty> Expr<Id, Ty> {
     pub fn mk_univ(v: VariableS<Id, Ty>, e: Expr<Id, Ty>) -> Expr<Id, Ty> {
         Expr::new(ExprKind::Univ(v, e))
     }
+    pub fn mk_exists(v: VariableS<Id, Ty>, e: Expr<Id, Ty>) -> Expr<Id, Ty> {
+        Expr::new(ExprKind::Exists(v, e))
+    }
     pub fn mk_abs(v: VariableS<Id, Ty>, e: Expr<Id, Ty>) -> Expr<Id, Ty> {
         Expr::new(ExprKind::Abs(v, e))
     }
@@ -122,6 +129,7 @@ pub struct Clause<Id, Ty> {
     pub id: VariableS<Id, Ty>,
     pub args: Vec<Id>,
     pub expr: Expr<Id, Ty>,
+    pub fixpoint: parse::Fixpoint,
 }
 
 #[derive(Debug, Clone)]
@@ -136,7 +144,7 @@ impl<Id: fmt::Display, Ty: fmt::Display> fmt::Display for Clause<Id, Ty> {
         for arg in self.args.iter() {
             write!(f, " {}", arg)?;
         }
-        write!(f, " = {}", self.expr)
+        write!(f, " ={} {}", self.fixpoint, self.expr)
     }
 }
 
@@ -174,7 +182,11 @@ fn quantify_validity_checking(vc: NuHFLzValidityChecking) -> NuHFLzValidityCheck
             fixpoint,
         });
     }
-    NuHFLzValidityChecking { formulas, toplevel }
+    NuHFLzValidityChecking {
+        formulas,
+        toplevel,
+        types: vc.types,
+    }
 }
 
 /// the algorithm of preprocess can be reordered, called more than once.
@@ -186,6 +198,8 @@ pub struct Config {
     is_checker: bool,
     lightweight_find_ite: bool,
     trace: bool,
+    inlining: bool,
+    remove_disjunction: bool,
 }
 
 impl Config {
@@ -222,6 +236,19 @@ impl Config {
         self.trace = val;
         self
     }
+    pub fn inlining(mut self, val: bool) -> Self {
+        self.inlining = val;
+        self
+    }
+    pub fn remove_disjunction(mut self, val: bool) -> Self {
+        self.remove_disjunction = val;
+        self
+    }
+    /// takes `inlining` and `remove_disjunction` from `config`
+    pub fn with_configuration(self, config: &crate::Configuration) -> Self {
+        self.inlining(config.inlining)
+            .remove_disjunction(config.remove_disjunction)
+    }
 }
 
 pub fn preprocess_for_typed_problem(
@@ -229,6 +256,13 @@ pub fn preprocess_for_typed_problem(
     config: &Config,
 ) -> hes::Problem<formula::Constraint> {
     info!("[problem]\n{}\n", problem);
+    // both change the structure of the equations, which the traces rely on
+    if config.inlining && !config.trace {
+        problem = inlining::transform(problem);
+    }
+    if config.remove_disjunction && !config.trace {
+        problem = remove_disj::transform(problem);
+    }
     if !config.trace {
         problem = safety::transform(problem);
     }
@@ -262,22 +296,64 @@ pub fn preprocess_for_typed_problem(
     problem
 }
 
-pub fn preprocess(
-    vc: parse::Problem,
-    config: &Config,
-) -> (hes::Problem<formula::Constraint>, Context) {
+/// A problem that has passed `type_check`, which `preprocess_typed` preprocesses without
+/// inferring its types again.
+#[derive(Debug, Clone)]
+pub struct TypedProblem {
+    problem: ValidityChecking<parse::Ident, SimpleType>,
+    /// the `%TYPES` section of the input, which is not preprocessed
+    pub types: Vec<parse::TypeAssignment>,
+}
+
+impl TypedProblem {
+    pub fn clauses(&self) -> &[Clause<parse::Ident, SimpleType>] {
+        &self.problem.clauses
+    }
+    pub fn toplevel(&self) -> &Expr<parse::Ident, SimpleType> {
+        &self.problem.toplevel
+    }
+}
+
+/// Checks that `vc` is well-typed without preprocessing it.
+pub fn type_check(vc: parse::Problem) -> Result<TypedProblem, typing::Diagnostics> {
     match vc {
-        parse::Problem::NuHFLZValidityChecking(vc) => {
+        parse::Problem::NuHFLZValidityChecking(mut vc) => {
+            let types = std::mem::take(&mut vc.types);
             let vc = quantify_validity_checking(vc);
-            let problem = typing(vc.formulas, vc.toplevel);
-            let (problem, ctx) = alpha_renaming(problem);
-            // let problem = extravar::transform(problem);
-            let problem = transform(problem);
-            let problem = preprocess_for_typed_problem(problem, &config);
-            (problem, ctx)
+            let problem = typing::typing(vc.formulas, vc.toplevel)?;
+            Ok(TypedProblem { problem, types })
         }
     }
 }
+
+/// Preprocesses the problem checked by `type_check`.
+pub fn preprocess_typed(
+    typed: TypedProblem,
+    config: &Config,
+) -> (hes::Problem<formula::Constraint>, Context) {
+    let (problem, ctx) = alpha_renaming(typed.problem);
+    // let problem = extravar::transform(problem);
+    let problem = transform(problem);
+    let problem = preprocess_for_typed_problem(problem, &config);
+    (problem, ctx)
+}
+
+/// Same as `preprocess` except that it returns the type errors of `vc` instead of
+/// panicking.
+pub fn try_preprocess(
+    vc: parse::Problem,
+    config: &Config,
+) -> Result<(hes::Problem<formula::Constraint>, Context), typing::Diagnostics> {
+    let typed = type_check(vc)?;
+    Ok(preprocess_typed(typed, config))
+}
+
+pub fn preprocess(
+    vc: parse::Problem,
+    config: &Config,
+) -> (hes::Problem<formula::Constraint>, Context) {
+    try_preprocess(vc, config).unwrap_or_else(|e| panic!("{}", e))
+}
 pub fn preprocess_with_default_config(
     vc: parse::Problem,
 ) -> (hes::Problem<formula::Constraint>, Context) {
@@ -71,6 +71,10 @@ impl<'a> InlineLeafCallTransform {
                 let g = self.f(g);
                 Goal::mk_univ_opt(x.clone(), g)
             }
+            GoalKind::Exists(x, g) => {
+                let g = self.f(g);
+                Goal::mk_exists_opt(x.clone(), g)
+            }
             GoalKind::ITE(c, g1, g2) => {
                 let g1 = self.f(g1);
                 let g2 = self.f(g2);
@@ -134,7 +138,7 @@ fn test_inline_leaf_call() {
     let g3 = Goal::mk_disj(g1, g2.clone());
     let g = Goal::mk_conj(g0, g3);
     let g = Goal::mk_abs(xv, Goal::mk_abs(yv, g));
-    let c = Clause { head: fv, body: g };
+    let c = Clause::new(g, fv);
     let problem = Problem {
         top: Goal::mk_true(),
         clauses: vec![c],
@@ -0,0 +1,200 @@
+//! Inlines the predicates that are not recursive.
+//!
+//! A non-recursive equation has a unique solution whichever fixpoint it is, so replacing
+//! each occurrence of the predicate with its body does not change the validity. To avoid
+//! blowing up the problem, a predicate is inlined only if it occurs at most once or its
+//! body is small.
+use super::TypedPreprocessor;
+use crate::formula::hes::{self, GoalKind, Problem};
+use crate::formula::{self, Constraint, FormulaSize, Ident, Logic, TyEnv, Type};
+
+use std::collections::{HashMap, HashSet};
+
+/// bodies larger than this are inlined only if the predicate occurs once
+const INLINE_SIZE_BOUND: usize = 20;
+
+type Goal = hes::Goal<formula::Constraint>;
+
+pub struct InliningTransform {
+    /// the bodies of the inlined predicates, in which the other inlined predicates have
+    /// already been inlined
+    defs: HashMap<Ident, Goal>,
+}
+
+fn count_occurrences(g: &Goal, counts: &mut HashMap<Ident, usize>) {
+    match g.kind() {
+        GoalKind::Var(x) => *counts.entry(*x).or_default() += 1,
+        GoalKind::Constr(_) | GoalKind::Op(_) => (),
+        GoalKind::Abs(_, g) | GoalKind::Univ(_, g) | GoalKind::Exists(_, g) => {
+            count_occurrences(g, counts)
+        }
+        GoalKind::App(g1, g2)
+        | GoalKind::Conj(g1, g2)
+        | GoalKind::Disj(g1, g2)
+        | GoalKind::ITE(_, g1, g2) => {
+            count_occurrences(g1, counts);
+            count_occurrences(g2, counts);
+        }
+    }
+}
+
+impl InliningTransform {
+    fn new(problem: &Problem<Constraint>) -> InliningTransform {
+        let graph = problem.dependency_graph();
+        let is_recursive = |x: Ident| {
+            let mut visited = HashSet::new();
+            let mut stack = vec![x];
+            while let Some(y) = stack.pop() {
+                for z in graph[&y].iter() {
+                    if *z == x {
+                        return true;
+                    }
+                    if visited.insert(*z) {
+                        stack.push(*z);
+                    }
+                }
+            }
+            false
+        };
+        let mut counts = HashMap::new();
+        count_occurrences(&problem.top, &mut counts);
+        for c in problem.clauses.iter() {
+            count_occurrences(&c.body, &mut counts);
+        }
+        let inlined: HashSet<Ident> = problem
+            .clauses
+            .iter()
+            .filter(|c| !is_recursive(c.head.id))
+            .filter(|c| {
+                counts.get(&c.head.id).copied().unwrap_or(0) <= 1
+                    || c.body.formula_size() <= INLINE_SIZE_BOUND
+            })
+            .map(|c| c.head.id)
+            .collect();
+
+        let mut t = InliningTransform {
+            defs: HashMap::new(),
+        };
+        // the inlined predicates do not depend on themselves, so this terminates
+        fn define(
+            x: Ident,
+            problem: &Problem<Constraint>,
+            graph: &HashMap<Ident, HashSet<Ident>>,
+            inlined: &HashSet<Ident>,
+            t: &mut InliningTransform,
+        ) {
+            if t.defs.contains_key(&x) {
+                return;
+            }
+            for y in graph[&x].iter().filter(|y| inlined.contains(y)) {
+                define(*y, problem, graph, inlined, t);
+            }
+            let body = t.inline(&problem.get_clause(&x).unwrap().body);
+            t.defs.insert(x, body);
+        }
+        for x in inlined.iter() {
+            define(*x, problem, &graph, &inlined, &mut t);
+        }
+        t
+    }
+
+    fn replace(&self, goal: &Goal) -> Goal {
+        match goal.kind() {
+            GoalKind::Var(x) => match self.defs.get(x) {
+                Some(g) => g.alpha_renaming(),
+                None => goal.clone(),
+            },
+            GoalKind::Constr(_) | GoalKind::Op(_) => goal.clone(),
+            GoalKind::Abs(x, g) => Goal::mk_abs(x.clone(), self.replace(g)),
+            GoalKind::Univ(x, g) => Goal::mk_univ(x.clone(), self.replace(g)),
+            GoalKind::Exists(x, g) => Goal::mk_exists(x.clone(), self.replace(g)),
+            GoalKind::App(g1, g2) => Goal::mk_app(self.replace(g1), self.replace(g2)),
+            GoalKind::Conj(g1, g2) => Goal::mk_conj(self.replace(g1), self.replace(g2)),
+            GoalKind::Disj(g1, g2) => Goal::mk_disj(self.replace(g1), self.replace(g2)),
+            GoalKind::ITE(c, g1, g2) => Goal::mk_ite(c.clone(), self.replace(g1), self.replace(g2)),
+        }
+    }
+
+    fn inline(&self, goal: &Goal) -> Goal {
+        let g = self.replace(goal);
+        if &g == goal {
+            g
+        } else {
+            g.reduce_goal()
+        }
+    }
+}
+
+impl TypedPreprocessor for InliningTransform {
+    const PASS_NAME: &'static str = "inlining";
+
+    fn transform_goal(&self, goal: &Goal, _t: &Type, _env: &mut TyEnv) -> Goal {
+        self.inline(goal)
+    }
+
+    fn transform_internal(&self, problem: Problem<Constraint>) -> Problem<Constraint> {
+        let mut env = formula::generate_global_environment(&problem.clauses);
+        let clauses = problem
+            .clauses
+            .into_iter()
+            .filter(|c| !self.defs.contains_key(&c.head.id))
+            .map(|c| self.transform_clause(c, &mut env))
+            .collect();
+        let top = self.transform_goal(&problem.top, &Type::mk_type_prop(), &mut env);
+        Problem { top, clauses }
+    }
+}
+
+pub fn transform(problem: Problem<Constraint>) -> Problem<Constraint> {
+    crate::title!("inlining");
+    let t = InliningTransform::new(&problem);
+    t.transform(problem)
+}
+
+#[test]
+fn test_inlining() {
+    use crate::formula::{Fv, Op, Variable};
+    // F x =ν G x ∧ F (x + 1), G y =ν y ≥ 0, toplevel: ∀x. x < 0 ∨ F x
+    let x = Ident::fresh();
+    let y = Ident::fresh();
+    let z = Ident::fresh();
+    let f = Ident::fresh();
+    let g = Ident::fresh();
+    let pred_ty = Type::mk_type_arrow(Type::mk_type_int(), Type::mk_type_prop());
+
+    let g_body = Goal::mk_abs(
+        Variable::mk(y, Type::mk_type_int()),
+        Goal::mk_constr(Constraint::mk_geq(Op::mk_var(y), Op::zero())),
+    );
+    let f_body = Goal::mk_abs(
+        Variable::mk(x, Type::mk_type_int()),
+        Goal::mk_conj(
+            Goal::mk_app(Goal::mk_var(g), Goal::mk_op(Op::mk_var(x))),
+            Goal::mk_app(
+                Goal::mk_var(f),
+                Goal::mk_op(Op::mk_add(Op::mk_var(x), Op::one())),
+            ),
+        ),
+    );
+    let top = Goal::mk_univ(
+        Variable::mk(z, Type::mk_type_int()),
+        Goal::mk_disj(
+            Goal::mk_constr(Constraint::mk_lt(Op::mk_var(z), Op::zero())),
+            Goal::mk_app(Goal::mk_var(f), Goal::mk_op(Op::mk_var(z))),
+        ),
+    );
+    let problem = Problem {
+        top,
+        clauses: vec![
+            hes::Clause::new(f_body, Variable::mk(f, pred_ty.clone())),
+            hes::Clause::new(g_body, Variable::mk(g, pred_ty)),
+        ],
+    };
+    println!("{problem}");
+    let problem = transform(problem);
+    println!("{problem}");
+    // G is inlined, but F is recursive
+    assert_eq!(problem.clauses.len(), 1);
+    assert_eq!(problem.clauses[0].head.id, f);
+    assert!(!problem.clauses[0].body.fv().contains(&g));
+}
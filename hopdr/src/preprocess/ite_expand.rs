@@ -35,6 +35,10 @@ fn transform_goal(goal: &hes::Goal<formula::Constraint>) -> hes::Goal<formula::C
                 let g = translate(g).finalize_goal();
                 ExpandITEState::id(Goal::mk_univ(x.clone(), g))
             }
+            GoalKind::Exists(x, g) => {
+                let g = translate(g).finalize_goal();
+                ExpandITEState::id(Goal::mk_exists(x.clone(), g))
+            }
             GoalKind::Conj(c1, c2) => {
                 let c1 = match translate(c1) {
                     ExpandITEState::NotModified(c) => c,
@@ -6,17 +6,22 @@ mod extravar;
 mod find_ite;
 mod forall_pass;
 pub mod hes;
+#[deprecated(
+    note = "inlining and removal of disjunctions are built-in passes of `preprocess::hes`"
+)]
 pub mod hfl_preprocessor;
 mod inline_leaf_call;
+mod inlining;
 mod ite_expand;
 mod prenex_norm;
+mod remove_disj;
 mod remove_tmp_var;
 mod reorder_conj;
 mod reorder_disj;
 mod safety;
 mod simplify_constr_op;
 mod transform;
-mod typing;
+pub mod typing;
 mod unpack_constr;
 use crate::formula;
 use crate::formula::Type as SimpleType;
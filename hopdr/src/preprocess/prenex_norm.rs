@@ -11,7 +11,7 @@ type Goal = hes::Goal<Constraint>;
 fn append_quantifiers(quantifiers: Vec<(QuantifierKind, Variable)>, g: Goal) -> Goal {
     quantifiers.into_iter().rev().fold(g, |g, (q, x)| match q {
         QuantifierKind::Universal => Goal::mk_univ(x, g),
-        QuantifierKind::Existential => panic!("program error"),
+        QuantifierKind::Existential => Goal::mk_exists(x, g),
     })
 }
 
@@ -59,6 +59,17 @@ fn f(g: &Goal, mut env: formula::TyEnv) -> (Type, Vec<(QuantifierKind, Variable)
             v.push((QuantifierKind::Universal, x.clone()));
             (Type::mk_type_prop(), v, g)
         }
+        // universal quantifiers inside an existential one cannot be pulled out of it
+        GoalKind::Exists(x, g) => {
+            env.add(x.id, x.ty.clone());
+            let (_, v, g) = f(g, env);
+            let g = append_quantifiers(v, g);
+            (
+                Type::mk_type_prop(),
+                Vec::new(),
+                Goal::mk_exists(x.clone(), g),
+            )
+        }
         GoalKind::Conj(g1, g2) => {
             let (_, v1, g1) = f(g1, env.clone());
             let (_, v2, g2) = f(g2, env);
@@ -0,0 +1,79 @@
+//! Removes the disjunctions of two formulas neither of which is a constraint.
+//!
+//! `ψ₁ ∨ ψ₂` is replaced with `∃b. (b ≠ 0 ∨ ψ₁) ∧ (b = 0 ∨ ψ₂)`, which is equivalent to it
+//! (take b = 0 if ψ₁ holds, and b = 1 otherwise). After this pass, every disjunction has
+//! a constraint as one of its sides.
+//!
+//! `b` has type bit so that the checker only tries 0 and 1 as its witness.
+use super::TypedPreprocessor;
+use crate::formula::hes::{self, GoalKind};
+use crate::formula::{self, Constraint, Ident, Logic, Op, TyEnv, Type, Variable};
+
+pub struct RemoveDisjTransform {}
+
+type Goal = hes::Goal<formula::Constraint>;
+
+fn f(goal: &Goal) -> Goal {
+    match goal.kind() {
+        GoalKind::Constr(_) | GoalKind::Op(_) | GoalKind::Var(_) => goal.clone(),
+        GoalKind::Abs(x, g) => Goal::mk_abs(x.clone(), f(g)),
+        GoalKind::Univ(x, g) => Goal::mk_univ(x.clone(), f(g)),
+        GoalKind::Exists(x, g) => Goal::mk_exists(x.clone(), f(g)),
+        GoalKind::App(g1, g2) => Goal::mk_app(f(g1), f(g2)),
+        GoalKind::Conj(g1, g2) => Goal::mk_conj(f(g1), f(g2)),
+        GoalKind::ITE(c, g1, g2) => Goal::mk_ite(c.clone(), f(g1), f(g2)),
+        GoalKind::Disj(g1, g2) if g1.is_constr() || g2.is_constr() => Goal::mk_disj(f(g1), f(g2)),
+        GoalKind::Disj(g1, g2) => {
+            let b = Ident::fresh();
+            let zero = || Op::mk_const(0);
+            let g1 = Goal::mk_disj(
+                Goal::mk_constr(Constraint::mk_neq(Op::mk_var(b), zero())),
+                f(g1),
+            );
+            let g2 = Goal::mk_disj(
+                Goal::mk_constr(Constraint::mk_eq(Op::mk_var(b), zero())),
+                f(g2),
+            );
+            Goal::mk_exists(Variable::mk(b, Type::mk_type_bit()), Goal::mk_conj(g1, g2))
+        }
+    }
+}
+
+impl TypedPreprocessor for RemoveDisjTransform {
+    const PASS_NAME: &'static str = "remove disjunction";
+
+    fn transform_goal(&self, goal: &Goal, _t: &Type, _env: &mut TyEnv) -> Goal {
+        f(goal)
+    }
+}
+
+pub fn transform(problem: hes::Problem<formula::Constraint>) -> hes::Problem<formula::Constraint> {
+    crate::title!("remove_disjunction");
+    let t = RemoveDisjTransform {};
+    t.transform(problem)
+}
+
+#[test]
+fn test_remove_disj() {
+    // F x ∨ G x
+    let x = Ident::fresh();
+    let pred = || Goal::mk_var(Ident::fresh());
+    let g = Goal::mk_disj(
+        Goal::mk_app(pred(), Goal::mk_op(Op::mk_var(x))),
+        Goal::mk_app(pred(), Goal::mk_op(Op::mk_var(x))),
+    );
+    let g = f(&g);
+    println!("{g}");
+    let (b, g) = g.exists();
+    assert!(b.ty.is_bit());
+    let (g1, g2) = g.conj();
+    assert!(g1.disj().0.is_constr());
+    assert!(g2.disj().0.is_constr());
+
+    // x = 0 ∨ F x is left as it is
+    let g = Goal::mk_disj(
+        Goal::mk_constr(Constraint::mk_eq(Op::mk_var(x), Op::mk_const(0))),
+        Goal::mk_app(pred(), Goal::mk_op(Op::mk_var(x))),
+    );
+    assert_eq!(f(&g), g);
+}
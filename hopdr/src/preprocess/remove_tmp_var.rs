@@ -108,6 +108,16 @@ fn f(g: &Goal, tmp_vars: &mut HashSet<Ident>) -> Goal {
                 g
             }
         }
+        // the witness of x may depend on the temporary variables bound outside,
+        // so they must not be substituted inside its body.
+        GoalKind::Exists(x, g) => {
+            let g = f(g, &mut HashSet::new());
+            if g.fv().contains(&x.id) {
+                Goal::mk_exists_opt(x.clone(), g)
+            } else {
+                g
+            }
+        }
         GoalKind::ITE(c, g1, g2) => {
             let g1 = f(g1, tmp_vars);
             let g2 = f(g2, tmp_vars);
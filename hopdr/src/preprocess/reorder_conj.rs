@@ -100,6 +100,10 @@ impl TypedPreprocessor for ReorderConjTransform {
                 let g = self.transform_goal(g, t, env);
                 hes::Goal::mk_univ(x.clone(), g)
             }
+            hes::GoalKind::Exists(x, g) => {
+                let g = self.transform_goal(g, t, env);
+                hes::Goal::mk_exists(x.clone(), g)
+            }
             GoalKind::ITE(c, g1, g2) => {
                 let c = c.clone();
                 let g1 = self.transform_goal(g1, t, env);
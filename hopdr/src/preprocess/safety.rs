@@ -57,6 +57,7 @@ fn transform_goal(goal: &hes::Goal<formula::Constraint>) -> hes::Goal<formula::C
         GoalKind::Constr(_) | hes::GoalKind::Op(_) | GoalKind::Var(_) => goal.clone(),
         GoalKind::Abs(x, g) => Goal::mk_abs(x.clone(), f(g)),
         GoalKind::Univ(x, g) => Goal::mk_univ(x.clone(), f(g)),
+        GoalKind::Exists(x, g) => Goal::mk_exists(x.clone(), f(g)),
         GoalKind::App(g1, g2) => Goal::mk_app(f(g1), f(g2)),
         GoalKind::Conj(g1, g2) => Goal::mk_conj(f(g1), f(g2)),
         GoalKind::ITE(c, g1, g2) => Goal::mk_ite(c.clone(), f(g1), f(g2)),
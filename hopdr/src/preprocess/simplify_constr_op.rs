@@ -42,6 +42,10 @@ impl TypedPreprocessor for SimplifyConstrOpTransform {
                 let g = self.transform_goal(g, t, env);
                 hes::Goal::mk_univ_opt(x.clone(), g)
             }
+            hes::GoalKind::Exists(x, g) => {
+                let g = self.transform_goal(g, t, env);
+                hes::Goal::mk_exists_opt(x.clone(), g)
+            }
             hes::GoalKind::ITE(c, g1, g2) => {
                 let c = c.clone();
                 let g1 = self.transform_goal(g1, t, env);
@@ -146,6 +146,19 @@ fn transform_expr_inner(input: &InExpr, env: &mut HashMap<Ident, SimpleType>) ->
             }
             EitherExpr::mk_goal(Goal::mk_univ(x.clone().into(), y))
         }
+        Exists(x, y) => {
+            let old = env.insert(x.id, x.ty.clone());
+            let y = transform_expr_inner(y, env).goal();
+            match old {
+                Some(old) => {
+                    env.insert(x.id, old);
+                }
+                None => {
+                    env.remove(&x.id);
+                }
+            }
+            EitherExpr::mk_goal(Goal::mk_exists(x.clone().into(), y))
+        }
         Abs(x, y) => {
             let old = env.insert(x.id, x.ty.clone());
             let y = transform_expr_inner(y, env).goal();
@@ -193,5 +206,5 @@ fn append_args(mut body: InExpr, args: &[Ident], mut t: SimpleType) -> InExpr {
 fn transform_clause(input: InClause, mut env: HashMap<Ident, SimpleType>) -> OutClause {
     let input_expr = append_args(input.expr, &input.args, input.id.ty.clone());
     let body = transform_expr(&input_expr, &mut env);
-    OutClause::new(body, input.id.into())
+    OutClause::new_with_fixpoint(body, input.id.into(), input.fixpoint)
 }
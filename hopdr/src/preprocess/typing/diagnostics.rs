@@ -0,0 +1,71 @@
+//! Errors found by the simple type inference of HES equations.
+use crate::formula::Type as SimpleType;
+
+use std::fmt;
+
+/// name of the equation that the toplevel formula is put in during the inference
+pub(super) const TOPLEVEL: &str = "!!TOPLEVEL!!";
+
+#[derive(Debug, Clone)]
+pub enum Reason {
+    Mismatch {
+        expected: SimpleType,
+        inferred: SimpleType,
+    },
+    /// the type of the subexpression would contain itself
+    InfiniteType {
+        expected: SimpleType,
+        inferred: SimpleType,
+    },
+    UnboundVariable(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct TypeDiagnostic {
+    /// the name of the equation (or `None` for the toplevel formula)
+    pub equation: Option<String>,
+    /// the subexpression whose type is wrong
+    pub expr: String,
+    pub reason: Reason,
+}
+
+impl fmt::Display for TypeDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.equation {
+            Some(x) => writeln!(f, "type error in the equation of {}", x)?,
+            None => writeln!(f, "type error in the toplevel formula")?,
+        }
+        match &self.reason {
+            Reason::Mismatch { expected, inferred } => {
+                writeln!(f, "  {}", self.expr)?;
+                writeln!(f, "  expected: {}", expected)?;
+                write!(f, "  inferred: {}", inferred)
+            }
+            Reason::InfiniteType { expected, inferred } => {
+                writeln!(f, "  {}", self.expr)?;
+                writeln!(f, "  expected: {}", expected)?;
+                writeln!(f, "  inferred: {}", inferred)?;
+                write!(f, "  (the type would be infinite)")
+            }
+            Reason::UnboundVariable(x) => write!(f, "  unbound variable {}", x),
+        }
+    }
+}
+
+/// All the errors found in a problem
+#[derive(Debug, Clone)]
+pub struct Diagnostics(pub Vec<TypeDiagnostic>);
+
+impl fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, d) in self.0.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            writeln!(f, "{}", d)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Diagnostics {}
@@ -1,6 +1,11 @@
+mod diagnostics;
+
 use rpds::HashTrieMap;
 use std::{collections::HashMap, fmt};
 
+use diagnostics::TOPLEVEL;
+pub use diagnostics::{Diagnostics, Reason, TypeDiagnostic};
+
 use super::hes::{Clause as ClauseS, Expr, ExprKind, ValidityChecking, VariableS};
 use crate::formula::Type as SimpleType;
 use crate::parse;
@@ -61,37 +66,6 @@ impl TmpType {
     fn mk_prop() -> TmpType {
         TmpType::new(TmpTypeKind::Proposition)
     }
-    fn subst(&self, k: TypeVariable, t: TmpType) -> TmpType {
-        use TmpTypeKind::*;
-        match self.kind() {
-            Arrow(t1, t2) => TmpType::mk_arrow(t1.subst(k.clone(), t.clone()), t2.subst(k, t)),
-            Var(ty_var) if ty_var == &k => t,
-            _ => self.clone(),
-        }
-    }
-    fn occur(&self, v: &TypeVariable) -> bool {
-        match self.kind() {
-            TmpTypeKind::Arrow(t1, t2) => t1.occur(v) || t2.occur(v),
-            TmpTypeKind::Var(x) if x == v => true,
-            _ => false,
-        }
-    }
-    fn force(&self) -> SimpleType {
-        use TmpTypeKind::*;
-        match self.kind() {
-            Proposition => SimpleType::mk_type_prop(),
-            Integer => SimpleType::mk_type_int(),
-            Arrow(t1, t2) => SimpleType::mk_type_arrow(t1.force(), t2.force()),
-            Var(ty_var) => {
-                warn!(
-                    "{} is not constrained in the process of type checking",
-                    ty_var
-                );
-                warn!("{} is regarded as prop", ty_var);
-                SimpleType::mk_type_prop()
-            }
-        }
-    }
 }
 
 impl VariableS<parse::Ident, TmpType> {
@@ -140,60 +114,68 @@ impl ExprTmp {
                 let id = VariableS::mk(x, t);
                 Expr::mk_univ(id, ExprTmp::from(e))
             }
+            parse::ExprKind::Exist(x, e) => {
+                let t = TmpType::mk_int();
+                let id = VariableS::mk(x, t);
+                Expr::mk_exists(id, ExprTmp::from(e))
+            }
             parse::ExprKind::Abs(x, e) => {
                 let id = VariableS::from_ident(x);
                 Expr::mk_abs(id, ExprTmp::from(e))
             }
-            parse::ExprKind::Exist(_, _) | parse::ExprKind::Fix(_, _, _) => {
+            parse::ExprKind::Fix(_, _, _) => {
                 panic!("not implemented")
             }
         }
     }
+    /// returns the type of `self`, and adds the constraints of the form
+    /// `inferred type = expected type` of its subexpressions
     fn append_constraints<'a>(
         &'a self,
         env: &mut Environment<'a>,
-        constraints: &mut Constraints,
+        constraints: &mut Constraints<'a>,
     ) -> TmpType {
         match self.kind() {
-            ExprKind::Var(ident) => env
-                .get(ident)
-                .unwrap_or_else(|| panic!("not found {}", ident)),
+            ExprKind::Var(ident) => env.get(ident).unwrap_or_else(|| {
+                constraints.unbound(ident);
+                TmpType::fresh_type_variable()
+            }),
             ExprKind::Num(_) => TmpType::mk_int(),
             ExprKind::True | ExprKind::False => TmpType::mk_prop(),
             ExprKind::Op(_, e1, e2) => {
                 let t1 = e1.append_constraints(env, constraints);
                 let t2 = e2.append_constraints(env, constraints);
-                constraints.add(t1, env.mk_int());
-                constraints.add(t2, env.mk_int());
+                constraints.add(t1, env.mk_int(), Subject::Expr(e1));
+                constraints.add(t2, env.mk_int(), Subject::Expr(e2));
                 env.mk_int()
             }
             ExprKind::Pred(_, e1, e2) => {
                 let t1 = e1.append_constraints(env, constraints);
                 let t2 = e2.append_constraints(env, constraints);
-                constraints.add(t1, env.mk_int());
-                constraints.add(t2, env.mk_int());
+                constraints.add(t1, env.mk_int(), Subject::Expr(e1));
+                constraints.add(t2, env.mk_int(), Subject::Expr(e2));
                 env.mk_prop()
             }
             ExprKind::App(e1, e2) => {
                 let t1 = e1.append_constraints(env, constraints);
                 let t2 = e2.append_constraints(env, constraints);
                 let ret_t = TmpType::fresh_type_variable();
-                constraints.add(t1, TmpType::mk_arrow(t2, ret_t.clone()));
+                constraints.add(t1, TmpType::mk_arrow(t2, ret_t.clone()), Subject::Expr(e1));
                 ret_t
             }
             ExprKind::And(e1, e2) | ExprKind::Or(e1, e2) => {
                 let t1 = e1.append_constraints(env, constraints);
                 let t2 = e2.append_constraints(env, constraints);
-                constraints.add(t1, env.mk_prop());
-                constraints.add(t2, env.mk_prop());
+                constraints.add(t1, env.mk_prop(), Subject::Expr(e1));
+                constraints.add(t2, env.mk_prop(), Subject::Expr(e2));
                 env.mk_prop()
             }
-            ExprKind::Univ(x, e) => {
+            ExprKind::Univ(x, e) | ExprKind::Exists(x, e) => {
                 let old = env.clone();
                 env.add(&x.id, env.mk_int());
                 let t = e.append_constraints(env, constraints);
                 *env = old;
-                constraints.add(t, env.mk_prop());
+                constraints.add(t, env.mk_prop(), Subject::Expr(e));
                 env.mk_prop()
             }
             ExprKind::Abs(x, e) => {
@@ -242,6 +224,12 @@ impl ExprTmp {
                 let v = VariableS::new(v.id, ty);
                 ExprSimpleType::mk_univ(v, x)
             }
+            ExprKind::Exists(v, x) => {
+                let x = x.ty_subst(subst);
+                let ty = subst.subst(v.ty);
+                let v = VariableS::new(v.id, ty);
+                ExprSimpleType::mk_exists(v, x)
+            }
             ExprKind::Abs(v, x) => {
                 let x = x.ty_subst(subst);
                 let ty = subst.subst(v.ty);
@@ -260,9 +248,15 @@ impl Clause<TmpType> {
             id,
             args: vc.args,
             expr,
+            fixpoint: vc.fixpoint,
         }
     }
-    fn append_constraints<'a>(&'a self, mut env: Environment<'a>, constraints: &mut Constraints) {
+    fn append_constraints<'a>(
+        &'a self,
+        mut env: Environment<'a>,
+        constraints: &mut Constraints<'a>,
+    ) {
+        constraints.equation = &self.id.id;
         let ret_ty = TmpType::fresh_type_variable();
         let mut current_ty = ret_ty.clone();
         for arg in self.args.iter().rev() {
@@ -270,11 +264,11 @@ impl Clause<TmpType> {
             current_ty = TmpType::mk_arrow(arg_ty.clone(), current_ty);
             env.add(arg, arg_ty);
         }
-        constraints.add(current_ty, self.id.ty.clone());
+        constraints.add(current_ty, self.id.ty.clone(), Subject::Head(self));
         debug!("{}", &env);
         debug!("{}", self);
         let t = self.expr.append_constraints(&mut env, constraints);
-        constraints.add(t, ret_ty)
+        constraints.add(t, ret_ty, Subject::Expr(&self.expr))
     }
 }
 
@@ -323,31 +317,40 @@ fn generate_global_environment(formulas: &[Clause<TmpType>]) -> Environment {
     env
 }
 
-struct Constraint {
-    left: TmpType,
-    right: TmpType,
+/// the subexpression that a constraint comes from
+#[derive(Clone, Copy)]
+enum Subject<'a> {
+    Expr(&'a ExprTmp),
+    /// the left hand side of an equation
+    Head(&'a Clause<TmpType>),
 }
 
-impl fmt::Display for Constraint {
+impl fmt::Display for Subject<'_> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{} = {}", self.left, self.right)
+        match self {
+            Subject::Expr(e) => write!(f, "{}", e),
+            Subject::Head(c) => {
+                write!(f, "{}", c.id.id)?;
+                for arg in c.args.iter() {
+                    write!(f, " {}", arg)?;
+                }
+                Ok(())
+            }
+        }
     }
 }
 
-impl Constraint {
-    fn new(left: TmpType, right: TmpType) -> Constraint {
-        Constraint { left, right }
-    }
-    fn kind(&self) -> (&TmpTypeKind, &TmpTypeKind) {
-        let left = self.left.kind();
-        let right = self.right.kind();
-        (left, right)
-    }
-    fn subst(&self, x: TypeVariable, t: TmpType) -> Constraint {
-        Constraint::new(
-            self.left.subst(x.clone(), t.clone()),
-            self.right.subst(x, t),
-        )
+/// `left` (the inferred type of `subject`) = `right` (its expected type)
+struct Constraint<'a> {
+    left: TmpType,
+    right: TmpType,
+    equation: &'a str,
+    subject: Subject<'a>,
+}
+
+impl fmt::Display for Constraint<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} = {}", self.left, self.right)
     }
 }
 
@@ -357,71 +360,85 @@ enum TypeError {
     OccurenceCheck,
 }
 
-struct Constraints(Vec<Constraint>);
+struct Constraints<'a> {
+    constraints: Vec<Constraint<'a>>,
+    /// the equation whose constraints are being added
+    equation: &'a str,
+    unbound: Vec<TypeDiagnostic>,
+}
 
-impl fmt::Display for Constraints {
+impl fmt::Display for Constraints<'_> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         writeln!(f, "[constraints]")?;
-        for c in self.0.iter() {
+        for c in self.constraints.iter() {
             writeln!(f, "{}", c)?;
         }
         writeln!(f)
     }
 }
 
-impl Constraints {
-    fn new() -> Constraints {
-        Constraints(Vec::new())
+fn equation_name(equation: &str) -> Option<String> {
+    if equation == TOPLEVEL {
+        None
+    } else {
+        Some(equation.to_string())
     }
-    fn add(&mut self, left: TmpType, right: TmpType) {
-        self.0.push(Constraint::new(left, right))
-    }
-    fn subst(&mut self, x: TypeVariable, t: TmpType) {
-        let x = &x;
-        let t = &t;
-        for c in self.0.iter_mut() {
-            *c = c.subst(x.clone(), t.clone())
+}
+
+impl<'a> Constraints<'a> {
+    fn new() -> Constraints<'a> {
+        Constraints {
+            constraints: Vec::new(),
+            equation: TOPLEVEL,
+            unbound: Vec::new(),
         }
     }
-    fn solve(&mut self) -> Result<TySubst, TypeError> {
+    fn add(&mut self, left: TmpType, right: TmpType, subject: Subject<'a>) {
+        self.constraints.push(Constraint {
+            left,
+            right,
+            equation: self.equation,
+            subject,
+        })
+    }
+    fn unbound(&mut self, x: &str) {
+        self.unbound.push(TypeDiagnostic {
+            equation: equation_name(self.equation),
+            expr: x.to_string(),
+            reason: Reason::UnboundVariable(x.to_string()),
+        })
+    }
+    /// Solves the constraints in the order they are added. A constraint that contradicts
+    /// the previous ones is reported and ignored, so that the errors in different
+    /// equations are found at once.
+    fn solve(self) -> (TySubst, Vec<TypeDiagnostic>) {
         let mut ty_subst = TySubst::new();
-        loop {
-            use TmpTypeKind::*;
-            match self.0.pop() {
-                None => break Ok(ty_subst),
-                Some(c) => {
-                    let rhs = c.right.clone();
-                    debug!("unify {} = {}", c.left, c.right);
-                    match c.kind() {
-                        (Proposition, Proposition) | (Integer, Integer) => {}
-                        (Var(x), Var(y)) if x == y => {}
-                        (Var(x), _) => {
-                            if rhs.occur(x) {
-                                break Err(TypeError::OccurenceCheck);
-                            }
-                            self.subst(x.clone(), rhs.clone());
-                            ty_subst.add(x.clone(), rhs);
-                        }
-                        (_, Var(_)) => {
-                            let lhs = c.left;
-                            let rhs = c.right;
-                            self.add(rhs, lhs);
-                        }
-                        (Arrow(t1, s1), Arrow(t2, s2)) => {
-                            self.add(t1.clone(), t2.clone());
-                            self.add(s1.clone(), s2.clone());
-                        }
-                        _ => {
-                            debug!("tried to unify {} = {}", c.left, c.right);
-                            break Err(TypeError::Error);
-                        }
-                    }
-                }
+        let mut diagnostics = self.unbound;
+        for c in self.constraints {
+            debug!("unify {} = {}", c.left, c.right);
+            let mut trail = Vec::new();
+            if let Err(e) = ty_subst.unify(c.left.clone(), c.right.clone(), &mut trail) {
+                debug!("tried to unify {} = {}", c.left, c.right);
+                ty_subst.undo(trail);
+                let expected = ty_subst.subst_lossy(&c.right);
+                let inferred = ty_subst.subst_lossy(&c.left);
+                let reason = match e {
+                    TypeError::Error => Reason::Mismatch { expected, inferred },
+                    TypeError::OccurenceCheck => Reason::InfiniteType { expected, inferred },
+                };
+                diagnostics.push(TypeDiagnostic {
+                    equation: equation_name(c.equation),
+                    expr: c.subject.to_string(),
+                    reason,
+                });
             }
         }
+        (ty_subst, diagnostics)
     }
 }
 
+/// Triangular substitution: the type assigned to a type variable may contain type
+/// variables assigned later, which makes it easy to undo a failed unification.
 #[derive(Debug)]
 struct TySubst(HashMap<TypeVariable, TmpType>);
 
@@ -429,65 +446,193 @@ impl TySubst {
     fn new() -> TySubst {
         TySubst(HashMap::new())
     }
-    fn add(&mut self, k: TypeVariable, t: TmpType) {
-        // compose two substitutions self∘[k:=t]
-        {
-            let k = &k;
-            let t = &t;
-            for (_, y) in self.0.iter_mut() {
-                *y = y.subst(k.clone(), t.clone());
+    /// resolves the type variable at the top of `t`
+    fn walk(&self, t: &TmpType) -> TmpType {
+        let mut t = t.clone();
+        while let TmpTypeKind::Var(x) = t.kind() {
+            match self.0.get(x) {
+                Some(t2) => t = t2.clone(),
+                None => break,
             }
         }
-        self.0.insert(k, t);
+        t
+    }
+    fn occur(&self, x: &TypeVariable, t: &TmpType) -> bool {
+        match self.walk(t).kind() {
+            TmpTypeKind::Arrow(t1, t2) => self.occur(x, t1) || self.occur(x, t2),
+            TmpTypeKind::Var(y) => x == y,
+            _ => false,
+        }
+    }
+    fn assign(
+        &mut self,
+        x: &TypeVariable,
+        t: TmpType,
+        trail: &mut Vec<TypeVariable>,
+    ) -> Result<(), TypeError> {
+        if self.occur(x, &t) {
+            return Err(TypeError::OccurenceCheck);
+        }
+        self.0.insert(x.clone(), t);
+        trail.push(x.clone());
+        Ok(())
+    }
+    /// the type variables assigned during the unification are pushed to `trail`
+    fn unify(
+        &mut self,
+        left: TmpType,
+        right: TmpType,
+        trail: &mut Vec<TypeVariable>,
+    ) -> Result<(), TypeError> {
+        use TmpTypeKind::*;
+        let left = self.walk(&left);
+        let right = self.walk(&right);
+        match (left.kind(), right.kind()) {
+            (Proposition, Proposition) | (Integer, Integer) => Ok(()),
+            (Var(x), Var(y)) if x == y => Ok(()),
+            (Var(x), _) => self.assign(x, right.clone(), trail),
+            (_, Var(y)) => self.assign(y, left.clone(), trail),
+            (Arrow(t1, s1), Arrow(t2, s2)) => {
+                self.unify(t1.clone(), t2.clone(), trail)?;
+                self.unify(s1.clone(), s2.clone(), trail)
+            }
+            _ => Err(TypeError::Error),
+        }
+    }
+    fn undo(&mut self, trail: Vec<TypeVariable>) {
+        for x in trail {
+            self.0.remove(&x);
+        }
+    }
+    /// same as `subst` except that it does not warn about the undetermined type variables
+    fn subst_lossy(&self, t: &TmpType) -> SimpleType {
+        match self.walk(t).kind() {
+            TmpTypeKind::Integer => SimpleType::mk_type_int(),
+            TmpTypeKind::Arrow(t1, t2) => {
+                SimpleType::mk_type_arrow(self.subst_lossy(t1), self.subst_lossy(t2))
+            }
+            TmpTypeKind::Proposition | TmpTypeKind::Var(_) => SimpleType::mk_type_prop(),
+        }
     }
     fn subst(&self, t: TmpType) -> SimpleType {
-        match t.kind() {
+        match self.walk(&t).kind() {
             TmpTypeKind::Proposition => SimpleType::mk_type_prop(),
             TmpTypeKind::Integer => SimpleType::mk_type_int(),
             TmpTypeKind::Arrow(t1, t2) => {
                 SimpleType::mk_type_arrow(self.subst(t1.clone()), self.subst(t2.clone()))
             }
-            TmpTypeKind::Var(ty_var) => match self.0.get(ty_var) {
-                Some(t) => t.force(),
-                None => {
-                    warn!(
-                        "{} is not constrained in the process of type checking",
-                        ty_var
-                    );
-                    warn!("{} is regarded as prop", ty_var);
-                    SimpleType::mk_type_prop()
-                }
-            },
+            TmpTypeKind::Var(ty_var) => {
+                warn!(
+                    "{} is not constrained in the process of type checking",
+                    ty_var
+                );
+                warn!("{} is regarded as prop", ty_var);
+                SimpleType::mk_type_prop()
+            }
         }
     }
 }
 
-pub fn typing(
-    mut formulas: Vec<parse::Clause>,
-    toplevel: parse::Expr,
-) -> ValidityChecking<parse::Ident, SimpleType> {
-    // adhoc
-    crate::stat::preprocess::start_clock("typing");
-
+fn to_tmp_clauses(mut formulas: Vec<parse::Clause>, toplevel: parse::Expr) -> Vec<Clause<TmpType>> {
     formulas.push(parse::Clause {
-        id: "!!TOPLEVEL!!".to_string(),
+        id: TOPLEVEL.to_string(),
         args: Vec::new(),
         expr: toplevel,
         fixpoint: parse::Fixpoint::Greatest,
     });
-
-    let formulas = formulas
+    formulas
         .into_iter()
         .map(Clause::<TmpType>::from)
-        .collect::<Vec<_>>();
-    let ty_subst = {
-        let env = generate_global_environment(&formulas);
-        let mut constraints = Constraints::new();
-        for clause in formulas.iter() {
-            clause.append_constraints(env.clone(), &mut constraints);
+        .collect::<Vec<_>>()
+}
+
+/// collects the variables that occur in `e`
+fn collect_vars<'a>(e: &'a ExprTmp, vars: &mut Vec<&'a str>) {
+    match e.kind() {
+        ExprKind::Var(x) => vars.push(x),
+        ExprKind::Num(_) | ExprKind::True | ExprKind::False => (),
+        ExprKind::Op(_, e1, e2)
+        | ExprKind::Pred(_, e1, e2)
+        | ExprKind::App(e1, e2)
+        | ExprKind::And(e1, e2)
+        | ExprKind::Or(e1, e2) => {
+            collect_vars(e1, vars);
+            collect_vars(e2, vars);
+        }
+        ExprKind::Univ(_, e) | ExprKind::Exists(_, e) | ExprKind::Abs(_, e) => {
+            collect_vars(e, vars)
+        }
+    }
+}
+
+/// Returns the order in which the constraints of the equations are solved: each equation
+/// comes after those it refers to, except in recursion. Since a constraint contradicting
+/// the previous ones is blamed, the misuse of a predicate is then reported in the equation
+/// that uses it rather than the one that defines it.
+fn callee_first_order(formulas: &[Clause<TmpType>]) -> Vec<usize> {
+    fn visit(
+        idx: usize,
+        formulas: &[Clause<TmpType>],
+        indices: &HashMap<&str, usize>,
+        visited: &mut Vec<bool>,
+        order: &mut Vec<usize>,
+    ) {
+        if visited[idx] {
+            return;
+        }
+        visited[idx] = true;
+        let mut vars = Vec::new();
+        collect_vars(&formulas[idx].expr, &mut vars);
+        for x in vars {
+            if let Some(callee) = indices.get(x) {
+                visit(*callee, formulas, indices, visited, order);
+            }
+        }
+        order.push(idx);
+    }
+    let indices: HashMap<&str, usize> = formulas
+        .iter()
+        .enumerate()
+        .map(|(idx, clause)| (clause.id.id.as_str(), idx))
+        .collect();
+    let mut visited = vec![false; formulas.len()];
+    let mut order = Vec::new();
+    for idx in 0..formulas.len() {
+        visit(idx, formulas, &indices, &mut visited, &mut order);
+    }
+    order
+}
+
+fn infer(formulas: &[Clause<TmpType>]) -> Result<TySubst, Diagnostics> {
+    let env = generate_global_environment(formulas);
+    let mut constraints = Constraints::new();
+    for idx in callee_first_order(formulas) {
+        formulas[idx].append_constraints(env.clone(), &mut constraints);
+    }
+    debug!("{}", constraints);
+    let (ty_subst, diagnostics) = constraints.solve();
+    if diagnostics.is_empty() {
+        Ok(ty_subst)
+    } else {
+        Err(Diagnostics(diagnostics))
+    }
+}
+
+pub fn typing(
+    formulas: Vec<parse::Clause>,
+    toplevel: parse::Expr,
+) -> Result<ValidityChecking<parse::Ident, SimpleType>, Diagnostics> {
+    // adhoc
+    crate::stat::preprocess::start_clock("typing");
+
+    let formulas = to_tmp_clauses(formulas, toplevel);
+    let ty_subst = infer(&formulas);
+    let ty_subst = match ty_subst {
+        Ok(ty_subst) => ty_subst,
+        Err(e) => {
+            crate::stat::preprocess::end_clock("typing");
+            return Err(e);
         }
-        debug!("{}", constraints);
-        constraints.solve().unwrap()
     };
     let mut formulas: Vec<Clause<SimpleType>> = formulas
         .into_iter()
@@ -502,6 +647,7 @@ pub fn typing(
                 id,
                 expr,
                 args: clause.args,
+                fixpoint: clause.fixpoint,
             }
         })
         .collect();
@@ -509,8 +655,29 @@ pub fn typing(
     let toplevel = formulas.pop().unwrap().expr;
     crate::stat::preprocess::end_clock("typing");
 
-    ValidityChecking {
+    Ok(ValidityChecking {
         clauses: formulas,
         toplevel,
-    }
+    })
+}
+
+#[test]
+fn test_type_diagnostics() {
+    use nom::error::VerboseError;
+    let s = "
+    %HES
+    S =v F 1 && G 0.
+    F x =v x && true.
+    G x =v H x 1.
+    H x =v x > 0.
+    ";
+    let (_, f) = parse::parse::<VerboseError<&str>>(s).unwrap();
+    let parse::Problem::NuHFLZValidityChecking(vc) = f;
+    let e = typing(vc.formulas, vc.toplevel).err().unwrap();
+    println!("{}", e);
+    // `F 1` in the toplevel, and `H` with two arguments in G
+    let equations: Vec<_> = e.0.iter().map(|d| d.equation.clone()).collect();
+    assert_eq!(equations.len(), 2);
+    assert!(equations.contains(&None));
+    assert!(equations.contains(&Some("G".to_string())));
 }
@@ -61,6 +61,10 @@ fn transform_goal(g: &Goal) -> Goal {
             let g = transform_goal(g);
             Goal::mk_univ(x.clone(), g)
         }
+        GoalKind::Exists(x, g) => {
+            let g = transform_goal(g);
+            Goal::mk_exists(x.clone(), g)
+        }
         GoalKind::ITE(c, g1, g2) => {
             let c = c.clone();
             let g1 = transform_goal(g1);
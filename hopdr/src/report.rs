@@ -0,0 +1,117 @@
+//! Machine-readable summary of a run, which the binaries print with `--output json`.
+//!
+//! ```json
+//! {"verdict":"valid","engine":"HoPDR","elapsed":1.23,
+//!  "phases":{"preprocess":0.01,"smt":0.5,"chc":0.3,"qe":0.0},
+//!  "certificate":"...","trace":null,"reason":null,"seed":null}
+//! ```
+use crate::stat;
+
+use serde::Serialize;
+
+use std::fmt;
+use std::time::Instant;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Verdict {
+    Valid,
+    Invalid,
+    Unknown,
+    /// the solver failed (see `reason`)
+    Fail,
+    Timeout,
+    /// interrupted by Ctrl-C
+    Terminated,
+    ParseError,
+    TypeError,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+    pub verdict: Verdict,
+    /// the engine that gave the verdict
+    pub engine: Option<String>,
+    /// wall-clock time of the whole run (sec)
+    pub elapsed: f64,
+    pub phases: stat::PhaseTimes,
+    /// the certificate of a valid problem in the format accepted by `verify-certificate`
+    pub certificate: Option<String>,
+    /// the counterexample or the trace of an invalid problem
+    pub trace: Option<String>,
+    /// why the verdict is `fail`, `parse_error` or `type_error`
+    pub reason: Option<String>,
+    /// the seed of the random testing of ModeTrans, with which `--seed` replays it
+    pub seed: Option<u64>,
+}
+
+impl Report {
+    pub fn new(verdict: Verdict) -> Report {
+        Report {
+            verdict,
+            engine: None,
+            elapsed: 0.0,
+            phases: stat::PhaseTimes::default(),
+            certificate: None,
+            trace: None,
+            reason: None,
+            seed: None,
+        }
+    }
+
+    /// set the engine that gave the verdict
+    pub fn engine(mut self, engine: impl fmt::Display) -> Self {
+        self.engine = Some(engine.to_string());
+        self
+    }
+
+    /// set the certificate
+    pub fn certificate(mut self, certificate: String) -> Self {
+        self.certificate = Some(certificate);
+        self
+    }
+
+    /// set the counterexample or the trace
+    pub fn trace(mut self, trace: Option<String>) -> Self {
+        self.trace = trace;
+        self
+    }
+
+    /// set the reason of the failure
+    pub fn reason(mut self, reason: impl fmt::Display) -> Self {
+        self.reason = Some(reason.to_string());
+        self
+    }
+
+    /// set the seed of the random testing
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// records the elapsed time since `start` and that of each phase.
+    ///
+    /// `stat::finalize` should be called before this.
+    pub fn finish(mut self, start: Instant) -> Self {
+        self.elapsed = start.elapsed().as_secs_f64();
+        self.phases = stat::phase_times();
+        self
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+#[test]
+fn test_report_to_json() {
+    let r = Report::new(Verdict::ParseError)
+        .reason("expected '.'")
+        .seed(42);
+    let v: serde_json::Value = serde_json::from_str(&r.to_json()).unwrap();
+    assert_eq!(v["verdict"], "parse_error");
+    assert_eq!(v["reason"], "expected '.'");
+    assert_eq!(v["seed"], 42);
+    assert!(v["engine"].is_null());
+    assert!(v["phases"]["smt"].is_number());
+}
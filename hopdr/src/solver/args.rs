@@ -0,0 +1,93 @@
+//! Command line options of the solvers, which `hopdr` and `check` share by flattening
+//! `SolverArgs` into their arguments.
+use super::qe::QESolverType;
+use super::{SMTSolverType, SolverRegistry, SolverTimeouts};
+
+use std::time::Duration;
+
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct SolverArgs {
+    /// SMT solver used by default (z3 or z3lib)
+    #[clap(long)]
+    pub smt_solver: Option<String>,
+    /// Solver of quantifier elimination (z3, ultimate_eliminator, or native for Cooper's
+    /// method alone)
+    #[clap(long)]
+    pub qe_solver: Option<String>,
+    /// Time limit (sec) of each SMT solver call
+    #[clap(long)]
+    pub smt_timeout: Option<u64>,
+    /// Time limit (sec) of each CHC solver call
+    #[clap(long)]
+    pub chc_timeout: Option<u64>,
+    /// Time limit (sec) of each quantifier elimination call
+    #[clap(long)]
+    pub qe_timeout: Option<u64>,
+    /// Time limit (sec) of each interpolation solver call
+    #[clap(long)]
+    pub interpolation_timeout: Option<u64>,
+    /// File in which the results of solver calls are cached across runs
+    #[clap(long)]
+    pub solver_cache: Option<String>,
+    /// Disables the cache of the results of solver calls
+    #[clap(long)]
+    pub no_solver_cache: bool,
+}
+
+impl SolverArgs {
+    pub fn timeouts(&self) -> SolverTimeouts {
+        let secs = |t: Option<u64>| t.map(Duration::from_secs);
+        SolverTimeouts {
+            smt: secs(self.smt_timeout),
+            chc: secs(self.chc_timeout),
+            qe: secs(self.qe_timeout),
+            interpolation: secs(self.interpolation_timeout),
+        }
+    }
+
+    /// returns the registry with the solvers and the time limits of the options.
+    /// Panics on an unknown solver name.
+    pub fn registry(&self) -> SolverRegistry {
+        let solvers = SolverRegistry::new()
+            .timeouts(self.timeouts())
+            .cache(!self.no_solver_cache);
+        let solvers = match &self.smt_solver {
+            Some(s) => match SMTSolverType::from_name(s) {
+                Some(sol) => solvers.smt(sol),
+                None => panic!("Unknown SMT solver: {}", s),
+            },
+            None => solvers,
+        };
+        match &self.qe_solver {
+            Some(s) => match QESolverType::from_name(s) {
+                Some(sol) => solvers.qe(sol),
+                None => panic!("Unknown QE solver: {}", s),
+            },
+            None => solvers,
+        }
+    }
+
+    /// loads the file of `--solver-cache` into the cache if it exists
+    pub fn load_cache(&self) {
+        match &self.solver_cache {
+            Some(path) if !self.no_solver_cache && std::path::Path::new(path).exists() => {
+                if let Err(e) = super::cache::load(path) {
+                    warn!("failed to load the solver cache: {}", e)
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// saves the cache to the file of `--solver-cache`
+    pub fn save_cache(&self) {
+        match &self.solver_cache {
+            Some(path) if !self.no_solver_cache => {
+                if let Err(e) = super::cache::save(path) {
+                    warn!("failed to save the solver cache: {}", e)
+                }
+            }
+            _ => (),
+        }
+    }
+}
@@ -0,0 +1,599 @@
+//! Cache of the results of SMT, QE and interpolation queries.
+//!
+//! The same queries are issued many times across PDR iterations, often with different
+//! variable names. A query is keyed on its alpha-normalized form: free variables are
+//! numbered in the order of their first occurrence, and bound variables by their de Bruijn
+//! index. Two queries share an entry iff they are alpha-equivalent (up to the order of
+//! occurrences), and a cached answer is translated back to the variables of the query.
+//!
+//! Only definite answers are cached (`Unknown` and `Timeout` are retried), and the cache
+//! can be saved to a file and loaded at the next run so that repeated benchmark runs are
+//! faster. SMT answers are also keyed on the solver that gave them, since an incomplete
+//! one (`AutoSolver`) may answer `Unsat` to a satisfiable query. Interpolants are cached
+//! regardless of the interpolation solver that computed them; any of them is a valid
+//! answer.
+use super::smt::SMTSolver;
+use super::{Model, SMTSolverType, SolverResult};
+use crate::formula::chc::{self, Atom, CHCHead};
+use crate::formula::{
+    Bot, Constraint, ConstraintExpr, FirstOrderLogic, Ident, Logic, Op, OpExpr, OpKind, PredKind,
+    QuantifierKind, Top,
+};
+use crate::stat::cache::{hit, miss, Query};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::Mutex;
+
+type CHC = chc::CHC<Atom, Constraint>;
+
+#[derive(Debug, Error)]
+pub enum CacheError {
+    #[error("failed to access the cache file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("malformed cache file: {0}")]
+    Format(#[from] serde_json::Error),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+enum Var {
+    Free(usize),
+    Bound(usize),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+enum Term {
+    Var(Var),
+    Const(i64),
+    Op(OpKind, Box<Term>, Box<Term>),
+    Ite(Box<Formula>, Box<Term>, Box<Term>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+enum Formula {
+    True,
+    False,
+    Pred(PredKind, Vec<Term>),
+    Conj(Box<Formula>, Box<Formula>),
+    Disj(Box<Formula>, Box<Formula>),
+    Quantifier(QuantifierKind, Box<Formula>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+struct NormalizedAtom {
+    predicate: usize,
+    args: Vec<Term>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+enum NormalizedHead {
+    Constraint(Formula),
+    Predicate(NormalizedAtom),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+struct NormalizedCHC {
+    predicates: Vec<NormalizedAtom>,
+    constraint: Formula,
+    head: NormalizedHead,
+}
+
+/// Renames variables to their canonical names.
+#[derive(Default)]
+struct Normalizer {
+    free: Vec<Ident>,
+    free_map: HashMap<Ident, usize>,
+    bound: Vec<Ident>,
+}
+
+impl Normalizer {
+    /// normalizer in which `args` are already registered as the first free variables
+    fn with_args(args: &[Ident]) -> Normalizer {
+        let mut n = Normalizer::default();
+        for x in args {
+            n.var(x);
+        }
+        n
+    }
+
+    fn var(&mut self, x: &Ident) -> Var {
+        if let Some(i) = self.bound.iter().rev().position(|y| y == x) {
+            return Var::Bound(i);
+        }
+        let free = &mut self.free;
+        let i = *self.free_map.entry(*x).or_insert_with(|| {
+            free.push(*x);
+            free.len() - 1
+        });
+        Var::Free(i)
+    }
+
+    fn op(&mut self, o: &Op) -> Term {
+        match o.kind() {
+            OpExpr::Op(k, o1, o2) => Term::Op(*k, Box::new(self.op(o1)), Box::new(self.op(o2))),
+            OpExpr::Var(x) => Term::Var(self.var(x)),
+            OpExpr::Const(c) => Term::Const(*c),
+            OpExpr::ITE(c, o1, o2) => Term::Ite(
+                Box::new(self.constraint(c)),
+                Box::new(self.op(o1)),
+                Box::new(self.op(o2)),
+            ),
+            OpExpr::Ptr(_, o) => self.op(o),
+        }
+    }
+
+    fn constraint(&mut self, c: &Constraint) -> Formula {
+        match c.kind() {
+            ConstraintExpr::True => Formula::True,
+            ConstraintExpr::False => Formula::False,
+            ConstraintExpr::Pred(p, l) => Formula::Pred(*p, l.iter().map(|o| self.op(o)).collect()),
+            ConstraintExpr::Conj(c1, c2) => {
+                Formula::Conj(Box::new(self.constraint(c1)), Box::new(self.constraint(c2)))
+            }
+            ConstraintExpr::Disj(c1, c2) => {
+                Formula::Disj(Box::new(self.constraint(c1)), Box::new(self.constraint(c2)))
+            }
+            ConstraintExpr::Quantifier(q, x, c) => {
+                self.bound.push(x.id);
+                let c = self.constraint(c);
+                self.bound.pop();
+                Formula::Quantifier(*q, Box::new(c))
+            }
+        }
+    }
+
+    fn atom(&mut self, a: &Atom, predicates: &mut Normalizer) -> NormalizedAtom {
+        let predicate = match predicates.var(&a.predicate) {
+            Var::Free(i) => i,
+            Var::Bound(_) => unreachable!(),
+        };
+        let args = a.args.iter().map(|o| self.op(o)).collect();
+        NormalizedAtom { predicate, args }
+    }
+}
+
+/// Translates normalized formulas back, where free variables are `free`, and bound
+/// variables are fresh.
+struct Denormalizer<'a> {
+    free: &'a [Ident],
+    bound: Vec<Ident>,
+}
+
+impl<'a> Denormalizer<'a> {
+    fn new(free: &'a [Ident]) -> Denormalizer<'a> {
+        Denormalizer {
+            free,
+            bound: Vec::new(),
+        }
+    }
+
+    // `None` if the formula refers to a variable out of scope, which only happens when the
+    // cache file is broken.
+    fn var(&self, v: &Var) -> Option<Ident> {
+        match v {
+            Var::Free(i) => self.free.get(*i).copied(),
+            Var::Bound(i) => self.bound.iter().rev().nth(*i).copied(),
+        }
+    }
+
+    fn op(&mut self, t: &Term) -> Option<Op> {
+        let o = match t {
+            Term::Var(v) => Op::mk_var(self.var(v)?),
+            Term::Const(c) => Op::mk_const(*c),
+            Term::Op(k, t1, t2) => Op::mk_bin_op(*k, self.op(t1)?, self.op(t2)?),
+            Term::Ite(c, t1, t2) => Op::mk_ite(self.constraint(c)?, self.op(t1)?, self.op(t2)?),
+        };
+        Some(o)
+    }
+
+    fn constraint(&mut self, f: &Formula) -> Option<Constraint> {
+        let c = match f {
+            Formula::True => Constraint::mk_true(),
+            Formula::False => Constraint::mk_false(),
+            Formula::Pred(p, l) => {
+                let l = l.iter().map(|t| self.op(t)).collect::<Option<_>>()?;
+                Constraint::mk_pred(*p, l)
+            }
+            Formula::Conj(f1, f2) => {
+                Constraint::mk_conj(self.constraint(f1)?, self.constraint(f2)?)
+            }
+            Formula::Disj(f1, f2) => {
+                Constraint::mk_disj(self.constraint(f1)?, self.constraint(f2)?)
+            }
+            Formula::Quantifier(q, f) => {
+                let x = Ident::fresh();
+                self.bound.push(x);
+                let c = self.constraint(f);
+                self.bound.pop();
+                Constraint::mk_quantifier_int(*q, x, c?)
+            }
+        };
+        Some(c)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+struct SMTKey {
+    /// the solver that answered the query
+    solver: SMTSolverType,
+    formula: Formula,
+    /// whether each free variable is universally quantified
+    universal: Vec<bool>,
+}
+
+impl SMTKey {
+    fn new(solver: SMTSolverType, c: &Constraint, vars: &HashSet<Ident>) -> (SMTKey, Vec<Ident>) {
+        let mut n = Normalizer::default();
+        let formula = n.constraint(c);
+        let universal = n.free.iter().map(|x| vars.contains(x)).collect();
+        let key = SMTKey {
+            solver,
+            formula,
+            universal,
+        };
+        (key, n.free)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+enum SMTEntry {
+    Sat,
+    Unsat,
+    /// values of the free variables (those of universally quantified ones are dummy)
+    Model(Vec<i64>),
+}
+
+/// Interpretation of a predicate, whose arguments are the free variables 0..arity
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct PredicateModel {
+    predicate: usize,
+    arity: usize,
+    formula: Formula,
+}
+
+#[derive(Default)]
+struct Cache {
+    smt: HashMap<SMTKey, SMTEntry>,
+    qe: HashMap<Formula, Formula>,
+    interpolation: HashMap<Vec<NormalizedCHC>, Vec<PredicateModel>>,
+}
+
+/// On-disk representation of the cache (keys of JSON objects must be strings)
+#[derive(Default, Serialize, Deserialize)]
+struct CacheFile {
+    smt: Vec<(SMTKey, SMTEntry)>,
+    qe: Vec<(Formula, Formula)>,
+    interpolation: Vec<(Vec<NormalizedCHC>, Vec<PredicateModel>)>,
+}
+
+static CACHE: Lazy<Mutex<Cache>> = Lazy::new(|| Mutex::new(Cache::default()));
+
+/// whether the current registry enables the cache
+fn enabled() -> bool {
+    super::registry::with(|r| r.cache)
+}
+
+/// Loads the entries saved by `save` into the cache.
+pub fn load<P: AsRef<Path>>(path: P) -> Result<(), CacheError> {
+    let s = std::fs::read_to_string(path)?;
+    let file: CacheFile = serde_json::from_str(&s)?;
+    let mut cache = CACHE.lock().unwrap();
+    cache.smt.extend(file.smt);
+    cache.qe.extend(file.qe);
+    cache.interpolation.extend(file.interpolation);
+    Ok(())
+}
+
+/// Saves the current entries of the cache to `path`.
+pub fn save<P: AsRef<Path>>(path: P) -> Result<(), CacheError> {
+    let file = {
+        let cache = CACHE.lock().unwrap();
+        CacheFile {
+            smt: cache.smt.clone().into_iter().collect(),
+            qe: cache.qe.clone().into_iter().collect(),
+            interpolation: cache.interpolation.clone().into_iter().collect(),
+        }
+    };
+    std::fs::write(path, serde_json::to_string(&file)?)?;
+    Ok(())
+}
+
+/// SMT solver that consults the cache before invoking `inner`.
+pub(super) struct CachedSolver {
+    /// the kind of `inner`
+    solver: SMTSolverType,
+    inner: Box<dyn SMTSolver>,
+}
+
+impl CachedSolver {
+    pub(super) fn new(solver: SMTSolverType, inner: Box<dyn SMTSolver>) -> Box<dyn SMTSolver> {
+        if enabled() {
+            Box::new(CachedSolver { solver, inner })
+        } else {
+            inner
+        }
+    }
+}
+
+impl SMTSolver for CachedSolver {
+    fn solve(&mut self, c: &Constraint, vars: &HashSet<Ident>) -> SolverResult {
+        let (key, _) = SMTKey::new(self.solver, c, vars);
+        match CACHE.lock().unwrap().smt.get(&key) {
+            Some(SMTEntry::Sat) | Some(SMTEntry::Model(_)) => {
+                hit(Query::SMT);
+                return SolverResult::Sat;
+            }
+            Some(SMTEntry::Unsat) => {
+                hit(Query::SMT);
+                return SolverResult::Unsat;
+            }
+            None => miss(Query::SMT),
+        }
+        let r = self.inner.solve(c, vars);
+        let entry = match r {
+            SolverResult::Sat => SMTEntry::Sat,
+            SolverResult::Unsat => SMTEntry::Unsat,
+            SolverResult::Unknown | SolverResult::Timeout => return r,
+        };
+        CACHE.lock().unwrap().smt.insert(key, entry);
+        r
+    }
+
+    fn solve_with_model(
+        &mut self,
+        c: &Constraint,
+        vars: &HashSet<Ident>,
+        fvs: &HashSet<Ident>,
+    ) -> Result<Model, SolverResult> {
+        let (key, free) = SMTKey::new(self.solver, c, vars);
+        match CACHE.lock().unwrap().smt.get(&key) {
+            Some(SMTEntry::Model(values)) => {
+                hit(Query::SMT);
+                let mut m = Model {
+                    model: free.iter().copied().zip(values.iter().copied()).collect(),
+                };
+                // variables that do not appear in `c` can take any value
+                m.model.retain(|x, _| fvs.contains(x));
+                m.compensate(fvs);
+                return Ok(m);
+            }
+            Some(SMTEntry::Unsat) => {
+                hit(Query::SMT);
+                return Err(SolverResult::Unsat);
+            }
+            // a model is required
+            Some(SMTEntry::Sat) | None => miss(Query::SMT),
+        }
+        // ask for the values of all the free variables so that the entry can answer
+        // queries for any `fvs`
+        let all: HashSet<Ident> = free
+            .iter()
+            .filter(|x| !vars.contains(x))
+            .chain(fvs.iter())
+            .copied()
+            .collect();
+        let r = self.inner.solve_with_model(c, vars, &all);
+        let entry = match &r {
+            Ok(m) => SMTEntry::Model(free.iter().map(|x| m.get(x).unwrap_or(0)).collect()),
+            Err(SolverResult::Unsat) => SMTEntry::Unsat,
+            Err(_) => return r,
+        };
+        CACHE.lock().unwrap().smt.insert(key, entry);
+        r.map(|mut m| {
+            m.model.retain(|x, _| fvs.contains(x));
+            m
+        })
+    }
+}
+
+/// Eliminates quantifiers of `c` by `solve` unless the result is cached.
+pub(super) fn qe<E, F>(c: &Constraint, solve: F) -> Result<Constraint, E>
+where
+    F: FnOnce(&Constraint) -> Result<Constraint, E>,
+{
+    if !enabled() {
+        return solve(c);
+    }
+    let mut n = Normalizer::default();
+    let key = n.constraint(c);
+    let cached = CACHE.lock().unwrap().qe.get(&key).cloned();
+    if let Some(c) = cached.and_then(|f| Denormalizer::new(&n.free).constraint(&f)) {
+        hit(Query::QE);
+        return Ok(c);
+    }
+    miss(Query::QE);
+
+    let r = solve(c)?;
+    let mut m = Normalizer::with_args(&n.free);
+    let value = m.constraint(&r);
+    // the result must not contain variables other than the free variables of `c`
+    if m.free.len() == n.free.len() {
+        CACHE.lock().unwrap().qe.insert(key, value);
+    }
+    Ok(r)
+}
+
+fn normalize_chcs(chcs: &[CHC]) -> (Vec<NormalizedCHC>, Vec<Ident>) {
+    let mut predicates = Normalizer::default();
+    let key = chcs
+        .iter()
+        .map(|c| {
+            // variables of each clause are implicitly quantified in the clause
+            let mut n = Normalizer::default();
+            let body = c
+                .body
+                .predicates
+                .iter()
+                .map(|a| n.atom(a, &mut predicates))
+                .collect();
+            let constraint = n.constraint(&c.body.constraint);
+            let head = match &c.head {
+                CHCHead::Constraint(c) => NormalizedHead::Constraint(n.constraint(c)),
+                CHCHead::Predicate(a) => NormalizedHead::Predicate(n.atom(a, &mut predicates)),
+            };
+            NormalizedCHC {
+                predicates: body,
+                constraint,
+                head,
+            }
+        })
+        .collect();
+    (key, predicates.free)
+}
+
+fn normalize_model(model: &chc::Model, predicates: &[Ident]) -> Option<Vec<PredicateModel>> {
+    predicates
+        .iter()
+        .enumerate()
+        .filter_map(|(i, p)| model.model.get(p).map(|m| (i, m)))
+        .map(|(predicate, (args, c))| {
+            let mut n = Normalizer::with_args(args);
+            let formula = n.constraint(c);
+            if n.free.len() == args.len() {
+                Some(PredicateModel {
+                    predicate,
+                    arity: args.len(),
+                    formula,
+                })
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+fn denormalize_model(models: &[PredicateModel], predicates: &[Ident]) -> Option<chc::Model> {
+    let mut model = chc::Model::new();
+    for m in models {
+        let p = predicates.get(m.predicate)?;
+        let args: Vec<_> = (0..m.arity).map(|_| Ident::fresh()).collect();
+        let c = Denormalizer::new(&args).constraint(&m.formula)?;
+        model.model.insert(*p, (args, c));
+    }
+    Some(model)
+}
+
+/// Interpolates the predicates in `chcs` by `solve` unless the result is cached.
+/// Failures (`None`) are not cached.
+pub(super) fn interpolation<F>(chcs: &[CHC], solve: F) -> Option<chc::Model>
+where
+    F: FnOnce() -> Option<chc::Model>,
+{
+    if !enabled() {
+        return solve();
+    }
+    let (key, predicates) = normalize_chcs(chcs);
+    let cached = CACHE.lock().unwrap().interpolation.get(&key).cloned();
+    if let Some(m) = cached.and_then(|m| denormalize_model(&m, &predicates)) {
+        hit(Query::Interpolation);
+        return Some(m);
+    }
+    miss(Query::Interpolation);
+
+    let m = solve()?;
+    if let Some(value) = normalize_model(&m, &predicates) {
+        CACHE.lock().unwrap().interpolation.insert(key, value);
+    }
+    Some(m)
+}
+
+#[test]
+fn test_normalize_alpha_equivalent() {
+    use crate::formula::{AlphaEquivalence, Rename};
+    fn gen() -> (Constraint, Ident, Ident) {
+        let x = Ident::fresh();
+        let y = Ident::fresh();
+        let z = Ident::fresh();
+        // ∃z. x < z ∧ z < y
+        let c = Constraint::mk_exists_int(
+            z,
+            Constraint::mk_conj(
+                Constraint::mk_lt(Op::mk_var(x), Op::mk_var(z)),
+                Constraint::mk_lt(Op::mk_var(z), Op::mk_var(y)),
+            ),
+        );
+        (c, x, y)
+    }
+    let (c1, x1, y1) = gen();
+    let (c2, x2, y2) = gen();
+    let mut n1 = Normalizer::default();
+    let mut n2 = Normalizer::default();
+    assert_eq!(n1.constraint(&c1), n2.constraint(&c2));
+    assert_eq!(n1.free, vec![x1, y1]);
+    assert_eq!(n2.free, vec![x2, y2]);
+
+    // x and y are swapped
+    let c3 = c2.rename_idents(&[(x2, y1), (y2, x1)]);
+    let mut n3 = Normalizer::default();
+    assert_eq!(n1.constraint(&c1), n3.constraint(&c3));
+    assert_eq!(n3.free, vec![y1, x1]);
+
+    let c4 = Constraint::mk_lt(Op::mk_var(x1), Op::mk_var(x1));
+    assert_ne!(Normalizer::default().constraint(&c4), n1.constraint(&c1));
+
+    let f = n1.constraint(&c1);
+    let c5 = Denormalizer::new(&n2.free).constraint(&f).unwrap();
+    assert!(c5.alpha_equiv(&c2));
+}
+
+#[test]
+fn test_cached_solver() {
+    use std::cell::Cell;
+    use std::rc::Rc;
+    struct Counting {
+        count: Rc<Cell<usize>>,
+    }
+    impl SMTSolver for Counting {
+        fn solve(&mut self, _: &Constraint, _: &HashSet<Ident>) -> SolverResult {
+            self.count.set(self.count.get() + 1);
+            SolverResult::Unsat
+        }
+        fn solve_with_model(
+            &mut self,
+            _: &Constraint,
+            _: &HashSet<Ident>,
+            _: &HashSet<Ident>,
+        ) -> Result<Model, SolverResult> {
+            self.count.set(self.count.get() + 1);
+            Err(SolverResult::Unsat)
+        }
+    }
+    let x = Ident::fresh();
+    let y = Ident::fresh();
+    // x < y ∧ y < x ∧ x = 1234567 (unlikely to be shared with other tests)
+    let gen = |x, y| {
+        Constraint::mk_conj(
+            Constraint::mk_eq(Op::mk_var(x), Op::mk_const(1234567)),
+            Constraint::mk_conj(
+                Constraint::mk_lt(Op::mk_var(x), Op::mk_var(y)),
+                Constraint::mk_lt(Op::mk_var(y), Op::mk_var(x)),
+            ),
+        )
+    };
+    let count = Rc::new(Cell::new(0));
+    let mut solver = CachedSolver {
+        solver: SMTSolverType::Z3,
+        inner: Box::new(Counting {
+            count: count.clone(),
+        }),
+    };
+    assert!(solver.solve(&gen(x, y), &HashSet::new()).is_unsat());
+    assert!(solver.solve(&gen(y, x), &HashSet::new()).is_unsat());
+    let z = Ident::fresh();
+    assert!(solver.solve(&gen(z, x), &HashSet::new()).is_unsat());
+    assert!(solver
+        .solve_with_model(&gen(y, z), &HashSet::new(), &HashSet::new())
+        .is_err());
+    // universally quantifying x makes a different query
+    let vars = vec![x].into_iter().collect();
+    assert!(solver.solve(&gen(x, y), &vars).is_unsat());
+    assert_eq!(count.get(), 2);
+    // answers of another solver are not shared
+    solver.solver = SMTSolverType::Auto;
+    assert!(solver.solve(&gen(x, y), &HashSet::new()).is_unsat());
+    assert_eq!(count.get(), 3);
+}
@@ -17,9 +17,8 @@ use lexpr::Value;
 use rpds::Stack;
 
 use std::collections::HashMap;
-use std::time::Duration;
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug)]
 pub enum CHCStyle {
     Hoice,
     HoiceNoSimplify,
@@ -201,12 +200,13 @@ pub fn chc_solver(style: CHCStyle) -> Box<dyn CHCSolver> {
     }
 }
 
+/// returns the CHC solver of the current registry
 pub fn default_solver() -> Box<dyn CHCSolver> {
-    chc_solver(CHCStyle::Hoice)
+    chc_solver(super::registry::with(|r| r.chc))
 }
 
 pub fn interpolating_solver() -> Box<dyn CHCSolver> {
-    chc_solver(CHCStyle::Hoice)
+    chc_solver(super::registry::with(|r| r.chc))
 }
 
 macro_rules! chc_execution {
@@ -223,14 +223,14 @@ macro_rules! chc_execution {
     }};
 }
 
-fn hoice_solver(smt_string: String) -> String {
+fn hoice_solver(smt_string: String) -> Result<String, util::ExecError> {
     debug!("hoice_solver: {}", smt_string);
     let f = smt::save_smt2(smt_string);
     let args = vec![f.path().to_str().unwrap()];
     debug!("filename: {}", &args[0]);
-    // TODO: determine the path when it's compiled
-    let out = chc_execution!({ util::exec_with_timeout("hoice", &args, Duration::from_secs(1),) });
-    String::from_utf8(out).unwrap()
+    let hoice = super::registry::with(|r| r.paths.hoice.clone());
+    let out = chc_execution!({ util::exec_with_timeout(&hoice, &args, super::timeouts().chc) });
+    Ok(String::from_utf8(out?).unwrap())
 }
 
 #[derive(Clone, Debug)]
@@ -850,6 +850,8 @@ fn test_parse_spacer_model() {
     }
 }
 
+/// returns false only if `model` is shown not to be a solution of `clauses`. It is used
+/// in assertions, so Unknown and Timeout of the solver do not fail them.
 pub fn is_solution_valid(clauses: &[CHC], model: &Model) -> bool {
     crate::title!("is_solution_valid");
     let mut c = Constraint::mk_true();
@@ -863,8 +865,10 @@ pub fn is_solution_valid(clauses: &[CHC], model: &Model) -> bool {
     match solver.solve(&c, &fvs) {
         super::SolverResult::Sat => true,
         super::SolverResult::Unsat => false,
-        super::SolverResult::Unknown => panic!("failed to verify"),
-        super::SolverResult::Timeout => panic!("smt timeout"),
+        r @ (super::SolverResult::Unknown | super::SolverResult::Timeout) => {
+            warn!("is_solution_valid: failed to verify the solution ({:?})", r);
+            true
+        }
     }
 }
 
@@ -890,7 +894,10 @@ impl CHCSolver for HoiceSolver {
     fn solve(&mut self, clauses: &[CHC]) -> CHCResult {
         let smt2 = chcs_to_smt2(clauses, self.style);
         debug!("smt2: {}", &smt2);
-        let s = hoice_solver(smt2);
+        let s = match hoice_solver(smt2) {
+            Ok(s) => s,
+            Err(util::ExecError::Timeout) => return CHCResult::Timeout,
+        };
         debug!("smt_solve result: {:?}", &s);
         if s.starts_with("sat") {
             let m = Model::parse_hoice_model(&s[4..]).unwrap();
@@ -903,7 +910,7 @@ impl CHCSolver for HoiceSolver {
     }
 }
 
-fn spacer_solver(smt_string: String, interpolation: bool) -> String {
+fn spacer_solver(smt_string: String, interpolation: bool) -> Result<String, util::ExecError> {
     debug!("spacer_solver: {}", smt_string);
     let f = smt::save_smt2(smt_string);
     let mut args = vec!["fp.engine=spacer"];
@@ -914,15 +921,19 @@ fn spacer_solver(smt_string: String, interpolation: bool) -> String {
     }
     args.push(f.path().to_str().unwrap());
     debug!("filename: {}", &args[1]);
-    let out = chc_execution!({ util::exec_with_timeout("z3", &args, Duration::from_secs(1),) });
-    String::from_utf8(out).unwrap()
+    let z3 = super::registry::with(|r| r.paths.z3.clone());
+    let out = chc_execution!({ util::exec_with_timeout(&z3, &args, super::timeouts().chc) });
+    Ok(String::from_utf8(out?).unwrap())
 }
 
 impl CHCSolver for SpacerSolver {
     fn solve(&mut self, clauses: &[CHC]) -> CHCResult {
         let smt2 = chcs_to_smt2(clauses, self.style);
         debug!("smt2: {}", &smt2);
-        let s = spacer_solver(smt2, self.interpolation);
+        let s = match spacer_solver(smt2, self.interpolation) {
+            Ok(s) => s,
+            Err(util::ExecError::Timeout) => return CHCResult::Timeout,
+        };
         debug!("smt_solve result: {:?}", &s);
         if s.starts_with("sat") {
             let m = Model::parse_spacer_model(&s[4..]).unwrap();
@@ -0,0 +1,589 @@
+//! Quantifier elimination for linear integer arithmetic by Cooper's method.
+//!
+//! Quantifiers are eliminated from the innermost one. For `∃x. φ` where `φ` is
+//! quantifier-free,
+//! 1. the atoms containing `x` are normalized to `c·x + s ≤ 0`, `c·x + s = 0`,
+//!    `c·x + s ≠ 0`, `d | c·x + s` and `¬(d | c·x + s)`;
+//! 2. the coefficients of `x` are unified to ±1 by multiplying the atoms, and replacing
+//!    `l·x` with `x` where `l | x`, where `l` is the lcm of the coefficients;
+//! 3. with the lcm `D` of the divisors and the lower bounds `B` of `x`,
+//!    `∃x. φ ⟺ ∨_{j=1..D} φ(-∞)[j/x] ∨ ∨_{j=1..D} ∨_{b∈B} φ[b + j/x]`.
+//!
+//! When there are fewer upper bounds than lower bounds, the dual (`x ↦ -x`) is used.
+//! `∀x. φ` is handled as `¬∃x. ¬φ`. Divisibility `d | t` is represented by `t mod d = 0`.
+//!
+//! Among the atoms with `mod`, only `t mod d = k` and `t mod d ≠ k` with constants `d > 0`
+//! and `k` are supported; `x` in other shapes of `mod` and in `div` makes the elimination
+//! fail with `QEError::Unsupported`, and so does an lcm `l` or `D` beyond `MAX_LCM` since
+//! the result grows with it. Constants are computed in the semantics of SMT-LIB, where
+//! `mod` is not negative (`eval_const`), not with `Op::eval`.
+use super::qe::{QEError, QESolver};
+use crate::formula::{
+    Bot, Constraint, ConstraintExpr, Fv, Ident, Logic, Negation, Op, OpExpr, OpKind, PredKind,
+    QuantifierKind, Top,
+};
+
+pub struct Cooper {}
+
+/// the largest lcm of the coefficients (`l`) and of the divisors (`D`), which bounds the
+/// number of the candidates enumerated
+const MAX_LCM: i64 = 1 << 10;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum AtomKind {
+    /// c·x + s ≤ 0
+    Le,
+    /// c·x + s = 0
+    Eq,
+    /// c·x + s ≠ 0
+    Neq,
+    /// d | c·x + s
+    Dvd(i64),
+    /// ¬(d | c·x + s)
+    NDvd(i64),
+}
+
+/// Atom `kind(coef·x + rest)`, where `rest` does not contain x.
+#[derive(Clone, Debug)]
+struct Atom {
+    kind: AtomKind,
+    coef: i64,
+    rest: Op,
+}
+
+/// Quantifier-free formula whose atoms containing the target variable are normalized.
+#[derive(Clone, Debug)]
+enum Formula {
+    /// a constraint that does not contain the target variable
+    Lit(Constraint),
+    Atom(Atom),
+    Conj(Box<Formula>, Box<Formula>),
+    Disj(Box<Formula>, Box<Formula>),
+}
+
+fn gcd(x: i64, y: i64) -> i64 {
+    if y == 0 {
+        x.abs()
+    } else {
+        gcd(y, x % y)
+    }
+}
+
+/// lcm of `x` and `y` if it is at most `MAX_LCM`
+fn bounded_lcm(x: i64, y: i64) -> Option<i64> {
+    let l = (x / gcd(x, y)).checked_mul(y)?;
+    if l <= MAX_LCM {
+        Some(l)
+    } else {
+        None
+    }
+}
+
+/// Evaluates the constant `o` in the semantics of SMT-LIB, where `div` and `mod` are
+/// Euclidean. `Op::eval` truncates them as Rust's `/` and `%` do.
+fn eval_const(o: &Op) -> Option<i64> {
+    match o.kind() {
+        OpExpr::Const(c) => Some(*c),
+        OpExpr::Op(op, x, y) => {
+            let x = eval_const(x)?;
+            let y = eval_const(y)?;
+            match op {
+                OpKind::Add => x.checked_add(y),
+                OpKind::Sub => x.checked_sub(y),
+                OpKind::Mul => x.checked_mul(y),
+                OpKind::Div => x.checked_div_euclid(y),
+                OpKind::Mod => x.checked_rem_euclid(y),
+            }
+        }
+        OpExpr::Ptr(_, o) => eval_const(o),
+        OpExpr::Var(_) | OpExpr::ITE(_, _, _) => None,
+    }
+}
+
+/// Replaces the constant subexpressions of `o` with their values (see `eval_const`).
+fn fold_consts(o: &Op) -> Op {
+    if let Some(v) = eval_const(o) {
+        return Op::mk_const(v);
+    }
+    match o.kind() {
+        OpExpr::Op(op, x, y) => Op::mk_bin_op(*op, fold_consts(x), fold_consts(y)),
+        OpExpr::Ptr(_, o) => fold_consts(o),
+        OpExpr::Var(_) | OpExpr::Const(_) | OpExpr::ITE(_, _, _) => o.clone(),
+    }
+}
+
+fn unsupported(c: &Constraint) -> QEError {
+    QEError::Unsupported(c.to_string())
+}
+
+/// Decomposes `o` into `c·x + s`, where `s` does not contain x.
+fn linear(o: &Op, x: &Ident) -> Option<(i64, Op)> {
+    match o.kind() {
+        OpExpr::Var(y) if y == x => Some((1, Op::mk_const(0))),
+        OpExpr::Var(_) | OpExpr::Const(_) => Some((0, o.clone())),
+        OpExpr::Op(OpKind::Add, o1, o2) => {
+            let (c1, s1) = linear(o1, x)?;
+            let (c2, s2) = linear(o2, x)?;
+            Some((c1 + c2, Op::mk_add(s1, s2)))
+        }
+        OpExpr::Op(OpKind::Sub, o1, o2) => {
+            let (c1, s1) = linear(o1, x)?;
+            let (c2, s2) = linear(o2, x)?;
+            Some((c1 - c2, Op::mk_sub(s1, s2)))
+        }
+        OpExpr::Op(OpKind::Mul, o1, o2) => {
+            let (c1, s1) = linear(o1, x)?;
+            let (c2, s2) = linear(o2, x)?;
+            match (c1, c2) {
+                (0, 0) => Some((0, o.clone())),
+                (0, _) => {
+                    let k = eval_const(&s1)?;
+                    Some((k.checked_mul(c2)?, Op::mk_mul(Op::mk_const(k), s2)))
+                }
+                (_, 0) => {
+                    let k = eval_const(&s2)?;
+                    Some((k.checked_mul(c1)?, Op::mk_mul(Op::mk_const(k), s1)))
+                }
+                (_, _) => None,
+            }
+        }
+        OpExpr::Ptr(_, o) => linear(o, x),
+        OpExpr::Op(OpKind::Div, _, _) | OpExpr::Op(OpKind::Mod, _, _) | OpExpr::ITE(_, _, _) => {
+            if o.fv().contains(x) {
+                None
+            } else {
+                Some((0, o.clone()))
+            }
+        }
+    }
+}
+
+/// Recognizes `t mod d = k` (or its symmetric form), where d and k are constants.
+fn as_divisibility(l: &Op, r: &Op) -> Option<(Op, i64, i64)> {
+    fn inner(l: &Op, r: &Op) -> Option<(Op, i64, i64)> {
+        let k = eval_const(r)?;
+        match l.kind() {
+            OpExpr::Op(OpKind::Mod, t, d) => {
+                let d = eval_const(d)?;
+                if d > 0 {
+                    Some((t.clone(), d, k))
+                } else {
+                    None
+                }
+            }
+            OpExpr::Ptr(_, l) => inner(l, r),
+            _ => None,
+        }
+    }
+    inner(l, r).or_else(|| inner(r, l))
+}
+
+fn atom(kind: AtomKind, o: &Op, x: &Ident, original: &Constraint) -> Result<Formula, QEError> {
+    let (coef, rest) = linear(o, x).ok_or_else(|| unsupported(original))?;
+    if coef == 0 {
+        Ok(Formula::Lit(original.clone()))
+    } else {
+        Ok(Formula::Atom(Atom { kind, coef, rest }))
+    }
+}
+
+/// Translates a quantifier-free constraint to `Formula` with respect to `x`.
+fn to_formula(c: &Constraint, x: &Ident) -> Result<Formula, QEError> {
+    match c.kind() {
+        ConstraintExpr::True | ConstraintExpr::False => Ok(Formula::Lit(c.clone())),
+        ConstraintExpr::Pred(_, _) if !c.fv().contains(x) => Ok(Formula::Lit(c.clone())),
+        ConstraintExpr::Pred(p, l) if l.len() == 2 => {
+            let (left, right) = (&l[0], &l[1]);
+            match (p, as_divisibility(left, right)) {
+                (PredKind::Eq, Some((_, d, _))) | (PredKind::Neq, Some((_, d, _)))
+                    if d > MAX_LCM =>
+                {
+                    return Err(unsupported(c))
+                }
+                (PredKind::Eq, Some((t, d, k))) | (PredKind::Neq, Some((t, d, k))) => {
+                    let positive = *p == PredKind::Eq;
+                    // t mod d (∈ [0, d)) never equals k
+                    if k < 0 || k >= d {
+                        return Ok(Formula::Lit(if positive {
+                            Constraint::mk_false()
+                        } else {
+                            Constraint::mk_true()
+                        }));
+                    }
+                    let kind = if positive {
+                        AtomKind::Dvd(d)
+                    } else {
+                        AtomKind::NDvd(d)
+                    };
+                    return atom(kind, &Op::mk_sub(t, Op::mk_const(k)), x, c);
+                }
+                _ => (),
+            }
+            let one = || Op::mk_const(1);
+            let (kind, o) = match p {
+                PredKind::Leq => (AtomKind::Le, Op::mk_sub(left.clone(), right.clone())),
+                PredKind::Geq => (AtomKind::Le, Op::mk_sub(right.clone(), left.clone())),
+                // l < r ⟺ l - r + 1 ≤ 0
+                PredKind::Lt => (
+                    AtomKind::Le,
+                    Op::mk_add(Op::mk_sub(left.clone(), right.clone()), one()),
+                ),
+                PredKind::Gt => (
+                    AtomKind::Le,
+                    Op::mk_add(Op::mk_sub(right.clone(), left.clone()), one()),
+                ),
+                PredKind::Eq => (AtomKind::Eq, Op::mk_sub(left.clone(), right.clone())),
+                PredKind::Neq => (AtomKind::Neq, Op::mk_sub(left.clone(), right.clone())),
+            };
+            atom(kind, &o, x, c)
+        }
+        ConstraintExpr::Conj(c1, c2) => Ok(Formula::Conj(
+            Box::new(to_formula(c1, x)?),
+            Box::new(to_formula(c2, x)?),
+        )),
+        ConstraintExpr::Disj(c1, c2) => Ok(Formula::Disj(
+            Box::new(to_formula(c1, x)?),
+            Box::new(to_formula(c2, x)?),
+        )),
+        ConstraintExpr::Pred(_, _) | ConstraintExpr::Quantifier(_, _, _) => Err(unsupported(c)),
+    }
+}
+
+impl Formula {
+    fn atoms<'a>(&'a self, v: &mut Vec<&'a Atom>) {
+        match self {
+            Formula::Lit(_) => (),
+            Formula::Atom(a) => v.push(a),
+            Formula::Conj(f1, f2) | Formula::Disj(f1, f2) => {
+                f1.atoms(v);
+                f2.atoms(v);
+            }
+        }
+    }
+
+    fn map_atoms<F: Fn(&Atom) -> Formula + Copy>(&self, f: F) -> Formula {
+        match self {
+            Formula::Lit(_) => self.clone(),
+            Formula::Atom(a) => f(a),
+            Formula::Conj(f1, f2) => {
+                Formula::Conj(Box::new(f1.map_atoms(f)), Box::new(f2.map_atoms(f)))
+            }
+            Formula::Disj(f1, f2) => {
+                Formula::Disj(Box::new(f1.map_atoms(f)), Box::new(f2.map_atoms(f)))
+            }
+        }
+    }
+
+    /// the formula where x is replaced with t
+    fn subst(&self, t: &Op) -> Constraint {
+        match self {
+            Formula::Lit(c) => c.clone(),
+            Formula::Atom(a) => a.subst(t),
+            Formula::Conj(f1, f2) => Constraint::mk_conj(f1.subst(t), f2.subst(t)),
+            Formula::Disj(f1, f2) => Constraint::mk_disj(f1.subst(t), f2.subst(t)),
+        }
+    }
+}
+
+impl Atom {
+    /// the atom where x is replaced with t
+    fn subst(&self, t: &Op) -> Constraint {
+        let o = Op::mk_add(
+            Op::mk_mul(Op::mk_const(self.coef), t.clone()),
+            self.rest.clone(),
+        );
+        // folded beforehand so that `simplify` does not compute `mod` by `Op::eval`
+        let o = fold_consts(&o).simplify();
+        let zero = Op::mk_const(0);
+        let v = eval_const(&o);
+        match self.kind {
+            AtomKind::Le => match v {
+                Some(v) => from_bool(v <= 0),
+                None => Constraint::mk_leq(o, zero),
+            },
+            AtomKind::Eq => match v {
+                Some(v) => from_bool(v == 0),
+                None => Constraint::mk_eq(o, zero),
+            },
+            AtomKind::Neq => match v {
+                Some(v) => from_bool(v != 0),
+                None => Constraint::mk_neq(o, zero),
+            },
+            AtomKind::Dvd(d) => match v {
+                _ if d == 1 => Constraint::mk_true(),
+                Some(v) => from_bool(v.rem_euclid(d) == 0),
+                None => Constraint::mk_eq(Op::mk_mod(o, Op::mk_const(d)), zero),
+            },
+            AtomKind::NDvd(d) => match v {
+                _ if d == 1 => Constraint::mk_false(),
+                Some(v) => from_bool(v.rem_euclid(d) != 0),
+                None => Constraint::mk_neq(Op::mk_mod(o, Op::mk_const(d)), zero),
+            },
+        }
+    }
+
+    /// the value of x that makes the atom an equality, provided that coef = ±1
+    fn solution(&self) -> Op {
+        // c·x + s = 0 ⟺ x = -c·s
+        Op::mk_mul(Op::mk_const(-self.coef), self.rest.clone())
+    }
+}
+
+fn from_bool(b: bool) -> Constraint {
+    if b {
+        Constraint::mk_true()
+    } else {
+        Constraint::mk_false()
+    }
+}
+
+/// Eliminates x from `∃x. c` where `c` is quantifier-free.
+fn eliminate_exists(x: &Ident, c: &Constraint) -> Result<Constraint, QEError> {
+    let too_large = || QEError::Unsupported(format!("{} (the lcm exceeds {})", c, MAX_LCM));
+    let f = to_formula(c, x)?;
+    let mut atoms = Vec::new();
+    f.atoms(&mut atoms);
+    if atoms.is_empty() {
+        return Ok(c.clone());
+    }
+
+    // 2. unify the coefficients of x to ±1
+    let l = atoms
+        .iter()
+        .try_fold(1, |l, a| bounded_lcm(l, a.coef.abs()))
+        .ok_or_else(too_large)?;
+    let f = f.map_atoms(|a| {
+        let m = l / a.coef.abs();
+        let kind = match a.kind {
+            AtomKind::Dvd(d) => AtomKind::Dvd(d * m),
+            AtomKind::NDvd(d) => AtomKind::NDvd(d * m),
+            k => k,
+        };
+        Formula::Atom(Atom {
+            kind,
+            coef: a.coef.signum(),
+            rest: Op::mk_mul(Op::mk_const(m), a.rest.clone()),
+        })
+    });
+    let f = if l > 1 {
+        let divisible = Atom {
+            kind: AtomKind::Dvd(l),
+            coef: 1,
+            rest: Op::mk_const(0),
+        };
+        Formula::Conj(Box::new(f), Box::new(Formula::Atom(divisible)))
+    } else {
+        f
+    };
+
+    // use the dual if there are fewer upper bounds
+    let mut atoms = Vec::new();
+    f.atoms(&mut atoms);
+    let bounds = |coef| {
+        atoms
+            .iter()
+            .filter(|a| a.kind == AtomKind::Le && a.coef == coef)
+            .count()
+    };
+    let f = if bounds(1) < bounds(-1) {
+        f.map_atoms(|a| {
+            Formula::Atom(Atom {
+                coef: -a.coef,
+                ..a.clone()
+            })
+        })
+    } else {
+        f
+    };
+
+    // 3. enumerate the candidates
+    let mut atoms = Vec::new();
+    f.atoms(&mut atoms);
+    let mut d = 1;
+    let mut lower_bounds = Vec::new();
+    for a in atoms.iter() {
+        match a.kind {
+            AtomKind::Dvd(e) | AtomKind::NDvd(e) => d = bounded_lcm(d, e).ok_or_else(too_large)?,
+            // -x + s ≤ 0 ⟺ x > s - 1
+            AtomKind::Le if a.coef == -1 => {
+                lower_bounds.push(Op::mk_sub(a.rest.clone(), Op::mk_const(1)))
+            }
+            AtomKind::Le => (),
+            AtomKind::Eq => lower_bounds.push(Op::mk_sub(a.solution(), Op::mk_const(1))),
+            AtomKind::Neq => lower_bounds.push(a.solution()),
+        }
+    }
+    let minus_infinity = f.map_atoms(|a| match a.kind {
+        AtomKind::Le if a.coef == 1 => Formula::Lit(Constraint::mk_true()),
+        AtomKind::Le | AtomKind::Eq => Formula::Lit(Constraint::mk_false()),
+        AtomKind::Neq => Formula::Lit(Constraint::mk_true()),
+        AtomKind::Dvd(_) | AtomKind::NDvd(_) => Formula::Atom(a.clone()),
+    });
+
+    let mut result = Constraint::mk_false();
+    for j in 1..=d {
+        let j = Op::mk_const(j);
+        result = Constraint::mk_disj(result, minus_infinity.subst(&j));
+        for b in lower_bounds.iter() {
+            result = Constraint::mk_disj(result, f.subst(&Op::mk_add(b.clone(), j.clone())));
+        }
+        if result.is_true() {
+            break;
+        }
+    }
+    Ok(result)
+}
+
+fn eliminate(c: &Constraint) -> Result<Constraint, QEError> {
+    match c.kind() {
+        ConstraintExpr::True | ConstraintExpr::False | ConstraintExpr::Pred(_, _) => Ok(c.clone()),
+        ConstraintExpr::Conj(c1, c2) => Ok(Constraint::mk_conj(eliminate(c1)?, eliminate(c2)?)),
+        ConstraintExpr::Disj(c1, c2) => Ok(Constraint::mk_disj(eliminate(c1)?, eliminate(c2)?)),
+        ConstraintExpr::Quantifier(q, x, body) => {
+            if !x.ty.is_int() {
+                return Err(unsupported(c));
+            }
+            let body = eliminate(body)?;
+            match q {
+                QuantifierKind::Existential => eliminate_exists(&x.id, &body),
+                // ∀x. φ ⟺ ¬∃x. ¬φ
+                QuantifierKind::Universal => {
+                    let body = body.negate().ok_or_else(|| unsupported(c))?;
+                    let r = eliminate_exists(&x.id, &body)?;
+                    r.negate().ok_or_else(|| unsupported(c))
+                }
+            }
+        }
+    }
+}
+
+impl QESolver for Cooper {
+    fn try_solve(&self, formula: &Constraint) -> Result<Constraint, QEError> {
+        debug!("trying quantifier elimination by cooper: {formula}");
+        crate::stat::qe::qe_count();
+        crate::stat::qe::start_clock();
+        let r = eliminate(formula);
+        crate::stat::qe::end_clock();
+        r
+    }
+}
+
+#[cfg(test)]
+fn check_equivalent(c1: &Constraint, c2: &Constraint) -> bool {
+    use crate::solver::smt;
+    smt::default_solver().check_equivalent(c1, c2).is_sat()
+}
+
+#[test]
+fn test_cooper() {
+    use crate::formula::FirstOrderLogic;
+    let x = Ident::fresh();
+    let y = Ident::fresh();
+    let z = Ident::fresh();
+    let v = Op::mk_var;
+
+    // ∃x. y < x ∧ x < z  ⟺  y + 1 < z
+    let c = Constraint::mk_exists_int(
+        x,
+        Constraint::mk_conj(Constraint::mk_lt(v(y), v(x)), Constraint::mk_lt(v(x), v(z))),
+    );
+    let r = Cooper {}.solve(&c);
+    println!("{r}");
+    assert!(r.fv().is_subset(&vec![y, z].into_iter().collect()));
+    assert!(check_equivalent(&r, &c));
+
+    // ∃x. y = 2x  ⟺  y mod 2 = 0
+    let c = Constraint::mk_exists_int(
+        x,
+        Constraint::mk_eq(v(y), Op::mk_mul(Op::mk_const(2), v(x))),
+    );
+    let r = Cooper {}.solve(&c);
+    println!("{r}");
+    assert!(!r.fv().contains(&x));
+    assert!(check_equivalent(&r, &c));
+
+    // ∀x. ∃z. x < 3z + y ∧ 3z + y ≤ x + 3
+    let three_z = Op::mk_add(Op::mk_mul(Op::mk_const(3), v(z)), v(y));
+    let c = Constraint::mk_univ_int(
+        x,
+        Constraint::mk_exists_int(
+            z,
+            Constraint::mk_conj(
+                Constraint::mk_lt(v(x), three_z.clone()),
+                Constraint::mk_leq(three_z, Op::mk_add(v(x), Op::mk_const(3))),
+            ),
+        ),
+    );
+    let r = Cooper {}.solve(&c);
+    println!("{r}");
+    assert!(r.fv().is_empty());
+    assert!(check_equivalent(&r, &Constraint::mk_true()));
+
+    // ∃x. x mod 3 = 1 ∧ y ≤ x ∧ x ≤ y + 1
+    let c = Constraint::mk_exists_int(
+        x,
+        Constraint::mk_conj(
+            Constraint::mk_eq(Op::mk_mod(v(x), Op::mk_const(3)), Op::mk_const(1)),
+            Constraint::mk_conj(
+                Constraint::mk_leq(v(y), v(x)),
+                Constraint::mk_leq(v(x), Op::mk_add(v(y), Op::mk_const(1))),
+            ),
+        ),
+    );
+    let r = Cooper {}.solve(&c);
+    println!("{r}");
+    assert!(!r.fv().contains(&x));
+    assert!(check_equivalent(&r, &c));
+
+    // ∃x. x mod 3 = (-4) mod 3 ∧ x ≠ y - 4 ∧ y - 4 ≤ x ∧ x ≤ y - 2
+    // `(-4) mod 3` is 2 in SMT-LIB, whereas Rust's `%` gives -1
+    let c = Constraint::mk_exists_int(
+        x,
+        Constraint::mk_conj(
+            Constraint::mk_eq(
+                Op::mk_mod(v(x), Op::mk_const(3)),
+                Op::mk_mod(Op::mk_const(-4), Op::mk_const(3)),
+            ),
+            Constraint::mk_conj(
+                Constraint::mk_neq(v(x), Op::mk_sub(v(y), Op::mk_const(4))),
+                Constraint::mk_conj(
+                    Constraint::mk_leq(Op::mk_sub(v(y), Op::mk_const(4)), v(x)),
+                    Constraint::mk_leq(v(x), Op::mk_sub(v(y), Op::mk_const(2))),
+                ),
+            ),
+        ),
+    );
+    let r = Cooper {}.solve(&c);
+    println!("{r}");
+    assert!(!r.fv().contains(&x));
+    assert!(check_equivalent(&r, &c));
+
+    // non-linear constraints are not supported
+    let c = Constraint::mk_exists_int(x, Constraint::mk_eq(Op::mk_mul(v(x), v(x)), v(y)));
+    assert!(Cooper {}.try_solve(&c).is_err());
+
+    // `mod` of other shapes is not supported
+    let c = Constraint::mk_exists_int(
+        x,
+        Constraint::mk_lt(Op::mk_mod(v(x), Op::mk_const(3)), v(y)),
+    );
+    assert!(Cooper {}.try_solve(&c).is_err());
+
+    // too large moduli are not supported
+    let c = Constraint::mk_exists_int(
+        x,
+        Constraint::mk_conj(
+            Constraint::mk_eq(Op::mk_mod(v(x), Op::mk_const(1 << 20)), Op::mk_const(1)),
+            Constraint::mk_eq(Op::mk_mod(v(x), Op::mk_const(7)), Op::mk_const(1)),
+        ),
+    );
+    assert!(Cooper {}.try_solve(&c).is_err());
+    let c = Constraint::mk_exists_int(
+        x,
+        Constraint::mk_conj(
+            Constraint::mk_eq(Op::mk_mod(v(x), Op::mk_const(1000)), Op::mk_const(1)),
+            Constraint::mk_eq(Op::mk_mod(v(x), Op::mk_const(999)), Op::mk_const(1)),
+        ),
+    );
+    assert!(Cooper {}.try_solve(&c).is_err());
+}
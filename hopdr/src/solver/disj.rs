@@ -272,12 +272,10 @@ fn solve_chcs(clauses: &[CHC], current_model: &chc::Model) -> Option<Model> {
     let m = match solver::chc::default_solver().solve(&clauses) {
         solver::chc::CHCResult::Sat(m) => m,
         solver::chc::CHCResult::Unsat => return None,
-        solver::chc::CHCResult::Unknown => {
-            panic!("PDR fails to infer a refinement type due to the background CHC solver's error")
+        solver::chc::CHCResult::Unknown | solver::chc::CHCResult::Timeout => {
+            warn!("PDR fails to infer a refinement type since the background CHC solver gave up");
+            return None;
         }
-        solver::chc::CHCResult::Timeout => panic!(
-            "PDR fails to infer a refinement type due to timeout of the background CHC solver"
-        ),
     };
 
     // 3. interpolate it
@@ -285,12 +283,12 @@ fn solve_chcs(clauses: &[CHC], current_model: &chc::Model) -> Option<Model> {
     // TODO: Display model
     debug!("{}", m);
     let config = solver::interpolation::InterpolationConfig::new();
-    let m = solver::interpolation::solve(&clauses, &config);
-
-    Some(m)
+    solver::interpolation::solve(&clauses, &config)
 }
 
-pub fn solve(clauses: &[Clause]) -> Model {
+/// returns None if one of the CHC problems is unsat, or the background solvers give up
+/// (or time out)
+pub fn solve(clauses: &[Clause]) -> Option<Model> {
     crate::title!("clauses");
     for clause in clauses {
         debug!("- {}", clause);
@@ -308,8 +306,8 @@ pub fn solve(clauses: &[Clause]) -> Model {
     let mut current_model = Model::new();
     // 2. solve constraints sequentially
     for problem in problems {
-        let model = solve_chcs(&problem, &current_model).unwrap();
+        let model = solve_chcs(&problem, &current_model)?;
         current_model.merge(model);
     }
-    current_model
+    Some(current_model)
 }
@@ -0,0 +1,268 @@
+//! Interpolation of linear constraints based on Farkas' lemma (McMillan, TACAS 2004).
+//!
+//! Given `A` and `B` such that `A ∧ B` is unsatisfiable, both of which are conjunctions
+//! of linear inequalities `eᵢ ≥ 0`, Farkas' lemma gives λᵢ ≥ 0 for `A` and μⱼ ≥ 0 for `B`
+//! such that `Σλᵢeᵢ + Σμⱼe'ⱼ` is a negative constant. Then, `Σλᵢeᵢ ≥ 0` is an interpolant:
+//! it is implied by `A`, contradicts `B`, and the coefficients of the variables local to
+//! `A` cancel out. The multipliers are found by the SMT solver of the current registry.
+//!
+//! Disjunctions are handled by splitting both sides into DNF: for `A = ∨ᵢ Aᵢ` and
+//! `B = ∨ⱼ Bⱼ`, `∨ᵢ ∧ⱼ Iᵢⱼ` is an interpolant where `Iᵢⱼ` interpolates `Aᵢ` and `Bⱼ`.
+//!
+//! Farkas' lemma is complete only for rationals, so interpolation fails when `A ∧ B` is
+//! unsatisfiable over integers but satisfiable over rationals (e.g. `x = 2y` and
+//! `x = 2z + 1`), or when the constraints are not linear.
+use super::smt;
+use crate::formula::{Bot, Constraint, ConstraintExpr, Fv, Ident, Logic, Op, PredKind, Top};
+
+use std::collections::HashSet;
+
+/// Upper bound of the number of pairs of disjuncts of both sides
+const MAX_DISJUNCT_PAIRS: usize = 64;
+
+/// Linear inequality `Σ coefs[i] * vars[i] + constant ≥ 0`
+#[derive(Clone, Debug)]
+struct Row {
+    coefs: Vec<i64>,
+    constant: i64,
+}
+
+fn gcd(x: i64, y: i64) -> i64 {
+    if y == 0 {
+        x.abs()
+    } else {
+        gcd(y, x % y)
+    }
+}
+
+impl Row {
+    /// `o ≥ 0`
+    fn from_op(o: &Op, vars: &Vec<Ident>) -> Option<Row> {
+        let v = o.normalize(vars)?;
+        let v = v
+            .iter()
+            .map(|o| o.eval_with_empty_env())
+            .collect::<Option<Vec<_>>>()?;
+        let (constant, coefs) = v.split_last().unwrap();
+        Some(Row {
+            coefs: coefs.to_vec(),
+            constant: *constant,
+        })
+    }
+
+    /// Divides the coefficients by their gcd, which makes the inequality stronger
+    /// without changing its integer solutions.
+    fn tighten(mut self) -> Row {
+        let g = self.coefs.iter().fold(0, |g, c| gcd(g, *c));
+        if g > 1 {
+            self.coefs.iter_mut().for_each(|c| *c /= g);
+            self.constant = self.constant.div_euclid(g);
+        }
+        self
+    }
+
+    fn to_constraint(&self, vars: &[Ident]) -> Constraint {
+        let o = self
+            .coefs
+            .iter()
+            .zip(vars.iter())
+            .filter(|(c, _)| **c != 0)
+            .map(|(c, x)| match c {
+                1 => Op::mk_var(*x),
+                c => Op::mk_mul(Op::mk_const(*c), Op::mk_var(*x)),
+            })
+            .reduce(Op::mk_add);
+        match o {
+            Some(o) => Constraint::mk_geq(o, Op::mk_const(-self.constant)),
+            None if self.constant >= 0 => Constraint::mk_true(),
+            None => Constraint::mk_false(),
+        }
+    }
+}
+
+/// Translates a conjunction of atoms to inequalities. `≠` must have been eliminated.
+fn to_rows(c: &Constraint, vars: &Vec<Ident>) -> Option<Vec<Row>> {
+    let mut rows = Vec::new();
+    for atom in c.to_cnf() {
+        match atom.kind() {
+            ConstraintExpr::True => (),
+            ConstraintExpr::False => rows.push(Row {
+                coefs: vec![0; vars.len()],
+                constant: -1,
+            }),
+            ConstraintExpr::Pred(p, l) if l.len() == 2 => {
+                let x = l[0].clone();
+                let y = l[1].clone();
+                let one = Op::mk_const(1);
+                let ops = match p {
+                    PredKind::Geq => vec![Op::mk_sub(x, y)],
+                    PredKind::Leq => vec![Op::mk_sub(y, x)],
+                    PredKind::Gt => vec![Op::mk_sub(Op::mk_sub(x, y), one)],
+                    PredKind::Lt => vec![Op::mk_sub(Op::mk_sub(y, x), one)],
+                    PredKind::Eq => vec![Op::mk_sub(x.clone(), y.clone()), Op::mk_sub(y, x)],
+                    PredKind::Neq => return None,
+                };
+                for o in ops {
+                    rows.push(Row::from_op(&o, vars)?);
+                }
+            }
+            _ => return None,
+        }
+    }
+    Some(rows)
+}
+
+/// Replaces `x ≠ y` with `x < y ∨ x > y`. Quantifiers are not supported.
+fn eliminate_neq(c: &Constraint) -> Option<Constraint> {
+    let c = match c.kind() {
+        ConstraintExpr::True | ConstraintExpr::False => c.clone(),
+        ConstraintExpr::Pred(PredKind::Neq, l) if l.len() == 2 => Constraint::mk_disj(
+            Constraint::mk_lt(l[0].clone(), l[1].clone()),
+            Constraint::mk_gt(l[0].clone(), l[1].clone()),
+        ),
+        ConstraintExpr::Pred(_, _) => c.clone(),
+        ConstraintExpr::Conj(x, y) => Constraint::mk_conj(eliminate_neq(x)?, eliminate_neq(y)?),
+        ConstraintExpr::Disj(x, y) => Constraint::mk_disj(eliminate_neq(x)?, eliminate_neq(y)?),
+        ConstraintExpr::Quantifier(_, _, _) => return None,
+    };
+    Some(c)
+}
+
+/// Finds the Farkas multipliers for `left ∧ right`, and returns `Σλᵢeᵢ ≥ 0` where `eᵢ ≥ 0`
+/// are the rows of `left`.
+fn interpolate_rows(left: &[Row], right: &[Row], n_vars: usize) -> Option<Row> {
+    let lambdas: Vec<_> = left.iter().map(|_| Ident::fresh()).collect();
+    let mus: Vec<_> = right.iter().map(|_| Ident::fresh()).collect();
+    let rows: Vec<_> = left.iter().chain(right.iter()).collect();
+    let multipliers: Vec<_> = lambdas.iter().chain(mus.iter()).copied().collect();
+
+    let combination = |f: &dyn Fn(&Row) -> i64| {
+        rows.iter()
+            .zip(multipliers.iter())
+            .filter(|(r, _)| f(r) != 0)
+            .map(|(r, m)| Op::mk_mul(Op::mk_const(f(r)), Op::mk_var(*m)))
+            .fold(Op::mk_const(0), Op::mk_add)
+    };
+    let mut c = Constraint::mk_true();
+    for i in 0..n_vars {
+        // the coefficients of the variables cancel out
+        let o = combination(&|r| r.coefs[i]);
+        c = Constraint::mk_conj(c, Constraint::mk_eq(o, Op::mk_const(0)));
+    }
+    // and the constant is negative
+    let o = combination(&|r| r.constant);
+    c = Constraint::mk_conj(c, Constraint::mk_lt(o, Op::mk_const(0)));
+    for m in multipliers.iter() {
+        c = Constraint::mk_conj(c, Constraint::mk_geq(Op::mk_var(*m), Op::mk_const(0)));
+    }
+
+    let fvs = multipliers.iter().copied().collect();
+    let model = smt::default_solver()
+        .solve_with_model(&c, &HashSet::new(), &fvs)
+        .ok()?;
+
+    let mut row = Row {
+        coefs: vec![0; n_vars],
+        constant: 0,
+    };
+    for (r, l) in left.iter().zip(lambdas.iter()) {
+        let l = model.get(l)?;
+        for i in 0..n_vars {
+            row.coefs[i] = row.coefs[i].checked_add(l.checked_mul(r.coefs[i])?)?;
+        }
+        row.constant = row.constant.checked_add(l.checked_mul(r.constant)?)?;
+    }
+    Some(row.tighten())
+}
+
+/// Calculates ψ such that `left ⇒ ψ` and `ψ ∧ right` is unsatisfiable.
+pub(super) fn interpolate(left: &Constraint, right: &Constraint) -> Option<Constraint> {
+    let mut fvs = left.fv();
+    right.fv_with_vec(&mut fvs);
+    let mut vars: Vec<_> = fvs.into_iter().collect();
+    // for deterministic results
+    vars.sort();
+
+    let lefts = eliminate_neq(left)?.to_dnf();
+    let rights = eliminate_neq(right)?.to_dnf();
+    if lefts.len() * rights.len() > MAX_DISJUNCT_PAIRS {
+        debug!(
+            "farkas: too many disjuncts ({} * {})",
+            lefts.len(),
+            rights.len()
+        );
+        return None;
+    }
+    let rights = rights
+        .iter()
+        .map(|c| to_rows(c, &vars))
+        .collect::<Option<Vec<_>>>()?;
+
+    let mut result = Constraint::mk_false();
+    for l in lefts.iter() {
+        let l = to_rows(l, &vars)?;
+        let mut c = Constraint::mk_true();
+        for r in rights.iter() {
+            let row = interpolate_rows(&l, r, vars.len())?;
+            c = Constraint::mk_conj(c, row.to_constraint(&vars));
+        }
+        result = Constraint::mk_disj(result, c);
+    }
+    debug!("farkas interpolant: {result}");
+    Some(result)
+}
+
+#[test]
+fn test_farkas_interpolation() {
+    use crate::formula::Negation;
+    let x = Ident::fresh();
+    let y = Ident::fresh();
+    let z = Ident::fresh();
+
+    let check = |left: &Constraint, right: &Constraint, psi: &Constraint| {
+        let mut solver = smt::default_solver();
+        let c1 = Constraint::mk_implies(left.clone(), psi.clone());
+        assert!(solver.solve_with_universal_quantifiers(&c1).is_sat());
+        let c2 = Constraint::mk_conj(psi.clone(), right.clone());
+        assert!(solver
+            .solve_with_universal_quantifiers(&c2.negate().unwrap())
+            .is_sat());
+        let shared: HashSet<_> = left.fv().intersection(&right.fv()).copied().collect();
+        assert!(psi.fv().is_subset(&shared));
+    };
+
+    // x = y + 1 ∧ y ≥ 0 ; x ≤ 0 ∧ z = x
+    let left = Constraint::mk_conj(
+        Constraint::mk_eq(Op::mk_var(x), Op::mk_add(Op::mk_var(y), Op::mk_const(1))),
+        Constraint::mk_geq(Op::mk_var(y), Op::mk_const(0)),
+    );
+    let right = Constraint::mk_conj(
+        Constraint::mk_leq(Op::mk_var(x), Op::mk_const(0)),
+        Constraint::mk_eq(Op::mk_var(z), Op::mk_var(x)),
+    );
+    let psi = interpolate(&left, &right).unwrap();
+    println!("{psi}");
+    check(&left, &right, &psi);
+
+    // (x = 0 ∨ x = 2) ; x ≠ 0 ∧ x ≠ 2
+    let left = Constraint::mk_disj(
+        Constraint::mk_eq(Op::mk_var(x), Op::mk_const(0)),
+        Constraint::mk_eq(Op::mk_var(x), Op::mk_const(2)),
+    );
+    let right = Constraint::mk_conj(
+        Constraint::mk_neq(Op::mk_var(x), Op::mk_const(0)),
+        Constraint::mk_neq(Op::mk_var(x), Op::mk_const(2)),
+    );
+    let psi = interpolate(&left, &right).unwrap();
+    println!("{psi}");
+    check(&left, &right, &psi);
+
+    // 2x = y ; 2x = y + 1 is unsat only over integers
+    let two = || Op::mk_const(2);
+    let left = Constraint::mk_eq(Op::mk_mul(two(), Op::mk_var(x)), Op::mk_var(y));
+    let right = Constraint::mk_eq(
+        Op::mk_mul(two(), Op::mk_var(z)),
+        Op::mk_add(Op::mk_var(y), Op::mk_const(1)),
+    );
+    assert!(interpolate(&left, &right).is_none());
+}
@@ -22,55 +22,38 @@ use crate::solver::util;
 use crate::solver::{smt, SMTSolverType};
 
 use anyhow::{bail, Result};
-use home::home_dir;
 use thiserror::Error;
 
 use std::collections::{HashMap, HashSet};
-use std::ptr::addr_of;
-use std::time::Duration;
 
 type CHC = chc::CHC<chc::Atom, Constraint>;
 type CHCBody = chc::CHCBody<chc::Atom, Constraint>;
 
-static mut SMTINTERPOL_PATH: Option<String> = None;
-pub fn set_smt_interpol_path(path: String) {
-    unsafe {
-        SMTINTERPOL_PATH = Some(path);
-    }
-}
-fn get_smt_interpol_path() -> String {
-    match unsafe { addr_of!(SMTINTERPOL_PATH).as_ref() } {
-        Some(Some(s)) => s.clone(),
-        _ => {
-            let mut home = home_dir().unwrap();
-            home.push(".local/share/hopdr/smtinterpol.jar");
-            home.into_os_string().into_string().unwrap()
-        }
-    }
-}
-
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug)]
 pub enum InterpolationSolver {
     SMTInterpol,
     Csisat,
     Spacer,
     Hoice,
     SVMInterpol,
+    /// Farkas' lemma based interpolation, which needs no CHC solver
+    Farkas,
 }
 
-static mut DEFAULT_SOLVER: Option<InterpolationSolver> = None;
-
-pub fn set_default_solver(solver_name: &str) -> bool {
-    let sol = match solver_name {
-        "smtinterpol" => InterpolationSolver::SMTInterpol,
-        "csisat" => InterpolationSolver::Csisat,
-        "spacer" => InterpolationSolver::Spacer,
-        "hoice" => InterpolationSolver::Hoice,
-        "svminterpol" => InterpolationSolver::SVMInterpol,
-        _ => return false,
-    };
-    unsafe { DEFAULT_SOLVER = Some(sol) }
-    true
+impl InterpolationSolver {
+    /// parses the name accepted by `--default-interpolation-solver`
+    pub fn from_name(name: &str) -> Option<InterpolationSolver> {
+        let sol = match name {
+            "smtinterpol" => InterpolationSolver::SMTInterpol,
+            "csisat" => InterpolationSolver::Csisat,
+            "spacer" => InterpolationSolver::Spacer,
+            "hoice" => InterpolationSolver::Hoice,
+            "svminterpol" => InterpolationSolver::SVMInterpol,
+            "farkas" => InterpolationSolver::Farkas,
+            _ => return None,
+        };
+        Some(sol)
+    }
 }
 
 // topological sort
@@ -388,6 +371,7 @@ struct CsisatSolver {}
 struct SpacerSolver {}
 struct HoiceSolver {}
 struct SVMInterpol {}
+struct FarkasSolver {}
 
 impl InterpolationSolver {
     pub fn get_solver(sol: InterpolationSolver) -> Box<dyn Interpolation> {
@@ -397,14 +381,12 @@ impl InterpolationSolver {
             InterpolationSolver::Spacer => Box::new(SpacerSolver {}),
             InterpolationSolver::Hoice => Box::new(HoiceSolver {}),
             InterpolationSolver::SVMInterpol => Box::new(SVMInterpol {}),
+            InterpolationSolver::Farkas => Box::new(FarkasSolver {}),
         }
     }
+    /// returns the interpolation solver of the current registry
     pub fn default_solver() -> Box<dyn Interpolation> {
-        let sol = match unsafe { DEFAULT_SOLVER } {
-            Some(sol) => sol,
-            None => InterpolationSolver::Csisat,
-        };
-        Self::get_solver(sol)
+        Self::get_solver(solver::registry::with(|r| r.interpolation))
     }
 }
 
@@ -457,11 +439,10 @@ impl SMTInterpolSolver {
 
         (result, fvs)
     }
-    fn execute_solver(&mut self, smt_string: String) -> String {
+    fn execute_solver(&mut self, smt_string: String) -> Result<String, util::ExecError> {
         debug!("smt_string: {}", &smt_string);
         let f = smt::save_smt2(smt_string);
-        // TODO: determine the path when it's compiled
-        let s = get_smt_interpol_path();
+        let s = solver::registry::with(|r| r.paths.smtinterpol.clone());
         let args = vec!["-jar", &s, f.path().to_str().unwrap()];
         debug!("filename: {}", &args[0]);
         let out = interp_execution!({
@@ -469,10 +450,10 @@ impl SMTInterpolSolver {
                 "java",
                 //"../../../Hogeyama/hoice/target/debug/hoice",
                 &args,
-                Duration::from_secs(1),
+                super::timeouts().interpolation,
             )
         });
-        String::from_utf8(out).unwrap()
+        Ok(String::from_utf8(out?).unwrap())
     }
     fn parse_result(
         &mut self,
@@ -502,22 +483,29 @@ impl Interpolation for SMTInterpolSolver {
     fn interpolate(&mut self, left: &Constraint, right: &Constraint) -> Option<Constraint> {
         let (s, fvs) = self.generate_smt_string(left, right);
         //panic!("{}", s);
-        let r = self.execute_solver(s);
+        let r = match self.execute_solver(s) {
+            Ok(r) => r,
+            Err(e) => {
+                warn!("smtinterpol: {}", e);
+                return None;
+            }
+        };
         self.parse_result(r, fvs).ok()
     }
 }
 
 impl CsisatSolver {
-    fn execute_solver(&mut self, query: &str) -> String {
+    fn execute_solver(&mut self, query: &str) -> Result<String, util::ExecError> {
+        let csisat = solver::registry::with(|r| r.paths.csisat.clone());
         let out = interp_execution!({
             util::exec_input_with_timeout(
-                "csisat",
+                &csisat,
                 &["-int", "-round"],
                 query.as_bytes(),
-                Duration::from_secs(1),
+                super::timeouts().interpolation,
             )
         });
-        String::from_utf8(out).unwrap()
+        Ok(String::from_utf8(out?).unwrap())
     }
     fn parse_result(&mut self, result: String) -> Option<Constraint> {
         super::csisat::parse(&result)
@@ -531,7 +519,13 @@ impl Interpolation for CsisatSolver {
 
         let query = format!("{} ; {}", lefts, rights);
 
-        let s = self.execute_solver(&query);
+        let s = match self.execute_solver(&query) {
+            Ok(s) => s,
+            Err(e) => {
+                warn!("csisat: {}", e);
+                return None;
+            }
+        };
         crate::title!("csisat");
         debug!("query: {}", query);
         debug!("result: {}", s);
@@ -559,7 +553,7 @@ fn test_csisat() {
 }
 
 impl SVMInterpol {
-    fn execute_solver(&mut self, input1: &str, input2: &str) -> String {
+    fn execute_solver(&mut self, input1: &str, input2: &str) -> Result<String, util::ExecError> {
         println!("input1: {input1}");
         println!("input2: {input2}");
         let mut vs = Vec::new();
@@ -568,12 +562,13 @@ impl SVMInterpol {
         vs.push("--input2");
         vs.push(input2);
 
+        let svminterpol = solver::registry::with(|r| r.paths.svminterpol.clone());
         let out = interp_execution!({
-            util::exec_input_with_timeout("svminterpol", &vs, b"", Duration::from_secs(1))
+            util::exec_input_with_timeout(&svminterpol, &vs, b"", super::timeouts().interpolation)
         });
-        let s = String::from_utf8(out).unwrap();
+        let s = String::from_utf8(out?).unwrap();
         println!("result: {s}");
-        s
+        Ok(s)
     }
     fn parse_result(&mut self, result: String) -> Option<Constraint> {
         super::csisat::parse(&result)
@@ -584,7 +579,13 @@ impl Interpolation for SVMInterpol {
         let lefts = super::csisat::constraint_to_csisat(left);
         let rights = super::csisat::constraint_to_csisat(right);
 
-        let s = self.execute_solver(&lefts, &rights);
+        let s = match self.execute_solver(&lefts, &rights) {
+            Ok(s) => s,
+            Err(e) => {
+                warn!("svminterpol: {}", e);
+                return None;
+            }
+        };
         crate::title!("svminterpol ");
         debug!("left: {}", lefts);
         debug!("right: {}", rights);
@@ -611,7 +612,65 @@ impl Interpolation for SVMInterpol {
 //     println!("{}", s);
 // }
 
-fn interpolate_by_chc(left: &Constraint, right: &Constraint, style: CHCStyle) -> Constraint {
+impl Interpolation for FarkasSolver {
+    fn interpolate(&mut self, left: &Constraint, right: &Constraint) -> Option<Constraint> {
+        let r = interp_execution!({ super::farkas::interpolate(left, right) });
+        if r.is_none() {
+            debug!("farkas interpolation failed: {left} ; {right}");
+        }
+        r
+    }
+}
+
+#[test]
+fn test_farkas_interpolation_preds() {
+    use crate::formula::chc::Atom;
+    use chc::CHCHead;
+    // x = 0 => P(x)
+    // P(x) /\ y = x + 1 => Q(y)
+    // Q(y) => y > 0
+    let x = Ident::fresh();
+    let y = Ident::fresh();
+    let p = Ident::fresh();
+    let q = Ident::fresh();
+    let c1 = CHC {
+        body: CHCBody {
+            predicates: Vec::new(),
+            constraint: Constraint::mk_eq(Op::mk_var(x), Op::mk_const(0)),
+        },
+        head: CHCHead::Predicate(Atom::new(p, vec![Op::mk_var(x)])),
+    };
+    let c2 = CHC {
+        body: CHCBody {
+            predicates: vec![Atom::new(p, vec![Op::mk_var(x)])],
+            constraint: Constraint::mk_eq(
+                Op::mk_var(y),
+                Op::mk_add(Op::mk_var(x), Op::mk_const(1)),
+            ),
+        },
+        head: CHCHead::Predicate(Atom::new(q, vec![Op::mk_var(y)])),
+    };
+    let c3 = CHC {
+        body: CHCBody {
+            predicates: vec![Atom::new(q, vec![Op::mk_var(y)])],
+            constraint: Constraint::mk_true(),
+        },
+        head: CHCHead::Constraint(Constraint::mk_gt(Op::mk_var(y), Op::mk_const(0))),
+    };
+    let clauses = vec![c1, c2, c3];
+    let (preds, n_args) = topological_sort(&clauses).unwrap();
+    let least_model = generate_least_solution(&clauses, &preds, &n_args);
+    let get = || InterpolationSolver::get_solver(InterpolationSolver::Farkas);
+    let m = interpolate_preds(&clauses, &preds, &n_args, &least_model, get(), get()).unwrap();
+    assert!(crate::solver::chc::is_solution_valid(&clauses, &m));
+}
+
+/// returns None if the CHC solver gives up (or times out)
+fn interpolate_by_chc(
+    left: &Constraint,
+    right: &Constraint,
+    style: CHCStyle,
+) -> Option<Constraint> {
     use crate::solver::chc::chc_solver;
 
     debug!("{left} => {right}");
@@ -644,25 +703,26 @@ fn interpolate_by_chc(left: &Constraint, right: &Constraint, style: CHCStyle) ->
             panic!("interpolation: unsat, failed")
         }
         solver::chc::CHCResult::Unknown | solver::chc::CHCResult::Timeout => {
-            panic!("interpolation failed")
+            warn!("interpolation by {:?} failed", style);
+            return None;
         }
     };
     assert_eq!(idents.len(), args.len());
     for (i, o) in idents.into_iter().zip(args.into_iter()) {
         c = c.subst(&i, &o);
     }
-    c
+    Some(c)
 }
 
 impl Interpolation for SpacerSolver {
     fn interpolate(&mut self, left: &Constraint, right: &Constraint) -> Option<Constraint> {
-        Some(interpolate_by_chc(left, right, CHCStyle::Spacer))
+        interpolate_by_chc(left, right, CHCStyle::Spacer)
     }
 }
 
 impl Interpolation for HoiceSolver {
     fn interpolate(&mut self, left: &Constraint, right: &Constraint) -> Option<Constraint> {
-        Some(interpolate_by_chc(left, right, CHCStyle::Hoice))
+        interpolate_by_chc(left, right, CHCStyle::Hoice)
     }
 }
 
@@ -729,7 +789,7 @@ fn interpolate_preds(
     least_model: &Model,
     mut solver: Box<dyn Interpolation>,
     mut backup_solver: Box<dyn Interpolation>,
-) -> Model {
+) -> Option<Model> {
     crate::title!("intepolate_preds");
     debug_assert!(crate::solver::chc::is_solution_valid(chc, least_model));
     let mut model = Model::new();
@@ -840,8 +900,7 @@ fn interpolate_preds(
         crate::title!("trying to interpolate...");
         let c = solver
             .interpolate(&weakest, &strongest)
-            .or_else(|| backup_solver.interpolate(&weakest, &strongest))
-            .unwrap();
+            .or_else(|| backup_solver.interpolate(&weakest, &strongest))?;
 
         #[cfg(debug_assertions)]
         {
@@ -860,7 +919,7 @@ fn interpolate_preds(
         debug!("interpolated: {}({s}) = {c}", p);
         model.model.insert(*p, (arg_vars, c));
     }
-    model
+    Some(model)
 }
 
 pub struct InterpolationConfig {
@@ -888,7 +947,8 @@ impl Default for InterpolationConfig {
 /// interpolate predicates under the given CHC constraints.
 ///
 /// Assumption: `chc' is satisfiable.
-pub fn solve_old(chc: &Vec<CHC>, _config: &InterpolationConfig) -> Model {
+/// Returns None if the background solver gives up (or times out).
+pub fn solve_old(chc: &Vec<CHC>, _config: &InterpolationConfig) -> Option<Model> {
     use solver::chc::CHCSolver;
     debug!("[interpolation::solve]");
     for c in chc {
@@ -898,20 +958,24 @@ pub fn solve_old(chc: &Vec<CHC>, _config: &InterpolationConfig) -> Model {
     let chc: Vec<_> = chc.iter().map(|c| c.fresh_variables()).collect();
     match solver.solve(&chc) {
         solver::chc::CHCResult::Sat(mut m) => {
-            let qe_solver = solver::qe::default_solver();
+            let qe_solver = solver::qe::native_solver_with_fallback(solver::qe::default_solver());
             qe_solver.model_quantifer_elimination(&mut m);
-            m
+            Some(m)
         }
-        solver::chc::CHCResult::Unsat
-        | solver::chc::CHCResult::Unknown
-        | solver::chc::CHCResult::Timeout => panic!("program error"),
+        solver::chc::CHCResult::Unsat => panic!("program error"),
+        solver::chc::CHCResult::Unknown | solver::chc::CHCResult::Timeout => None,
     }
 }
 
 /// interpolate predicates under the given CHC constraints.
 ///
 /// Assumption: `chc' is satisfiable.
-pub fn solve(chc: &Vec<CHC>, config: &InterpolationConfig) -> Model {
+/// Returns None if all the interpolation strategies give up (or time out).
+pub fn solve(chc: &Vec<CHC>, config: &InterpolationConfig) -> Option<Model> {
+    solver::cache::interpolation(chc, || solve_uncached(chc, config))
+}
+
+fn solve_uncached(chc: &Vec<CHC>, config: &InterpolationConfig) -> Option<Model> {
     debug!("[interpolation::solve]");
     for c in chc {
         debug!("- {}", c);
@@ -942,13 +1006,15 @@ pub fn solve(chc: &Vec<CHC>, config: &InterpolationConfig) -> Model {
             let mut solver = solver::chc::interpolating_solver();
             match solver.solve(chc) {
                 solver::chc::CHCResult::Sat(mut m) => {
-                    let qe_solver = solver::qe::default_solver();
+                    let qe_solver =
+                        solver::qe::native_solver_with_fallback(solver::qe::default_solver());
                     qe_solver.model_quantifer_elimination(&mut m);
-                    m
+                    Some(m)
                 }
                 solver::chc::CHCResult::Unsat => panic!("program error: unsat!"),
                 solver::chc::CHCResult::Unknown | solver::chc::CHCResult::Timeout => {
-                    panic!("program error")
+                    warn!("interpolation by the CHC solver failed");
+                    None
                 }
             }
         }
@@ -995,7 +1061,7 @@ fn test_interpolation() {
     let clauses = vec![clause1, clause2];
 
     let config = InterpolationConfig::new().use_chc_if_requied();
-    let m = solve(&clauses, &config);
+    let m = solve(&clauses, &config).unwrap();
 
     for (x, (_, z)) in m.model {
         println!("{} => {}", x, z)
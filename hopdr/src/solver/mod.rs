@@ -1,23 +1,62 @@
-use std::{collections::HashSet, fmt};
+use serde::{Deserialize, Serialize};
 
+use std::{collections::HashSet, fmt, time::Duration};
+
+pub mod args;
+pub mod cache;
 pub mod chc;
+mod cooper;
 mod csisat;
 pub mod disj;
+mod farkas;
 pub mod interpolation;
 pub mod qe;
+pub mod registry;
 pub mod sat;
 pub mod smt;
 pub mod util;
+mod z3lib;
+
+pub use registry::SolverRegistry;
+
+/// Time limits of the invocations of external solvers for each kind of query.
+/// `None` means no limit. A solver that exceeds its limit is killed, and the
+/// query results in `Timeout` (or a failure where there is no such result).
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SolverTimeouts {
+    pub smt: Option<Duration>,
+    pub chc: Option<Duration>,
+    pub qe: Option<Duration>,
+    pub interpolation: Option<Duration>,
+}
+
+/// returns the time limits of the current registry
+pub fn timeouts() -> SolverTimeouts {
+    registry::with(|r| r.timeouts)
+}
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum SMTSolverType {
     Z3,
+    /// Z3 linked as a library (no process is spawned for each query)
+    Z3Lib,
     CVC,
     UltimateEliminator,
     Auto,
 }
 
-#[derive(Debug)]
+impl SMTSolverType {
+    /// parses the name accepted by `--smt-solver`
+    pub fn from_name(name: &str) -> Option<SMTSolverType> {
+        match name {
+            "z3" => Some(SMTSolverType::Z3),
+            "z3lib" => Some(SMTSolverType::Z3Lib),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
 pub enum SolverResult {
     Sat,
     Unsat,
@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use super::smt::{constraint_to_smt2_inner, encode_ident, ultimate_solver, z3_solver};
+use super::smt::{constraint_to_smt2_inner, encode_ident, invoke_smt_solver, ultimate_solver};
 use super::SMTSolverType;
 use crate::formula::chc::Model;
 use crate::formula::{Bot, Constraint, Fv, Ident, Logic, Negation, Op, OpKind, PredKind, Top};
@@ -13,28 +13,32 @@ use thiserror::Error;
 pub enum QEError {
     #[error("QE Result Parse Failed: `{0}` ")]
     FailedToParse(String),
+    #[error("QE timeout")]
+    Timeout,
+    #[error("QE unsupported: `{0}`")]
+    Unsupported(String),
 }
 
 pub trait QESolver {
-    fn to_smt(&self, formula: &Constraint) -> String;
-    fn solve_string(&self, s: String) -> String;
-    fn parse(&self, s: &str) -> Result<Constraint, lexpr::parse::Error>;
-
-    fn try_solve(&self, formula: &Constraint) -> Result<Constraint, QEError> {
-        debug!("trying quantifier elimination: {formula}");
-        let smt_string = self.to_smt(formula);
-        let result = self.solve_string(smt_string);
-        match self.parse(&result) {
-            Ok(r) => Ok(r),
-            Err(_) => Err(QEError::FailedToParse(result)),
-        }
-    }
+    /// returns a quantifier-free constraint equivalent to `formula`
+    fn try_solve(&self, formula: &Constraint) -> Result<Constraint, QEError>;
 
+    /// same as `try_solve`, but returns `formula` as it is when the elimination fails
+    /// (e.g. on `--qe-timeout`), so the result may still contain quantifiers
     fn solve(&self, formula: &Constraint) -> Constraint {
-        let r = self.try_solve(formula).unwrap();
-        debug!("result: {r}");
-        r
+        match self.try_solve(formula) {
+            Ok(r) => {
+                debug!("result: {r}");
+                r
+            }
+            Err(e) => {
+                warn!("quantifier elimination failed ({e}); {formula} is kept as it is");
+                formula.clone()
+            }
+        }
     }
+    /// eliminates existential quantifiers in the interpretations of `model` by `solve`;
+    /// those whose elimination fails are kept as they are
     fn model_quantifer_elimination(&self, model: &mut Model) {
         for (_, (_, c)) in model.model.iter_mut() {
             let (qs, _) = c.to_pnf_raw();
@@ -45,17 +49,105 @@ pub trait QESolver {
     }
 }
 
+/// QE solver that passes an SMT-LIB query to an external process
+pub trait ExternalQESolver {
+    fn to_smt(&self, formula: &Constraint) -> String;
+    fn solve_string(&self, s: String) -> Result<String, QEError>;
+    fn parse(&self, s: &str) -> Result<Constraint, lexpr::parse::Error>;
+}
+
+impl<T: ExternalQESolver> QESolver for T {
+    fn try_solve(&self, formula: &Constraint) -> Result<Constraint, QEError> {
+        debug!("trying quantifier elimination: {formula}");
+        let smt_string = self.to_smt(formula);
+        let result = self.solve_string(smt_string)?;
+        match self.parse(&result) {
+            Ok(r) => Ok(r),
+            Err(_) => Err(QEError::FailedToParse(result)),
+        }
+    }
+}
+
 pub struct Z3 {}
 pub struct UltimateEliminator {}
+pub use super::cooper::Cooper;
+
+/// Backend of quantifier elimination in `SolverRegistry`
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum QESolverType {
+    Z3,
+    UltimateEliminator,
+    /// Cooper's method alone: the formulas it fails on are not passed to external solvers
+    Native,
+}
+
+impl QESolverType {
+    /// parses the name accepted by `--qe-solver`
+    pub fn from_name(name: &str) -> Option<QESolverType> {
+        let sol = match name {
+            "z3" => QESolverType::Z3,
+            "ultimate_eliminator" => QESolverType::UltimateEliminator,
+            "native" => QESolverType::Native,
+            _ => return None,
+        };
+        Some(sol)
+    }
+}
+
+/// QE solver that consults the cache before invoking `inner`.
+struct Cached {
+    inner: Box<dyn QESolver>,
+}
+
+impl QESolver for Cached {
+    fn try_solve(&self, formula: &Constraint) -> Result<Constraint, QEError> {
+        super::cache::qe(formula, |f| self.inner.try_solve(f))
+    }
+}
 
 pub fn qe_solver(ty: SMTSolverType) -> Box<dyn QESolver> {
-    match ty {
-        SMTSolverType::Z3 | SMTSolverType::Auto => Box::new(Z3 {}),
+    let inner: Box<dyn QESolver> = match ty {
+        SMTSolverType::Z3 | SMTSolverType::Z3Lib | SMTSolverType::Auto => Box::new(Z3 {}),
         SMTSolverType::UltimateEliminator => Box::new(UltimateEliminator {}),
         SMTSolverType::CVC => unimplemented!(),
+    };
+    Box::new(Cached { inner })
+}
+
+/// Built-in QE solver for linear integer arithmetic, which needs no external process
+pub fn native_solver() -> Box<dyn QESolver> {
+    Box::new(Cached {
+        inner: Box::new(Cooper {}),
+    })
+}
+
+/// QE solver that tries `first`, and then `second` when `first` fails
+struct Fallback {
+    first: Box<dyn QESolver>,
+    second: Box<dyn QESolver>,
+}
+
+impl QESolver for Fallback {
+    fn try_solve(&self, formula: &Constraint) -> Result<Constraint, QEError> {
+        self.first.try_solve(formula).or_else(|e| {
+            debug!("falling back to another QE solver: {e}");
+            self.second.try_solve(formula)
+        })
     }
 }
 
+/// `native_solver` that delegates the formulas it fails on (e.g. non-linear ones) to `fallback`,
+/// unless the current registry restricts quantifier elimination to `QESolverType::Native`
+pub fn native_solver_with_fallback(fallback: Box<dyn QESolver>) -> Box<dyn QESolver> {
+    if super::registry::with(|r| r.qe) == QESolverType::Native {
+        return native_solver();
+    }
+    Box::new(Fallback {
+        first: native_solver(),
+        second: fallback,
+    })
+}
+
 fn parse_variable(v: &str) -> Ident {
     assert!(v.starts_with('x'));
     Ident::parse_ident(&v[1..]).unwrap_or_else(|| panic!("parse fail"))
@@ -318,8 +410,13 @@ fn test_parse_constraint() {
     assert!(c.alpha_equiv(&Constraint::mk_false()));
 }
 
+/// returns the QE solver of the current registry
 pub fn default_solver() -> Box<dyn QESolver> {
-    qe_solver(SMTSolverType::Z3)
+    match super::registry::with(|r| r.qe) {
+        QESolverType::Z3 => qe_solver(SMTSolverType::Z3),
+        QESolverType::UltimateEliminator => qe_solver(SMTSolverType::UltimateEliminator),
+        QESolverType::Native => native_solver(),
+    }
 }
 
 fn gen_declare_fun<'a>(itr: impl Iterator<Item = &'a Ident> + 'a) -> String {
@@ -328,7 +425,7 @@ fn gen_declare_fun<'a>(itr: impl Iterator<Item = &'a Ident> + 'a) -> String {
         .join("\n")
 }
 
-impl QESolver for Z3 {
+impl ExternalQESolver for Z3 {
     fn to_smt(&self, formula: &Constraint) -> String {
         let fvs = formula.fv();
         let declare_funs = gen_declare_fun(fvs.iter());
@@ -368,12 +465,13 @@ impl QESolver for Z3 {
         };
         Ok(c)
     }
-    fn solve_string(&self, s: String) -> String {
+    fn solve_string(&self, s: String) -> Result<String, QEError> {
         crate::stat::smt::smt_count();
         crate::stat::smt::start_clock();
-        let result = z3_solver(s);
+        let z3 = super::registry::with(|r| r.paths.z3.clone());
+        let result = invoke_smt_solver(&z3, s, super::timeouts().qe);
         crate::stat::smt::end_clock();
-        result
+        result.map_err(|_| QEError::Timeout)
     }
 }
 
@@ -386,7 +484,7 @@ fn test_z3_qe_result() {
   (>= x_x2 1)
   :precision precise :depth 1)
 )";
-    let z3_solver = qe_solver(SMTSolverType::Z3);
+    let z3_solver = Z3 {};
     let c = z3_solver.parse(s).unwrap();
 
     let x1 = Ident::fresh();
@@ -449,7 +547,7 @@ fn test_z3_qe_result() {
     z3_solver.parse(s).unwrap();
 }
 
-impl QESolver for UltimateEliminator {
+impl ExternalQESolver for UltimateEliminator {
     fn to_smt(&self, formula: &Constraint) -> String {
         let fvs = formula.fv();
         let declare_funs = gen_declare_fun(fvs.iter());
@@ -460,7 +558,7 @@ impl QESolver for UltimateEliminator {
         )
     }
 
-    fn solve_string(&self, s: String) -> String {
+    fn solve_string(&self, s: String) -> Result<String, QEError> {
         debug!("smt2: {s}");
         crate::stat::qe::qe_count();
         crate::stat::qe::start_clock();
@@ -468,7 +566,7 @@ impl QESolver for UltimateEliminator {
         let result = ultimate_solver(s);
 
         crate::stat::qe::end_clock();
-        result
+        result.map_err(|_| QEError::Timeout)
     }
 
     fn parse(&self, s: &str) -> Result<Constraint, lexpr::parse::Error> {
@@ -526,3 +624,44 @@ fn test_quantifier_elimination() {
         assert_eq!(v.len(), 0);
     }
 }
+
+#[test]
+fn test_solve_keeps_formula_on_failure() {
+    use crate::formula::FirstOrderLogic;
+    struct TimingOut;
+    impl QESolver for TimingOut {
+        fn try_solve(&self, _formula: &Constraint) -> Result<Constraint, QEError> {
+            Err(QEError::Timeout)
+        }
+    }
+    let x = Ident::fresh();
+    let c = Constraint::mk_exists_int(x, Constraint::mk_eq(Op::mk_var(x), Op::mk_const(0)));
+    assert_eq!(TimingOut.solve(&c), c);
+}
+
+#[test]
+fn test_native_alone() {
+    use crate::formula::FirstOrderLogic;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    struct Called(Arc<AtomicBool>);
+    impl QESolver for Called {
+        fn try_solve(&self, formula: &Constraint) -> Result<Constraint, QEError> {
+            self.0.store(true, Ordering::Relaxed);
+            Ok(formula.clone())
+        }
+    }
+    // ∃x. x * y = 1, which Cooper's method does not support
+    let x = Ident::fresh();
+    let y = Ident::fresh();
+    let c = Constraint::mk_eq(Op::mk_mul(Op::mk_var(x), Op::mk_var(y)), Op::mk_const(1));
+    let c = Constraint::mk_exists_int(x, c);
+    let called = Arc::new(AtomicBool::new(false));
+    native_solver_with_fallback(Box::new(Called(called.clone()))).solve(&c);
+    assert!(called.load(Ordering::Relaxed));
+
+    called.store(false, Ordering::Relaxed);
+    let registry = super::SolverRegistry::new().qe(QESolverType::Native);
+    registry.scope(|| native_solver_with_fallback(Box::new(Called(called.clone()))).solve(&c));
+    assert!(!called.load(Ordering::Relaxed));
+}
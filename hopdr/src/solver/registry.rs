@@ -0,0 +1,165 @@
+//! Which backend and binary is used for each kind of query.
+//!
+//! A `SolverRegistry` is carried in `Configuration`, and is installed for a run by
+//! `SolverRegistry::scope`. The solvers consult the registry of the current thread (or
+//! the default one outside any scope), so runs with different backends can coexist in
+//! one process.
+use super::chc::CHCStyle;
+use super::interpolation::InterpolationSolver;
+use super::qe::QESolverType;
+use super::{SMTSolverType, SolverTimeouts};
+
+use home::home_dir;
+use once_cell::sync::Lazy;
+
+use std::cell::RefCell;
+
+/// Commands (or paths) of the external solvers
+#[derive(Clone, Debug)]
+pub struct SolverPaths {
+    pub z3: String,
+    pub hoice: String,
+    pub ultimate_eliminator: String,
+    pub csisat: String,
+    pub svminterpol: String,
+    /// the jar file of SMTInterpol, which is run by `java -jar`
+    pub smtinterpol: String,
+}
+
+impl Default for SolverPaths {
+    fn default() -> Self {
+        let mut smtinterpol = home_dir().unwrap_or_default();
+        smtinterpol.push(".local/share/hopdr/smtinterpol.jar");
+        SolverPaths {
+            z3: "z3".to_string(),
+            hoice: "hoice".to_string(),
+            ultimate_eliminator: "ultimate_eliminator".to_string(),
+            csisat: "csisat".to_string(),
+            svminterpol: "svminterpol".to_string(),
+            smtinterpol: smtinterpol.to_string_lossy().into_owned(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SolverRegistry {
+    /// backend of SMT queries (`Z3` or `Z3Lib`)
+    pub smt: SMTSolverType,
+    pub chc: CHCStyle,
+    pub qe: QESolverType,
+    pub interpolation: InterpolationSolver,
+    pub paths: SolverPaths,
+    pub timeouts: SolverTimeouts,
+    /// whether the results of the queries are cached (see `solver::cache`)
+    pub cache: bool,
+}
+
+impl Default for SolverRegistry {
+    fn default() -> Self {
+        SolverRegistry {
+            smt: SMTSolverType::Z3,
+            chc: CHCStyle::Hoice,
+            qe: QESolverType::Z3,
+            interpolation: InterpolationSolver::Csisat,
+            paths: SolverPaths::default(),
+            timeouts: SolverTimeouts::default(),
+            cache: true,
+        }
+    }
+}
+
+impl SolverRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// set the backend of SMT queries
+    pub fn smt(mut self, smt: SMTSolverType) -> Self {
+        self.smt = smt;
+        self
+    }
+
+    /// set the backend of CHC queries
+    pub fn chc(mut self, chc: CHCStyle) -> Self {
+        self.chc = chc;
+        self
+    }
+
+    /// set the backend of quantifier elimination
+    pub fn qe(mut self, qe: QESolverType) -> Self {
+        self.qe = qe;
+        self
+    }
+
+    /// set the backend of interpolation
+    pub fn interpolation(mut self, interpolation: InterpolationSolver) -> Self {
+        self.interpolation = interpolation;
+        self
+    }
+
+    /// set the commands of the external solvers
+    pub fn paths(mut self, paths: SolverPaths) -> Self {
+        self.paths = paths;
+        self
+    }
+
+    /// set time limits of external solvers
+    pub fn timeouts(mut self, timeouts: SolverTimeouts) -> Self {
+        self.timeouts = timeouts;
+        self
+    }
+
+    /// set whether the results of the queries are cached
+    pub fn cache(mut self, cache: bool) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// runs `f` with `self` as the registry of this thread
+    pub fn scope<T, F: FnOnce() -> T>(&self, f: F) -> T {
+        // restores the previous one even if `f` panics
+        struct Restore(Option<SolverRegistry>);
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                let prev = self.0.take();
+                CURRENT.with(|c| *c.borrow_mut() = prev);
+            }
+        }
+        let prev = CURRENT.with(|c| c.borrow_mut().replace(self.clone()));
+        let _restore = Restore(prev);
+        f()
+    }
+}
+
+/// used outside of any scope
+static DEFAULT: Lazy<SolverRegistry> = Lazy::new(SolverRegistry::default);
+
+thread_local! {
+    static CURRENT: RefCell<Option<SolverRegistry>> = RefCell::new(None);
+}
+
+/// applies `f` to the registry of the current thread
+pub fn with<T, F: FnOnce(&SolverRegistry) -> T>(f: F) -> T {
+    CURRENT.with(|c| match &*c.borrow() {
+        Some(r) => f(r),
+        None => f(&DEFAULT),
+    })
+}
+
+/// returns the registry of the current thread
+pub fn current() -> SolverRegistry {
+    with(|r| r.clone())
+}
+
+#[test]
+fn test_registry_scope() {
+    let r = SolverRegistry::new().smt(SMTSolverType::Z3Lib);
+    assert!(matches!(with(|r| r.smt), SMTSolverType::Z3));
+    r.scope(|| {
+        assert!(matches!(with(|r| r.smt), SMTSolverType::Z3Lib));
+        // runs of another thread are not affected
+        let h = std::thread::spawn(|| with(|r| r.smt));
+        assert!(matches!(h.join().unwrap(), SMTSolverType::Z3));
+    });
+    assert!(matches!(with(|r| r.smt), SMTSolverType::Z3));
+}
@@ -1,5 +1,4 @@
 use std::collections::{HashMap, HashSet};
-use std::time::Duration;
 
 use super::smt::{ident_2_smt2, save_smt2};
 use super::util;
@@ -125,7 +124,7 @@ impl SATSolver {
             solver: Solver::Z3,
         }
     }
-    fn z3_solver(&mut self, smt_string: String) -> String {
+    fn z3_solver(&mut self, smt_string: String) -> Result<String, util::ExecError> {
         let f = save_smt2(smt_string);
         let args = vec![f.path().to_str().unwrap()];
         // debug
@@ -134,11 +133,12 @@ impl SATSolver {
         crate::stat::smt::smt_count();
         crate::stat::smt::start_clock();
 
-        let out = util::exec_with_timeout("z3", &args, Duration::from_secs(1));
+        let z3 = super::registry::with(|r| r.paths.z3.clone());
+        let out = util::exec_with_timeout(&z3, &args, super::timeouts().smt);
 
         crate::stat::smt::end_clock();
 
-        String::from_utf8(out).unwrap()
+        Ok(String::from_utf8(out?).unwrap())
     }
     /// Given a constraint, the solver executes an SMT solver to check whether
     /// it is satisfiable or not.
@@ -154,6 +154,10 @@ impl SATSolver {
         let s = match self.solver {
             Solver::Z3 => self.z3_solver(smt2),
         };
+        let s = match s {
+            Ok(s) => s,
+            Err(util::ExecError::Timeout) => return Err(SolverResult::Timeout),
+        };
         debug!("smt_solve result: {:?}", &s);
         if s.starts_with("sat") {
             let pos = s.find('\n').unwrap();
@@ -285,7 +289,7 @@ fn z3_sat_model() {
     (get-model)"
         .to_string();
     let mut sol = SATSolver::default_solver(-256, 256, bit_size);
-    let r = sol.z3_solver(s);
+    let r = sol.z3_solver(s).unwrap();
     debug!("{}", r);
     assert!(r.starts_with("sat"));
     let pos = r.find('\n').unwrap();
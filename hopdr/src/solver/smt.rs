@@ -1,7 +1,10 @@
 use tempfile::NamedTempFile;
 
 use std::collections::{HashMap, HashSet};
-use std::time::Duration;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
 
 use super::util;
 use super::{Model, SMTSolverType, SolverResult};
@@ -158,6 +161,23 @@ pub trait SMTSolver {
     }
 }
 
+/// Incremental interface to an SMT solver.
+///
+/// Assertions are kept in a stack of scopes, and `pop` discards the assertions made
+/// since the matching `push`. Free variables of the assertions are existentially quantified.
+pub trait SMTSession {
+    fn assert(&mut self, constraint: &Constraint);
+    fn push(&mut self);
+    fn pop(&mut self);
+    /// checks if the conjunction of the current assertions is satisfiable
+    fn check(&mut self) -> SolverResult;
+    /// checks if the conjunction of the current assertions is satisfiable, and returns
+    /// the model for `fvs`.
+    ///
+    /// invariant: dom(returned model) == fvs
+    fn check_with_model(&mut self, fvs: &HashSet<Ident>) -> Result<Model, SolverResult>;
+}
+
 fn pred_to_smt2(p: &PredKind, args: &[String]) -> String {
     let args = args.join(" ");
     match p {
@@ -281,40 +301,314 @@ impl AutoSolver {
 }
 
 pub fn smt_solver(s: SMTSolverType) -> Box<dyn SMTSolver> {
-    match s {
+    let solver: Box<dyn SMTSolver> = match s {
         SMTSolverType::Z3 => Box::new(Z3Solver {}),
+        SMTSolverType::Z3Lib => Box::new(super::z3lib::Z3LibSolver {}),
         SMTSolverType::Auto => Box::new(AutoSolver::new()),
         SMTSolverType::CVC | SMTSolverType::UltimateEliminator => panic!("not supported"),
-    }
+    };
+    super::cache::CachedSolver::new(s, solver)
 }
 
+/// returns the SMT solver of the current registry
 pub fn default_solver() -> Box<dyn SMTSolver> {
-    smt_solver(SMTSolverType::Z3)
+    smt_solver(super::registry::with(|r| r.smt))
+}
+
+pub fn smt_session(s: SMTSolverType) -> Box<dyn SMTSession> {
+    match s {
+        SMTSolverType::Z3 => Box::new(Z3Session::new()),
+        SMTSolverType::Z3Lib => Box::new(super::z3lib::Z3LibSession::new()),
+        SMTSolverType::Auto => Box::new(StackSession::new(smt_solver(s))),
+        SMTSolverType::CVC | SMTSolverType::UltimateEliminator => panic!("not supported"),
+    }
+}
+
+pub fn default_session() -> Box<dyn SMTSession> {
+    smt_session(super::registry::with(|r| r.smt))
+}
+
+/// Session over a solver without incremental interfaces, which solves the
+/// conjunction of the assertions from scratch on each check.
+struct StackSession {
+    solver: Box<dyn SMTSolver>,
+    scopes: Vec<Vec<Constraint>>,
+}
+
+impl StackSession {
+    fn new(solver: Box<dyn SMTSolver>) -> Self {
+        StackSession {
+            solver,
+            scopes: vec![Vec::new()],
+        }
+    }
+    fn constraint(&self) -> Constraint {
+        self.scopes
+            .iter()
+            .flatten()
+            .fold(Constraint::mk_true(), |c1, c2| {
+                Constraint::mk_conj(c1, c2.clone())
+            })
+    }
+}
+
+impl SMTSession for StackSession {
+    fn assert(&mut self, c: &Constraint) {
+        self.scopes.last_mut().unwrap().push(c.clone());
+    }
+    fn push(&mut self) {
+        self.scopes.push(Vec::new());
+    }
+    fn pop(&mut self) {
+        assert!(self.scopes.len() > 1, "pop without push");
+        self.scopes.pop();
+    }
+    fn check(&mut self) -> SolverResult {
+        let c = self.constraint();
+        self.solver.solve(&c, &HashSet::new())
+    }
+    fn check_with_model(&mut self, fvs: &HashSet<Ident>) -> Result<Model, SolverResult> {
+        let c = self.constraint();
+        self.solver.solve_with_model(&c, &HashSet::new(), fvs)
+    }
+}
+
+/// Session over a `z3 -in` process, which lives as long as the session.
+///
+/// `-t:` is only a soft limit of z3, so each answer is awaited for at most the time limit
+/// (plus `Z3_SESSION_GRACE`). When z3 does not answer in time, reports an error or exits,
+/// the process is killed and all the later checks of the session fail in the same way.
+struct Z3Session {
+    child: Child,
+    stdin: ChildStdin,
+    /// lines of the output of z3, which are read by another thread
+    stdout: Receiver<String>,
+    timeout: Option<Duration>,
+    /// variables declared in each scope
+    declared: Vec<HashSet<Ident>>,
+    /// the result of the checks after the process got unusable
+    failed: Option<SolverResult>,
+}
+
+/// how long z3 is awaited after its soft time limit before it is killed
+const Z3_SESSION_GRACE: Duration = Duration::from_secs(1);
+
+impl Z3Session {
+    fn new() -> Self {
+        let timeout = super::timeouts().smt;
+        let mut args = vec!["-in".to_string()];
+        if let Some(timeout) = timeout {
+            // soft timeout for each check-sat
+            args.push(format!("-t:{}", timeout.as_millis()));
+        }
+        let z3 = super::registry::with(|r| r.paths.z3.clone());
+        let mut child = Command::new(z3)
+            .args(&args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        let (tx, rx) = mpsc::channel();
+        // ends when the process exits (or is killed) or the session is dropped
+        std::thread::spawn(move || {
+            for line in stdout.lines() {
+                match line {
+                    Ok(line) if tx.send(line).is_ok() => (),
+                    _ => break,
+                }
+            }
+        });
+        Z3Session {
+            child,
+            stdin,
+            stdout: rx,
+            timeout,
+            declared: vec![HashSet::new()],
+            failed: None,
+        }
+    }
+    /// kills the process, after which all the checks return `r`
+    fn fail<T>(&mut self, r: SolverResult) -> Result<T, SolverResult> {
+        if self.failed.is_none() {
+            warn!("z3 session: {:?}; the process is killed", r);
+            // the process may have already exited, so errors are ignored
+            let _ = self.child.kill();
+            self.failed = Some(r);
+        }
+        Err(r)
+    }
+    fn send(&mut self, command: &str) {
+        if self.failed.is_some() {
+            return;
+        }
+        debug!("z3 session: {}", command);
+        if writeln!(self.stdin, "{}", command).is_err() {
+            let _ = self.fail::<()>(SolverResult::Unknown);
+        }
+    }
+    /// reads a line of the output until `deadline` (forever if `None`)
+    fn read_line(&mut self, deadline: Option<Instant>) -> Result<String, SolverResult> {
+        if let Some(r) = self.failed {
+            return Err(r);
+        }
+        let line = match deadline {
+            Some(deadline) => {
+                let rest = deadline.saturating_duration_since(Instant::now());
+                match self.stdout.recv_timeout(rest) {
+                    Ok(line) => line,
+                    Err(RecvTimeoutError::Timeout) => return self.fail(SolverResult::Timeout),
+                    Err(RecvTimeoutError::Disconnected) => return self.fail(SolverResult::Unknown),
+                }
+            }
+            None => match self.stdout.recv() {
+                Ok(line) => line,
+                Err(_) => return self.fail(SolverResult::Unknown),
+            },
+        };
+        // an error of an assertion is reported before the answer of the next command
+        if line.starts_with("(error") {
+            warn!("z3 session: {}", line);
+            return self.fail(SolverResult::Unknown);
+        }
+        Ok(line)
+    }
+    /// reads an S-expression that may span multiple lines
+    fn read_sexp(&mut self, deadline: Option<Instant>) -> Result<String, SolverResult> {
+        let mut s = String::new();
+        let mut depth = 0;
+        loop {
+            let line = self.read_line(deadline)?;
+            for c in line.chars() {
+                match c {
+                    '(' => depth += 1,
+                    ')' => depth -= 1,
+                    _ => (),
+                }
+            }
+            s += &line;
+            s.push('\n');
+            if depth == 0 && s.contains('(') {
+                return Ok(s);
+            }
+        }
+    }
+    fn deadline(&self) -> Option<Instant> {
+        self.timeout
+            .map(|timeout| Instant::now() + timeout + Z3_SESSION_GRACE)
+    }
+    fn is_declared(&self, x: &Ident) -> bool {
+        self.declared.iter().any(|d| d.contains(x))
+    }
+}
+
+impl SMTSession for Z3Session {
+    fn assert(&mut self, c: &Constraint) {
+        for fv in c.fv() {
+            if !self.is_declared(&fv) {
+                self.send(&format!("(declare-const {} Int)", encode_ident(&fv)));
+                self.declared.last_mut().unwrap().insert(fv);
+            }
+        }
+        let c = constraint_to_smt2_inner(c, SMTSolverType::Z3);
+        self.send(&format!("(assert {})", c));
+    }
+    fn push(&mut self) {
+        self.send("(push 1)");
+        self.declared.push(HashSet::new());
+    }
+    fn pop(&mut self) {
+        assert!(self.declared.len() > 1, "pop without push");
+        self.send("(pop 1)");
+        self.declared.pop();
+    }
+    fn check(&mut self) -> SolverResult {
+        if let Some(r) = self.failed {
+            return r;
+        }
+        crate::stat::smt::smt_count();
+        crate::stat::smt::start_clock();
+        let now = Instant::now();
+        let deadline = self.deadline();
+        self.send("(check-sat)");
+        let s = self.read_line(deadline);
+        crate::stat::smt::end_clock();
+        let s = match s {
+            Ok(s) => s,
+            Err(r) => return r,
+        };
+        debug!("z3 session result: {}", s.trim());
+        match s.trim() {
+            "sat" => SolverResult::Sat,
+            "unsat" => SolverResult::Unsat,
+            "timeout" => SolverResult::Timeout,
+            _ => match self.timeout {
+                Some(timeout) if now.elapsed() >= timeout => SolverResult::Timeout,
+                _ => SolverResult::Unknown,
+            },
+        }
+    }
+    fn check_with_model(&mut self, fvs: &HashSet<Ident>) -> Result<Model, SolverResult> {
+        match self.check() {
+            SolverResult::Sat => (),
+            r => return Err(r),
+        }
+        let deadline = self.deadline();
+        self.send("(get-model)");
+        let s = self.read_sexp(deadline)?;
+        let mut m = match Model::from_z3_model_str(&s) {
+            Ok(m) => m,
+            Err(e) => {
+                warn!("z3 session: failed to parse the model: {}", e);
+                return Err(SolverResult::Unknown);
+            }
+        };
+        m.model.retain(|x, _| fvs.contains(x));
+        // variables that are not declared can take any value
+        m.compensate(fvs);
+        Ok(m)
+    }
+}
+
+impl Drop for Z3Session {
+    fn drop(&mut self) {
+        // the process may have already exited, so errors are ignored
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
 }
 
 /// Invoke an SMT solver with the command `<cmd> filename` where the contents of
 /// the file is `smt_string`.
-fn invoke_smt_solver(cmd: &str, smt_string: String) -> String {
+pub(super) fn invoke_smt_solver(
+    cmd: &str,
+    smt_string: String,
+    timeout: Option<Duration>,
+) -> Result<String, util::ExecError> {
     let f = save_smt2(smt_string);
     let args = vec![f.path().to_str().unwrap()];
     // debug
     debug!("filename: {}", &args[0]);
 
-    let out = util::exec_with_timeout(cmd, &args, Duration::from_secs(1));
+    let out = util::exec_with_timeout(cmd, &args, timeout)?;
 
-    String::from_utf8(out).unwrap()
+    Ok(String::from_utf8(out).unwrap())
 }
 
-pub fn z3_solver(smt_string: String) -> String {
+pub fn z3_solver(smt_string: String) -> Result<String, util::ExecError> {
     crate::stat::smt::smt_count();
     crate::stat::smt::start_clock();
-    let s = invoke_smt_solver("z3", smt_string);
+    let z3 = super::registry::with(|r| r.paths.z3.clone());
+    let s = invoke_smt_solver(&z3, smt_string, super::timeouts().smt);
     crate::stat::smt::end_clock();
     s
 }
 
-pub fn ultimate_solver(smt_string: String) -> String {
-    invoke_smt_solver("ultimate_eliminator", smt_string)
+pub fn ultimate_solver(smt_string: String) -> Result<String, util::ExecError> {
+    let ue = super::registry::with(|r| r.paths.ultimate_eliminator.clone());
+    invoke_smt_solver(&ue, smt_string, super::timeouts().qe)
 }
 
 impl AutoSolver {
@@ -336,6 +630,19 @@ impl AutoSolver {
         farkas::farkas_transform(&constraint)
     }
 
+    /// Existential quantifiers are instantiated by linear templates before Farkas' lemma
+    /// (see `farkas::farkas_transform`), so the failure to find a model of a constraint
+    /// with them does not mean that it is unsatisfiable.
+    fn unsat_to_unknown(c: &Constraint, r: SolverResult) -> SolverResult {
+        let (qs, _) = c.to_pnf_raw();
+        match r {
+            SolverResult::Unsat if qs.iter().any(|(q, _)| q.is_existential()) => {
+                SolverResult::Unknown
+            }
+            r => r,
+        }
+    }
+
     /// check if the given model is actually a valid model for constraint
     /// by using SMT solver (Model is assumed to be given by SAT solver)
     fn validate(&self, constraint: &Constraint, model: &Model) -> bool {
@@ -343,7 +650,7 @@ impl AutoSolver {
         for (var, val) in model.model.iter() {
             constraint = constraint.subst(var, &Op::mk_const(*val));
         }
-        let mut sat_solver = smt_solver(SMTSolverType::Z3);
+        let mut sat_solver = default_solver();
         for fv in constraint.fv() {
             // there is no constraint on fv, so any number is ok to substitute.
             constraint = constraint.subst(&fv, &Op::mk_const(0));
@@ -370,7 +677,7 @@ impl AutoSolver {
 
     fn solve_by_smt(&self, constraint: &Constraint) -> Result<Model, SolverResult> {
         let c = Self::add_range_to_fv(constraint, -256, 256);
-        let mut sat_solver = smt_solver(SMTSolverType::Z3);
+        let mut sat_solver = default_solver();
         sat_solver.solve_with_model(&c, &HashSet::new(), &c.fv())
     }
 
@@ -397,7 +704,7 @@ impl SMTSolver for AutoSolver {
 
         match self.solve_inner(&constraint) {
             Ok(_) => SolverResult::Sat,
-            Err(r) => r,
+            Err(r) => Self::unsat_to_unknown(c, r),
         }
     }
     fn solve_with_model(
@@ -409,11 +716,12 @@ impl SMTSolver for AutoSolver {
         debug!("smt::auto_solver: {c}");
         debug!("fvs len: {}", fvs.len());
         if c.fv().difference(vars).next().is_none() {
-            let mut smt_solver = smt_solver(SMTSolverType::Z3);
+            let mut smt_solver = default_solver();
             smt_solver.solve_with_model(c, vars, fvs)
         } else {
             let constraint = self.farkas_transform(c, vars);
             self.solve_inner(&constraint)
+                .map_err(|r| Self::unsat_to_unknown(c, r))
         }
         .map(|mut m| {
             m.compensate(fvs);
@@ -504,6 +812,21 @@ fn test_auto_solver_bug2() {
     }
 }
 
+#[test]
+fn test_auto_solver_existential() {
+    // ∀x. ∃y. 2y = x ∨ 2y = x + 1 holds, but y = ⌊x/2⌋ is not a linear template of x
+    let x = Ident::fresh();
+    let y = Ident::fresh();
+    let two_y = Op::mk_mul(Op::mk_const(2), Op::mk_var(y));
+    let c = Constraint::mk_disj(
+        Constraint::mk_eq(two_y.clone(), Op::mk_var(x)),
+        Constraint::mk_eq(two_y, Op::mk_add(Op::mk_var(x), Op::mk_const(1))),
+    );
+    let c = Constraint::mk_univ_int(x, Constraint::mk_exists_int(y, c));
+    let mut solver = smt_solver(SMTSolverType::Auto);
+    assert!(!solver.solve(&c, &HashSet::new()).is_unsat());
+}
+
 impl SMTSolver for Z3Solver {
     fn solve(&mut self, c: &Constraint, vars: &HashSet<Ident>) -> SolverResult {
         debug!("smt_solve: {}", c);
@@ -511,7 +834,10 @@ impl SMTSolver for Z3Solver {
         let fvs = &fvs - vars;
         let smt2 = constraint_to_smt2(c, SMTSolverType::Z3, vars, Some(&fvs));
         debug!("smt2: {}", &smt2);
-        let s = z3_solver(smt2);
+        let s = match z3_solver(smt2) {
+            Ok(s) => s,
+            Err(util::ExecError::Timeout) => return SolverResult::Timeout,
+        };
         debug!("smt_solve result: {:?}", &s);
         if s.starts_with("sat") {
             SolverResult::Sat
@@ -532,7 +858,10 @@ impl SMTSolver for Z3Solver {
         debug!("smt_solve_with_model: {} fvs.len(): {}", c, fvs.len());
         let smt2 = constraint_to_smt2(c, SMTSolverType::Z3, vars, Some(fvs));
         debug!("smt2: {}", &smt2);
-        let s = z3_solver(smt2);
+        let s = match z3_solver(smt2) {
+            Ok(s) => s,
+            Err(util::ExecError::Timeout) => return Err(SolverResult::Timeout),
+        };
         debug!("smt_solve result: {:?}", &s);
         if s.starts_with("sat") {
             let pos = s.find('\n').unwrap();
@@ -556,7 +885,7 @@ fn z3_sat_model() {
     (check-sat)
     (get-model)"
         .to_string();
-    let r = z3_solver(s);
+    let r = z3_solver(s).unwrap();
     debug!("{}", r);
     assert!(r.starts_with("sat"));
     let pos = r.find('\n').unwrap();
@@ -606,3 +935,52 @@ pub fn check_dual_semantically(c1: &Constraint, c2: &Constraint) -> bool {
     let mut sol = default_solver();
     sol.solve_with_universal_quantifiers(&c).is_sat()
 }
+
+#[test]
+fn test_z3_session() {
+    let x = Ident::fresh();
+    let y = Ident::fresh();
+    for mut session in vec![
+        smt_session(SMTSolverType::Z3),
+        smt_session(SMTSolverType::Auto),
+    ] {
+        session.assert(&Constraint::mk_gt(Op::mk_var(x), Op::mk_const(1)));
+        session.push();
+        session.assert(&Constraint::mk_lt(Op::mk_var(x), Op::mk_const(0)));
+        assert!(session.check().is_unsat());
+        session.pop();
+        session.push();
+        session.assert(&Constraint::mk_eq(Op::mk_var(y), Op::mk_var(x)));
+        let fvs = vec![x, y].into_iter().collect();
+        let m = session.check_with_model(&fvs).unwrap();
+        assert!(m.get(&x).unwrap() > 1);
+        assert_eq!(m.get(&x), m.get(&y));
+        session.pop();
+    }
+}
+
+#[test]
+fn test_z3_session_timeout() {
+    use std::os::unix::fs::PermissionsExt;
+    // a z3 that never answers
+    let f = NamedTempFile::new().unwrap();
+    std::fs::write(f.path(), "#!/bin/sh\ncat > /dev/null\n").unwrap();
+    std::fs::set_permissions(f.path(), std::fs::Permissions::from_mode(0o755)).unwrap();
+    // the file must be closed before it is executed
+    let path = f.into_temp_path();
+    let mut paths = super::registry::SolverPaths::default();
+    paths.z3 = path.to_str().unwrap().to_string();
+    let timeouts = super::SolverTimeouts {
+        smt: Some(Duration::from_millis(100)),
+        ..Default::default()
+    };
+    let registry = super::SolverRegistry::new().paths(paths).timeouts(timeouts);
+    registry.scope(|| {
+        let mut session = smt_session(SMTSolverType::Z3);
+        session.assert(&Constraint::mk_false());
+        let now = Instant::now();
+        assert!(matches!(session.check(), SolverResult::Timeout));
+        assert!(now.elapsed() < Duration::from_secs(5));
+        assert!(matches!(session.check(), SolverResult::Timeout));
+    });
+}
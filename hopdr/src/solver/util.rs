@@ -1,13 +1,50 @@
-use hoice::common::Discard;
 use tempfile::NamedTempFile;
+use thiserror::Error;
+use wait_timeout::ChildExt;
 
-use std::io::Write;
-use std::process::Command;
+use std::io::{Read, Write};
+use std::os::unix::process::CommandExt;
+use std::process::{Child, Command, Stdio};
 use std::time::Duration;
 
 use tokio;
 use tokio::io::AsyncWriteExt;
 
+#[derive(Debug, Error)]
+pub enum ExecError {
+    #[error("timeout")]
+    Timeout,
+}
+
+/// Kills the process group `pgid`.
+///
+/// The solvers are spawned in their own process groups (`process_group(0)`), so that the
+/// processes spawned by wrapper scripts (e.g. java of ultimate_eliminator) are killed
+/// together with them.
+fn kill_process_group(pgid: u32) {
+    // the group may have already exited, so errors are ignored
+    unsafe {
+        libc::killpg(pgid as libc::pid_t, libc::SIGKILL);
+    }
+}
+
+/// Kills the process group when dropped unless it is disarmed.
+struct ProcessGroupGuard(Option<u32>);
+
+impl ProcessGroupGuard {
+    fn disarm(mut self) {
+        self.0 = None;
+    }
+}
+
+impl Drop for ProcessGroupGuard {
+    fn drop(&mut self) {
+        if let Some(pgid) = self.0 {
+            kill_process_group(pgid);
+        }
+    }
+}
+
 pub fn save_to_file(s: String) -> NamedTempFile {
     let mut tf = NamedTempFile::new().unwrap();
     // debug!("{}", &s);
@@ -15,63 +52,185 @@ pub fn save_to_file(s: String) -> NamedTempFile {
     tf
 }
 
-pub fn exec_with_timeout(cmd: &str, args: &[&str], _timeout: Duration) -> Vec<u8> {
-    // todo: timeout
-    let output = Command::new(cmd).args(args).output().unwrap();
-    output.stdout
+/// Feeds `input` to the stdin of `child` (if piped), and waits for `child` for at most
+/// `timeout` (forever if `None`), and returns its stdout.
+/// The process group of `child` is killed when it does not finish in time.
+fn wait_with_timeout(
+    mut child: Child,
+    input: &[u8],
+    timeout: Option<Duration>,
+) -> Result<Vec<u8>, ExecError> {
+    // stdout has to be consumed concurrently; otherwise, the child blocks
+    // once the pipe gets full and never finishes.
+    let mut stdout = child.stdout.take().unwrap();
+    let reader = std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        stdout.read_to_end(&mut buf).unwrap();
+        buf
+    });
+    // so is stdin, and a stuck write is bounded by the timeout below
+    if let Some(mut stdin) = child.stdin.take() {
+        let input = input.to_vec();
+        std::thread::spawn(move || {
+            // the child may exit without reading the whole input
+            let _ = stdin.write_all(&input);
+        });
+    }
+    let status = match timeout {
+        Some(timeout) => child.wait_timeout(timeout).unwrap(),
+        None => Some(child.wait().unwrap()),
+    };
+    match status {
+        Some(_) => Ok(reader.join().unwrap()),
+        None => {
+            kill_process_group(child.id());
+            // the child may have finished just now, so errors are ignored
+            let _ = child.kill();
+            let _ = child.wait();
+            Err(ExecError::Timeout)
+        }
+    }
+}
+
+pub fn exec_with_timeout(
+    cmd: &str,
+    args: &[&str],
+    timeout: Option<Duration>,
+) -> Result<Vec<u8>, ExecError> {
+    let child = Command::new(cmd)
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .process_group(0)
+        .spawn()
+        .unwrap();
+    wait_with_timeout(child, &[], timeout)
 }
 
 pub async fn exec_input_with_timeout_async(
     cmd: &str,
     args: &[&str],
     input: &[u8],
-    _timeout: Duration,
-) -> Vec<u8> {
+    timeout: Option<Duration>,
+) -> Result<Vec<u8>, ExecError> {
     let mut child = tokio::process::Command::new(cmd)
         .kill_on_drop(true)
         .args(args)
-        .args(args)
         .stdin(std::process::Stdio::piped())
         .stdout(std::process::Stdio::piped())
-        .stderr(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null())
+        .process_group(0)
         .spawn()
         .unwrap();
+    // the whole group is killed if the future is dropped (e.g. on timeout or when the
+    // task is aborted) before the child finishes
+    let guard = ProcessGroupGuard(child.id());
 
     let mut stdin = child.stdin.take().unwrap();
-    stdin.write_all(input).await.unwrap();
-    stdin.discard();
-    child.wait_with_output().await.unwrap().stdout
+    let write = async move {
+        // the child may exit without reading the whole input
+        let _ = stdin.write_all(input).await;
+        // stdin is closed here
+    };
+    // stdout is read while the input is written; otherwise, the child blocks once the
+    // pipe gets full
+    let output = async {
+        let (_, output) = tokio::join!(write, child.wait_with_output());
+        output
+    };
+    let output = match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, output)
+            .await
+            .map_err(|_| ExecError::Timeout)?,
+        None => output.await,
+    };
+    guard.disarm();
+    Ok(output.unwrap().stdout)
 }
 
 pub fn exec_input_with_timeout(
     cmd: &str,
     args: &[&str],
     input: &[u8],
-    _timeout: Duration,
-) -> Vec<u8> {
-    // todo: timeout
-    let mut proc = Command::new(cmd)
+    timeout: Option<Duration>,
+) -> Result<Vec<u8>, ExecError> {
+    let child = Command::new(cmd)
         .args(args)
-        .stdin(std::process::Stdio::piped())
-        .stdout(std::process::Stdio::piped())
-        .stderr(std::process::Stdio::piped())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .process_group(0)
         .spawn()
         .unwrap();
 
-    proc.stdin.as_mut().unwrap().write_all(&input).unwrap();
-    let output = proc.wait_with_output().unwrap();
-    output.stdout
+    wait_with_timeout(child, input, timeout)
 }
 
 #[tokio::test]
 async fn test_exec_input_with_timeout_async() {
     let output =
-        exec_input_with_timeout_async("cat", &[], b"hello\n", Duration::from_secs(1)).await;
+        exec_input_with_timeout_async("cat", &[], b"hello\n", Some(Duration::from_secs(1)))
+            .await
+            .unwrap();
     assert_eq!(output, b"hello\n".to_vec());
 }
 
+#[tokio::test]
+async fn test_exec_input_with_timeout_async_kill() {
+    let r = exec_input_with_timeout_async("sleep", &["10"], b"", Some(Duration::from_millis(100)))
+        .await;
+    assert!(matches!(r, Err(ExecError::Timeout)));
+}
+
 #[test]
 fn test_exec_input_with_timeout() {
-    let output = exec_input_with_timeout("cat", &[], b"hello\n", Duration::from_secs(1));
+    let output =
+        exec_input_with_timeout("cat", &[], b"hello\n", Some(Duration::from_secs(1))).unwrap();
     assert_eq!(output, b"hello\n".to_vec());
 }
+
+#[test]
+fn test_exec_input_with_timeout_large() {
+    // more than the pipe buffers in both directions
+    let input = vec![b'a'; 1 << 20];
+    let output = exec_input_with_timeout("cat", &[], &input, Some(Duration::from_secs(5))).unwrap();
+    assert_eq!(output, input);
+}
+
+#[tokio::test]
+async fn test_exec_input_with_timeout_async_large() {
+    let input = vec![b'a'; 1 << 20];
+    let output = exec_input_with_timeout_async("cat", &[], &input, Some(Duration::from_secs(5)))
+        .await
+        .unwrap();
+    assert_eq!(output, input);
+}
+
+#[test]
+fn test_exec_with_timeout_kill() {
+    let now = std::time::Instant::now();
+    let r = exec_with_timeout("sleep", &["10"], Some(Duration::from_millis(100)));
+    assert!(matches!(r, Err(ExecError::Timeout)));
+    assert!(now.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn test_exec_with_timeout_kill_group() {
+    // sh spawns sleep, which is a grandchild of this process, and writes its pid to `f`
+    let f = NamedTempFile::new().unwrap();
+    let script = format!("sleep 10 & echo $! > {}; wait", f.path().display());
+    let r = exec_with_timeout("sh", &["-c", &script], Some(Duration::from_millis(500)));
+    assert!(matches!(r, Err(ExecError::Timeout)));
+    let pid = std::fs::read_to_string(f.path()).unwrap();
+    let stat = format!("/proc/{}/stat", pid.trim());
+    // killed processes may be left as zombies until they are reaped
+    let alive = || match std::fs::read_to_string(&stat) {
+        Ok(s) => !s.contains(") Z "),
+        Err(_) => false,
+    };
+    let now = std::time::Instant::now();
+    while alive() && now.elapsed() < Duration::from_secs(5) {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert!(!alive());
+}
@@ -0,0 +1,281 @@
+//! SMT solver that calls Z3 through its C API instead of spawning the `z3` binary.
+//!
+//! Constraints are translated to Z3 ASTs directly, so there is no SMT-LIB printing and
+//! parsing of models. Each thread holds one Z3 context, which is shared by all the
+//! queries of the thread, and is dropped when the thread and its sessions have gone.
+use super::smt::{encode_ident, SMTSession, SMTSolver};
+use super::{Model, SolverResult};
+use crate::formula::{
+    Constraint, ConstraintExpr, FirstOrderLogic, Ident, Op, OpExpr, OpKind, PredKind,
+    QuantifierKind,
+};
+
+use z3::ast::{self, Ast};
+use z3::{Context, SatResult};
+
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+thread_local! {
+    // sessions hold their own references so that they can outlive the borrow of the
+    // thread local storage
+    static CONTEXT: Rc<Context> = Rc::new(Context::new(&z3::Config::new()));
+}
+
+fn context() -> Rc<Context> {
+    CONTEXT.with(Rc::clone)
+}
+
+fn int_var<'ctx>(ctx: &'ctx Context, x: &Ident) -> ast::Int<'ctx> {
+    ast::Int::new_const(ctx, encode_ident(x))
+}
+
+fn op_to_z3<'ctx>(ctx: &'ctx Context, o: &Op) -> ast::Int<'ctx> {
+    match o.kind() {
+        OpExpr::Op(k, o1, o2) => {
+            let o1 = op_to_z3(ctx, o1);
+            let o2 = op_to_z3(ctx, o2);
+            match k {
+                OpKind::Add => ast::Int::add(ctx, &[&o1, &o2]),
+                OpKind::Sub => ast::Int::sub(ctx, &[&o1, &o2]),
+                OpKind::Mul => ast::Int::mul(ctx, &[&o1, &o2]),
+                OpKind::Div => o1.div(&o2),
+                OpKind::Mod => o1.modulo(&o2),
+            }
+        }
+        OpExpr::Var(x) => int_var(ctx, x),
+        OpExpr::Const(c) => ast::Int::from_i64(ctx, *c),
+        OpExpr::ITE(c, o1, o2) => {
+            let c = constraint_to_z3(ctx, c);
+            c.ite(&op_to_z3(ctx, o1), &op_to_z3(ctx, o2))
+        }
+        OpExpr::Ptr(_, o) => op_to_z3(ctx, o),
+    }
+}
+
+fn pred_to_z3<'ctx>(p: &PredKind, x: &ast::Int<'ctx>, y: &ast::Int<'ctx>) -> ast::Bool<'ctx> {
+    match p {
+        PredKind::Eq => x._eq(y),
+        PredKind::Neq => x._eq(y).not(),
+        PredKind::Lt => x.lt(y),
+        PredKind::Leq => x.le(y),
+        PredKind::Gt => x.gt(y),
+        PredKind::Geq => x.ge(y),
+    }
+}
+
+fn constraint_to_z3<'ctx>(ctx: &'ctx Context, c: &Constraint) -> ast::Bool<'ctx> {
+    match c.kind() {
+        ConstraintExpr::True => ast::Bool::from_bool(ctx, true),
+        ConstraintExpr::False => ast::Bool::from_bool(ctx, false),
+        ConstraintExpr::Pred(p, l) => {
+            assert_eq!(l.len(), 2);
+            let x = op_to_z3(ctx, &l[0]);
+            let y = op_to_z3(ctx, &l[1]);
+            pred_to_z3(p, &x, &y)
+        }
+        ConstraintExpr::Conj(c1, c2) => {
+            let c1 = constraint_to_z3(ctx, c1);
+            let c2 = constraint_to_z3(ctx, c2);
+            ast::Bool::and(ctx, &[&c1, &c2])
+        }
+        ConstraintExpr::Disj(c1, c2) => {
+            let c1 = constraint_to_z3(ctx, c1);
+            let c2 = constraint_to_z3(ctx, c2);
+            ast::Bool::or(ctx, &[&c1, &c2])
+        }
+        ConstraintExpr::Quantifier(q, x, c) => {
+            let x = int_var(ctx, &x.id);
+            let c = constraint_to_z3(ctx, c);
+            match q {
+                QuantifierKind::Universal => ast::forall_const(ctx, &[&x as &dyn Ast], &[], &c),
+                QuantifierKind::Existential => ast::exists_const(ctx, &[&x as &dyn Ast], &[], &c),
+            }
+        }
+    }
+}
+
+/// Returns `Err(x)` if the value of `x` is not available, e.g., when it does not fit in i64.
+fn eval_model<'ctx>(
+    ctx: &'ctx Context,
+    model: &z3::Model<'ctx>,
+    fvs: &HashSet<Ident>,
+) -> Result<Model, Ident> {
+    let mut m = HashMap::new();
+    for x in fvs.iter() {
+        // with model completion, variables that do not appear in the assertions get some value
+        let v = model
+            .eval(&int_var(ctx, x), true)
+            .and_then(|v| v.as_i64())
+            .ok_or(*x)?;
+        m.insert(*x, v);
+    }
+    Ok(Model { model: m })
+}
+
+pub(super) struct Z3LibSession {
+    // the fields are dropped in order, so `solver` is dropped before the context
+    solver: z3::Solver<'static>,
+    timeout: Option<Duration>,
+    ctx: &'static Context,
+    _owner: Rc<Context>,
+}
+
+impl Z3LibSession {
+    pub(super) fn new() -> Self {
+        let owner = context();
+        // SAFETY: the context is on the heap, and `_owner` keeps it alive as long as the
+        // session, which is the only holder of `ctx` and the ASTs made from it
+        let ctx: &'static Context = unsafe { &*Rc::as_ptr(&owner) };
+        let solver = z3::Solver::new(ctx);
+        let timeout = super::timeouts().smt;
+        if let Some(timeout) = timeout {
+            let mut params = z3::Params::new(ctx);
+            params.set_u32("timeout", timeout.as_millis() as u32);
+            solver.set_params(&params);
+        }
+        Z3LibSession {
+            solver,
+            timeout,
+            ctx,
+            _owner: owner,
+        }
+    }
+}
+
+impl SMTSession for Z3LibSession {
+    fn assert(&mut self, c: &Constraint) {
+        self.solver.assert(&constraint_to_z3(self.ctx, c));
+    }
+    fn push(&mut self) {
+        self.solver.push();
+    }
+    fn pop(&mut self) {
+        self.solver.pop(1);
+    }
+    fn check(&mut self) -> SolverResult {
+        crate::stat::smt::smt_count();
+        crate::stat::smt::start_clock();
+        let now = Instant::now();
+        let r = match self.solver.check() {
+            SatResult::Sat => SolverResult::Sat,
+            SatResult::Unsat => SolverResult::Unsat,
+            SatResult::Unknown => match self.timeout {
+                Some(timeout) if now.elapsed() >= timeout => SolverResult::Timeout,
+                _ => SolverResult::Unknown,
+            },
+        };
+        crate::stat::smt::end_clock();
+        r
+    }
+    fn check_with_model(&mut self, fvs: &HashSet<Ident>) -> Result<Model, SolverResult> {
+        match self.check() {
+            SolverResult::Sat => {
+                let model = self.solver.get_model().unwrap();
+                eval_model(self.ctx, &model, fvs).map_err(|x| {
+                    warn!("z3: failed to get the value of {} as i64", x);
+                    SolverResult::Unknown
+                })
+            }
+            r => Err(r),
+        }
+    }
+}
+
+pub(super) struct Z3LibSolver {}
+
+fn quantify(c: &Constraint, vars: &HashSet<Ident>) -> Constraint {
+    vars.iter()
+        .fold(c.clone(), |c, x| Constraint::mk_univ_int(*x, c))
+}
+
+impl SMTSolver for Z3LibSolver {
+    fn solve(&mut self, c: &Constraint, vars: &HashSet<Ident>) -> SolverResult {
+        debug!("smt_solve(z3lib): {}", c);
+        let mut session = Z3LibSession::new();
+        session.assert(&quantify(c, vars));
+        session.check()
+    }
+    fn solve_with_model(
+        &mut self,
+        c: &Constraint,
+        vars: &HashSet<Ident>,
+        fvs: &HashSet<Ident>,
+    ) -> Result<Model, SolverResult> {
+        debug!(
+            "smt_solve_with_model(z3lib): {} fvs.len(): {}",
+            c,
+            fvs.len()
+        );
+        let mut session = Z3LibSession::new();
+        session.assert(&quantify(c, vars));
+        session.check_with_model(fvs)
+    }
+}
+
+#[test]
+fn test_z3lib_solve() {
+    use crate::formula::{Fv, Logic};
+    let x = Ident::fresh();
+    let y = Ident::fresh();
+    let mut solver = Z3LibSolver {};
+
+    // x > 1 ∧ y = x * 2 - 1
+    let c1 = Constraint::mk_gt(Op::mk_var(x), Op::mk_const(1));
+    let c2 = Constraint::mk_eq(
+        Op::mk_var(y),
+        Op::mk_sub(Op::mk_mul(Op::mk_var(x), Op::mk_const(2)), Op::mk_const(1)),
+    );
+    let c = Constraint::mk_conj(c1, c2);
+    let m = solver
+        .solve_with_model(&c, &HashSet::new(), &c.fv())
+        .unwrap();
+    let vx = m.get(&x).unwrap();
+    let vy = m.get(&y).unwrap();
+    assert!(vx > 1);
+    assert_eq!(vy, vx * 2 - 1);
+
+    // ∀x. x > 1 is unsat
+    let c = Constraint::mk_gt(Op::mk_var(x), Op::mk_const(1));
+    let vars = vec![x].into_iter().collect();
+    assert!(solver.solve(&c, &vars).is_unsat());
+
+    // ∃y. y > x is valid
+    let c = Constraint::mk_exists_int(y, Constraint::mk_gt(Op::mk_var(y), Op::mk_var(x)));
+    assert!(solver.solve_with_universal_quantifiers(&c).is_sat());
+}
+
+#[test]
+fn test_z3lib_session() {
+    use crate::formula::Logic;
+    let x = Ident::fresh();
+    let mut session = Z3LibSession::new();
+    session.assert(&Constraint::mk_gt(Op::mk_var(x), Op::mk_const(1)));
+    session.push();
+    session.assert(&Constraint::mk_lt(Op::mk_var(x), Op::mk_const(0)));
+    assert!(session.check().is_unsat());
+    session.pop();
+    let fvs = vec![x].into_iter().collect();
+    let m = session.check_with_model(&fvs).unwrap();
+    assert!(m.get(&x).unwrap() > 1);
+
+    // the value of x does not fit in i64
+    let mut session = Z3LibSession::new();
+    session.assert(&Constraint::mk_gt(Op::mk_var(x), Op::mk_const(i64::MAX)));
+    assert!(session.check().is_sat());
+    assert!(session.check_with_model(&fvs).unwrap_err().is_unknown());
+}
+
+#[test]
+fn test_z3lib_context_owner() {
+    // only the thread holds the context of a fresh thread after its sessions have gone
+    std::thread::spawn(|| {
+        let session = Z3LibSession::new();
+        CONTEXT.with(|ctx| assert_eq!(Rc::strong_count(ctx), 2));
+        drop(session);
+        CONTEXT.with(|ctx| assert_eq!(Rc::strong_count(ctx), 1));
+    })
+    .join()
+    .unwrap();
+}
@@ -0,0 +1,89 @@
+/// Kinds of queries that go through the solver cache
+#[derive(Clone, Copy)]
+pub enum Query {
+    SMT,
+    QE,
+    Interpolation,
+}
+
+#[derive(Default)]
+struct Counter {
+    hits: usize,
+    misses: usize,
+}
+
+impl Counter {
+    fn merge(&mut self, other: &Counter) {
+        self.hits += other.hits;
+        self.misses += other.misses;
+    }
+}
+
+impl std::fmt::Display for Counter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} hits / {} misses", self.hits, self.misses)
+    }
+}
+
+pub struct CacheStatistics {
+    smt: Counter,
+    qe: Counter,
+    interpolation: Counter,
+}
+
+impl std::fmt::Display for CacheStatistics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "SMT: {}", self.smt)?;
+        writeln!(f, "QE: {}", self.qe)?;
+        writeln!(f, "interpolation: {}", self.interpolation)?;
+        Ok(())
+    }
+}
+
+impl CacheStatistics {
+    pub fn new() -> CacheStatistics {
+        CacheStatistics {
+            smt: Counter::default(),
+            qe: Counter::default(),
+            interpolation: Counter::default(),
+        }
+    }
+    pub(super) fn merge(&mut self, other: &CacheStatistics) {
+        self.smt.merge(&other.smt);
+        self.qe.merge(&other.qe);
+        self.interpolation.merge(&other.interpolation);
+    }
+}
+
+#[cfg(feature = "stat")]
+impl CacheStatistics {
+    fn counter(&mut self, q: Query) -> &mut Counter {
+        match q {
+            Query::SMT => &mut self.smt,
+            Query::QE => &mut self.qe,
+            Query::Interpolation => &mut self.interpolation,
+        }
+    }
+}
+
+impl Default for CacheStatistics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[allow(unused_variables)]
+pub fn hit(q: Query) {
+    #[cfg(feature = "stat")]
+    {
+        super::with(|s| s.cache.counter(q).hits += 1)
+    }
+}
+
+#[allow(unused_variables)]
+pub fn miss(q: Query) {
+    #[cfg(feature = "stat")]
+    {
+        super::with(|s| s.cache.counter(q).misses += 1)
+    }
+}
@@ -19,6 +19,13 @@ impl CHCStatistics {
             count: 0,
         }
     }
+    pub fn duration(&self) -> Duration {
+        self.total_time
+    }
+    pub(super) fn merge(&mut self, other: &CHCStatistics) {
+        self.count += other.count;
+        self.total_time += other.total_time;
+    }
 }
 
 impl Default for CHCStatistics {
@@ -30,7 +37,7 @@ impl Default for CHCStatistics {
 pub fn count() {
     #[cfg(feature = "stat")]
     {
-        super::STAT.lock().unwrap().chc.count += 1;
+        super::with(|s| s.chc.count += 1);
     }
 }
 
@@ -38,6 +45,6 @@ pub fn count() {
 pub fn total_time(total_time: Duration) {
     #[cfg(feature = "stat")]
     {
-        super::STAT.lock().unwrap().chc.total_time += total_time
+        super::with(|s| s.chc.total_time += total_time)
     }
 }
@@ -72,6 +72,17 @@ impl CheckStatistics {
         }
         total
     }
+    pub(super) fn merge(&mut self, other: &CheckStatistics) {
+        for (name, state) in other.sub_clocks.iter() {
+            let st = self.sub_clocks.entry(*name).or_insert(State {
+                in_progress: None,
+                duration: Duration::ZERO,
+                count: 0,
+            });
+            st.duration += state.duration;
+            st.count += state.count;
+        }
+    }
 }
 
 impl Default for CheckStatistics {
@@ -85,8 +96,10 @@ pub fn start_clock(name: &'static str) {
     #[cfg(feature = "stat")]
     {
         let now = Instant::now();
-        let s = &mut super::STAT.lock().unwrap().check;
-        s.sub_clocks.entry(name).or_insert(State::new()).in_progress = Some(now);
+        super::with(|s| {
+            let s = &mut s.check;
+            s.sub_clocks.entry(name).or_insert(State::new()).in_progress = Some(now);
+        })
     }
 }
 
@@ -94,9 +107,10 @@ pub fn start_clock(name: &'static str) {
 pub fn end_clock(name: &'static str) {
     #[cfg(feature = "stat")]
     {
-        let stat = &mut super::STAT.lock().unwrap().check;
-        let st = stat.sub_clocks.get_mut(name).expect("program error");
-        st.end_clock();
+        super::with(|s| {
+            let st = s.check.sub_clocks.get_mut(name).expect("program error");
+            st.end_clock();
+        })
     }
 }
 
@@ -110,11 +124,12 @@ pub fn stat<T, F: FnOnce() -> T>(name: &'static str, f: F) -> T {
 pub fn finalize() {
     #[cfg(feature = "stat")]
     {
-        let stat = &mut super::STAT.lock().unwrap().check;
-        stat.sub_clocks.iter_mut().for_each(|(_, state)| {
-            if state.is_in_progress() {
-                state.end_clock()
-            }
+        super::with(|s| {
+            s.check.sub_clocks.iter_mut().for_each(|(_, state)| {
+                if state.is_in_progress() {
+                    state.end_clock()
+                }
+            })
         })
     }
 }
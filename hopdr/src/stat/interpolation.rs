@@ -23,6 +23,10 @@ impl InterpolationStatistics {
             clock_starts_at: None,
         }
     }
+    pub(super) fn merge(&mut self, other: &InterpolationStatistics) {
+        self.count += other.count;
+        self.total_time += other.total_time;
+    }
 }
 
 impl Default for InterpolationStatistics {
@@ -34,7 +38,7 @@ impl Default for InterpolationStatistics {
 pub fn count() {
     #[cfg(feature = "stat")]
     {
-        super::STAT.lock().unwrap().interpolation.count += 1;
+        super::with(|s| s.interpolation.count += 1);
     }
 }
 
@@ -43,25 +47,26 @@ pub fn start_clock() {
     {
         let now = Instant::now();
 
-        super::STAT.lock().unwrap().interpolation.clock_starts_at = Some(now)
+        super::with(|s| s.interpolation.clock_starts_at = Some(now))
     }
 }
 
 pub fn end_clock() {
     #[cfg(feature = "stat")]
     {
-        let mut stat = super::STAT.lock().unwrap();
-        let st = stat.interpolation.clock_starts_at.expect("program error");
-        let dur = st.elapsed();
-        stat.interpolation.total_time += dur;
-        stat.interpolation.clock_starts_at = None;
+        super::with(|stat| {
+            let st = stat.interpolation.clock_starts_at.expect("program error");
+            let dur = st.elapsed();
+            stat.interpolation.total_time += dur;
+            stat.interpolation.clock_starts_at = None;
+        })
     }
 }
 
 pub fn finalize() {
     #[cfg(feature = "stat")]
     {
-        let r = { super::STAT.lock().unwrap().interpolation.clock_starts_at };
+        let r = super::with(|s| s.interpolation.clock_starts_at);
         if r.is_some() {
             end_clock()
         }
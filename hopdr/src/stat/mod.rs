@@ -1,9 +1,13 @@
-//! Singleton Object Statistics is initialized when the program is loaded.
+//! Statistics on the usage of the background solvers and the phases of the procedures.
 //! By utilizing the functions for updating the statistics, users can register their data.
 //!
-//! Stat module does not assume that it runs concurrently.
-//! The stat result can be broken if it is executed concurrently.
+//! The functions update the statistics of the *current* run, which is a `Stat` handle set
+//! by `Stat::scope` (or `Stat::scope_async` for futures) on the running thread. Outside
+//! any scope, a process-wide default is updated, which the binaries use. Since each run
+//! has its own handle, runs in parallel (e.g. tasks of a `JoinSet`, or solvers called
+//! from a multi-threaded host) do not break each other's statistics.
 
+pub mod cache;
 pub mod chc;
 pub mod check;
 pub mod interpolation;
@@ -13,8 +17,15 @@ pub mod qe;
 pub mod smt;
 
 use once_cell::sync::Lazy;
-use std::sync::Mutex;
+use serde::Serialize;
 
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use cache::CacheStatistics;
 use chc::CHCStatistics;
 use check::CheckStatistics;
 use interpolation::InterpolationStatistics;
@@ -31,6 +42,7 @@ struct Statistics {
     preprocess: PreprocessStatistics,
     qe: QEStatistics,
     check: CheckStatistics,
+    cache: CacheStatistics,
 }
 
 impl Statistics {
@@ -43,8 +55,20 @@ impl Statistics {
             preprocess: PreprocessStatistics::new(),
             qe: QEStatistics::new(),
             check: CheckStatistics::new(),
+            cache: CacheStatistics::new(),
         }
     }
+
+    /// adds the counts and durations of `other` (except the overall time)
+    fn merge(&mut self, other: &Statistics) {
+        self.smt.merge(&other.smt);
+        self.interpolation.merge(&other.interpolation);
+        self.chc.merge(&other.chc);
+        self.preprocess.merge(&other.preprocess);
+        self.qe.merge(&other.qe);
+        self.check.merge(&other.check);
+        self.cache.merge(&other.cache);
+    }
 }
 
 impl std::fmt::Display for Statistics {
@@ -61,16 +85,147 @@ impl std::fmt::Display for Statistics {
         writeln!(f, "{}", self.qe)?;
         writeln!(f, "[Check]")?;
         writeln!(f, "{}", self.check)?;
+        writeln!(f, "[Cache]")?;
+        writeln!(f, "{}", self.cache)?;
         writeln!(f, "[Overall]")?;
         writeln!(f, "{}", self.overall)?;
         Ok(())
     }
 }
 
-static STAT: Lazy<Mutex<Statistics>> = Lazy::new(|| Mutex::new(Statistics::new()));
+/// Handle to the statistics of a run. Clones share the same statistics.
+#[derive(Clone)]
+pub struct Stat(Arc<Mutex<Statistics>>);
+
+impl Default for Stat {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Stat {
+    pub fn new() -> Stat {
+        Stat(Arc::new(Mutex::new(Statistics::new())))
+    }
+
+    /// runs `f` with `self` as the current statistics of this thread
+    pub fn scope<T, F: FnOnce() -> T>(&self, f: F) -> T {
+        // restores the previous one even if `f` panics
+        struct Restore(Option<Stat>);
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                let prev = self.0.take();
+                CURRENT.with(|c| *c.borrow_mut() = prev);
+            }
+        }
+        let prev = CURRENT.with(|c| c.borrow_mut().replace(self.clone()));
+        let _restore = Restore(prev);
+        f()
+    }
+
+    /// returns the future that polls `f` with `self` as the current statistics, so that
+    /// it is kept even if the task moves between threads
+    pub fn scope_async<F: Future>(&self, f: F) -> Scoped<F> {
+        Scoped {
+            stat: self.clone(),
+            inner: Box::pin(f),
+        }
+    }
+
+    /// adds the counts and durations of `other` to `self`
+    pub fn merge(&self, other: &Stat) {
+        if Arc::ptr_eq(&self.0, &other.0) {
+            return;
+        }
+        let other = other.0.lock().unwrap();
+        self.0.lock().unwrap().merge(&other);
+    }
+
+    /// stops the clocks still running
+    pub fn finalize(&self) {
+        self.scope(finalize)
+    }
+
+    pub fn dump(&self) {
+        println!("{}", self.report());
+    }
+
+    /// returns the statistics in the same format as `dump`
+    pub fn report(&self) -> String {
+        self.0.lock().unwrap().to_string()
+    }
+
+    /// returns the elapsed time of each phase recorded so far (all zero without feature `stat`)
+    pub fn phase_times(&self) -> PhaseTimes {
+        let stat = self.0.lock().unwrap();
+        PhaseTimes {
+            preprocess: stat.preprocess.total_duration().as_secs_f64(),
+            smt: stat.smt.duration().as_secs_f64(),
+            chc: stat.chc.duration().as_secs_f64(),
+            qe: stat.qe.duration().as_secs_f64(),
+        }
+    }
+}
+
+/// Future returned by `Stat::scope_async`
+pub struct Scoped<F> {
+    stat: Stat,
+    inner: Pin<Box<F>>,
+}
+
+impl<F: Future> Future for Scoped<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        let this = &mut *self;
+        let inner = &mut this.inner;
+        this.stat.scope(|| inner.as_mut().poll(cx))
+    }
+}
+
+/// used outside of any scope
+static DEFAULT: Lazy<Stat> = Lazy::new(Stat::new);
+
+thread_local! {
+    static CURRENT: RefCell<Option<Stat>> = RefCell::new(None);
+}
+
+/// returns the statistics of the current run
+pub fn current() -> Stat {
+    CURRENT
+        .with(|c| c.borrow().clone())
+        .unwrap_or_else(|| DEFAULT.clone())
+}
+
+/// applies `f` to the statistics of the current run
+#[allow(dead_code)]
+fn with<T, F: FnOnce(&mut Statistics) -> T>(f: F) -> T {
+    let stat = current();
+    let mut s = stat.0.lock().unwrap();
+    f(&mut s)
+}
 
 pub fn dump() {
-    println!("{}", STAT.lock().unwrap());
+    current().dump()
+}
+
+/// returns the statistics of the current run in the same format as `dump`
+pub fn report() -> String {
+    current().report()
+}
+
+/// Elapsed time (sec) of each phase
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct PhaseTimes {
+    pub preprocess: f64,
+    pub smt: f64,
+    pub chc: f64,
+    pub qe: f64,
+}
+
+/// returns the elapsed time of each phase of the current run
+pub fn phase_times() -> PhaseTimes {
+    current().phase_times()
 }
 
 pub fn finalize() {
@@ -80,3 +235,23 @@ pub fn finalize() {
     self::qe::finalize();
     self::check::finalize();
 }
+
+#[test]
+fn test_stat_scope() {
+    let s = Stat::new();
+    let t = Stat::new();
+    s.scope(|| {
+        assert!(Arc::ptr_eq(&current().0, &s.0));
+        t.scope(|| assert!(Arc::ptr_eq(&current().0, &t.0)));
+        assert!(Arc::ptr_eq(&current().0, &s.0));
+    });
+    assert!(Arc::ptr_eq(&current().0, &DEFAULT.0));
+
+    // a spawned task keeps its statistics whichever thread polls it
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let u = rt.block_on(async {
+        let task = tokio::spawn(t.scope_async(async { current() }));
+        task.await.unwrap()
+    });
+    assert!(Arc::ptr_eq(&u.0, &t.0));
+}
@@ -37,10 +37,12 @@ impl Default for OverallStatistics {
 pub fn finalize() {
     #[cfg(feature = "stat")]
     {
-        let duration = match super::STAT.lock().unwrap().overall.total_time {
-            Either::Left(now) => now.elapsed(),
-            Either::Right(dur) => dur,
-        };
-        super::STAT.lock().unwrap().overall.total_time = Either::Right(duration);
+        super::with(|s| {
+            let duration = match s.overall.total_time {
+                Either::Left(now) => now.elapsed(),
+                Either::Right(dur) => dur,
+            };
+            s.overall.total_time = Either::Right(duration);
+        })
     }
 }
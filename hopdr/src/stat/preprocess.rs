@@ -71,6 +71,17 @@ impl PreprocessStatistics {
         }
         total
     }
+    pub(super) fn merge(&mut self, other: &PreprocessStatistics) {
+        for (name, state) in other.sub_clocks.iter() {
+            let st = self.sub_clocks.entry(*name).or_insert(State {
+                in_progress: None,
+                duration: Duration::ZERO,
+                count: 0,
+            });
+            st.duration += state.duration;
+            st.count += state.count;
+        }
+    }
 }
 
 impl Default for PreprocessStatistics {
@@ -84,8 +95,10 @@ pub fn start_clock(name: &'static str) {
     #[cfg(feature = "stat")]
     {
         let now = Instant::now();
-        let s = &mut super::STAT.lock().unwrap().preprocess;
-        s.sub_clocks.entry(name).or_insert(State::new()).in_progress = Some(now);
+        super::with(|s| {
+            let s = &mut s.preprocess;
+            s.sub_clocks.entry(name).or_insert(State::new()).in_progress = Some(now);
+        })
     }
 }
 
@@ -93,20 +106,26 @@ pub fn start_clock(name: &'static str) {
 pub fn end_clock(name: &'static str) {
     #[cfg(feature = "stat")]
     {
-        let stat = &mut super::STAT.lock().unwrap().preprocess;
-        let st = stat.sub_clocks.get_mut(name).expect("program error");
-        st.end_clock();
+        super::with(|s| {
+            let st = s
+                .preprocess
+                .sub_clocks
+                .get_mut(name)
+                .expect("program error");
+            st.end_clock();
+        })
     }
 }
 
 pub fn finalize() {
     #[cfg(feature = "stat")]
     {
-        let stat = &mut super::STAT.lock().unwrap().preprocess;
-        stat.sub_clocks.iter_mut().for_each(|(_, state)| {
-            if state.is_in_progress() {
-                state.end_clock()
-            }
+        super::with(|s| {
+            s.preprocess.sub_clocks.iter_mut().for_each(|(_, state)| {
+                if state.is_in_progress() {
+                    state.end_clock()
+                }
+            })
         })
     }
 }
@@ -23,6 +23,13 @@ impl QEStatistics {
             clock_starts_at: None,
         }
     }
+    pub fn duration(&self) -> Duration {
+        self.qe_duration
+    }
+    pub(super) fn merge(&mut self, other: &QEStatistics) {
+        self.qe_count += other.qe_count;
+        self.qe_duration += other.qe_duration;
+    }
 }
 
 impl Default for QEStatistics {
@@ -34,7 +41,7 @@ impl Default for QEStatistics {
 pub fn qe_count() {
     #[cfg(feature = "stat")]
     {
-        super::STAT.lock().unwrap().qe.qe_count += 1
+        super::with(|s| s.qe.qe_count += 1)
     }
 }
 
@@ -43,32 +50,26 @@ pub fn start_clock() {
     {
         let now = Instant::now();
 
-        super::STAT.lock().unwrap().qe.clock_starts_at = Some(now)
+        super::with(|s| s.qe.clock_starts_at = Some(now))
     }
 }
 
 pub fn end_clock() {
     #[cfg(feature = "stat")]
     {
-        let st = {
-            super::STAT
-                .lock()
-                .unwrap()
-                .qe
-                .clock_starts_at
-                .expect("program error")
-        };
-        let dur = st.elapsed();
-        let qe = &mut super::STAT.lock().unwrap().qe;
-        qe.qe_duration += dur;
-        qe.clock_starts_at = None;
+        super::with(|s| {
+            let qe = &mut s.qe;
+            let st = qe.clock_starts_at.expect("program error");
+            qe.qe_duration += st.elapsed();
+            qe.clock_starts_at = None;
+        })
     }
 }
 
 pub fn finalize() {
     #[cfg(feature = "stat")]
     {
-        let r = { super::STAT.lock().unwrap().qe.clock_starts_at };
+        let r = super::with(|s| s.qe.clock_starts_at);
         if r.is_some() {
             end_clock()
         }
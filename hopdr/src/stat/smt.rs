@@ -23,6 +23,13 @@ impl SMTStatistics {
             clock_starts_at: None,
         }
     }
+    pub fn duration(&self) -> Duration {
+        self.smt_duration
+    }
+    pub(super) fn merge(&mut self, other: &SMTStatistics) {
+        self.smt_count += other.smt_count;
+        self.smt_duration += other.smt_duration;
+    }
 }
 
 impl Default for SMTStatistics {
@@ -34,7 +41,7 @@ impl Default for SMTStatistics {
 pub fn smt_count() {
     #[cfg(feature = "stat")]
     {
-        super::STAT.lock().unwrap().smt.smt_count += 1
+        super::with(|s| s.smt.smt_count += 1)
     }
 }
 
@@ -42,35 +49,31 @@ pub fn start_clock() {
     #[cfg(feature = "stat")]
     {
         let now = Instant::now();
-        let s = &mut super::STAT.lock().unwrap().smt.clock_starts_at;
-        assert!(s.is_none());
+        super::with(|s| {
+            let s = &mut s.smt.clock_starts_at;
+            assert!(s.is_none());
 
-        *s = Some(now);
+            *s = Some(now);
+        })
     }
 }
 
 pub fn end_clock() {
     #[cfg(feature = "stat")]
     {
-        let st = {
-            super::STAT
-                .lock()
-                .unwrap()
-                .smt
-                .clock_starts_at
-                .expect("program error")
-        };
-        let dur = st.elapsed();
-        let smt = &mut super::STAT.lock().unwrap().smt;
-        smt.smt_duration += dur;
-        smt.clock_starts_at = None;
+        super::with(|s| {
+            let smt = &mut s.smt;
+            let st = smt.clock_starts_at.expect("program error");
+            smt.smt_duration += st.elapsed();
+            smt.clock_starts_at = None;
+        })
     }
 }
 
 pub fn finalize() {
     #[cfg(feature = "stat")]
     {
-        let r = { super::STAT.lock().unwrap().smt.clock_starts_at };
+        let r = super::with(|s| s.smt.clock_starts_at);
         if r.is_some() {
             end_clock()
         }
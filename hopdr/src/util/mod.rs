@@ -240,6 +240,25 @@ pub fn executes_with_timeout_and_ctrlc<'a, T: Send + 'static, F: FnOnce() -> T +
         ctrlc::set_handler(move || sender.clone().send(ExecResult::Ctrlc).unwrap())
             .expect("Error setting Ctrl-C handler");
     }
+    executes_with_channel(f, timeout, sender, recv)
+}
+
+/// same as `executes_with_timeout_and_ctrlc` except that it does not touch the ctrl-c
+/// handler of the process, so it can be called more than once (e.g. from a library).
+pub fn executes_with_timeout<'a, T: Send + 'static, F: FnOnce() -> T + Send + 'static>(
+    f: F,
+    timeout: Option<time::Duration>,
+) -> Result<T, ExecutionError> {
+    let (sender, recv) = mpsc::channel();
+    executes_with_channel(f, timeout, sender, recv)
+}
+
+fn executes_with_channel<T: Send + 'static, F: FnOnce() -> T + Send + 'static>(
+    f: F,
+    timeout: Option<time::Duration>,
+    sender: mpsc::Sender<ExecResult>,
+    recv: mpsc::Receiver<ExecResult>,
+) -> Result<T, ExecutionError> {
     // thread trampoline to handle panic in `f`
     let join_handler = thread::spawn(move || {
         let x = thread::Builder::new()
@@ -251,7 +270,8 @@ pub fn executes_with_timeout_and_ctrlc<'a, T: Send + 'static, F: FnOnce() -> T +
             Ok(_) => ExecResult::Succeeded,
             Err(_) => ExecResult::Panic,
         };
-        sender.send(s).unwrap();
+        // the receiver has gone after the timeout
+        let _ = sender.send(s);
         x.unwrap()
     });
     let r = match timeout {
@@ -593,6 +593,7 @@ impl<C: Pretty + Precedence, T> Pretty for hes::GoalBase<C, T> {
             Conj(x, y) => pretty_bin_op_soft(al, config, self.precedence(), "∧", x, y),
             Disj(x, y) => pretty_bin_op_soft(al, config, self.precedence(), "∨", x, y),
             Univ(x, y) => pretty_abs(al, config, "∀", x, y),
+            Exists(x, y) => pretty_abs(al, config, "∃", x, y),
             Abs(x, y) => pretty_abs(al, config, "λ", x, y),
             ITE(c, g1, g2) => {
                 let c = paren(al, config, PrecedenceKind::If, c);
@@ -622,9 +623,13 @@ impl<C: Pretty + Precedence> Pretty for hes::Clause<C> {
         D::Doc: Clone,
         A: Clone,
     {
+        let eq = match self.fixpoint {
+            hes::Fixpoint::Greatest => "=",
+            hes::Fixpoint::Least => "=μ",
+        };
         (self.head.pretty(al, config)
             + al.space()
-            + "="
+            + eq
             + al.line()
             + self.body.pretty(al, config).nest(4))
         .group()
@@ -339,3 +339,103 @@ fn type_check_poly() {
 //     }
 // }
 //
+
+#[test]
+fn solve_str() {
+    use hopdr::api::{solve_str, Mode, SolveError, SolveOptions, Verdict};
+    use hopdr::portfolio::Engine;
+    let options = SolveOptions::new();
+
+    let s = "
+        %HES
+        M =v ∀x. x < 0 || F x.
+        F x =v x >= 0.
+        ";
+    let solution = solve_str(s, &options).unwrap();
+    assert!(matches!(
+        solution.verdict,
+        Verdict::Valid {
+            certificate: Some(_)
+        }
+    ));
+    assert_eq!(solution.engine, Some(Engine::HoPDR));
+    assert_eq!(solution.seed, None);
+
+    let s = "
+        %HES
+        M =v F 0.
+        F x =v x > 0.
+        ";
+    let solution = solve_str(s, &options).unwrap();
+    assert!(matches!(solution.verdict, Verdict::Invalid { .. }));
+
+    // the seed of ModeTrans is returned with the solution
+    let options = SolveOptions::new().mode(Mode::ModeTrans).checker_seed(42);
+    let solution = solve_str(s, &options).unwrap();
+    assert!(matches!(solution.verdict, Verdict::Invalid { .. }));
+    assert_eq!(solution.seed, Some(42));
+    let options = SolveOptions::new().mode(Mode::Portfolio);
+    assert!(solve_str(s, &options).unwrap().seed.is_some());
+
+    assert!(matches!(
+        solve_str("%HES M =v", &options),
+        Err(SolveError::Parse(_))
+    ));
+}
+
+#[test]
+fn solve_exists() {
+    use hopdr::api::{solve_file, SolveOptions, Verdict};
+    let options = SolveOptions::new();
+
+    let solution = solve_file("inputs/valid/exists.in", &options).unwrap();
+    assert!(matches!(solution.verdict, Verdict::Valid { .. }));
+
+    let solution = solve_file("inputs/invalid/exists.in", &options).unwrap();
+    assert!(matches!(solution.verdict, Verdict::Invalid { .. }));
+}
+
+#[test]
+fn mode_trans_remove_disjunction() {
+    use hopdr::api::{solve_str, Mode, SolveOptions, Verdict};
+    // F x ∨ G x is rewritten to ∃b. (b ≠ 0 ∨ F x) ∧ (b = 0 ∨ G x)
+    let s = "
+        %HES
+        M =v ∀x. F x || G x.
+        F x =v x > 0.
+        G x =v x < 0.
+        ";
+    let config = Configuration::new()
+        .inlining(false)
+        .remove_disjunction(true);
+    let options = SolveOptions::new()
+        .mode(Mode::ModeTrans)
+        .config(config)
+        .checker_backend(checker::Backend::Interpreter);
+    let solution = solve_str(s, &options).unwrap();
+    assert!(matches!(solution.verdict, Verdict::Invalid { .. }));
+}
+
+#[test]
+#[ignore = "needs ocamlopt and hopdr-check-runner"]
+fn mode_trans_backends_agree() {
+    use hopdr::api::{solve_str, Mode, SolveOptions, Verdict};
+    // the compiled program and the interpreter reach the same verdict with the same seed
+    let s = "
+        %HES
+        M =v ∀x. F x.
+        F x =v x > 0.
+        ";
+    for backend in [checker::Backend::OCaml, checker::Backend::Interpreter] {
+        let options = SolveOptions::new()
+            .mode(Mode::ModeTrans)
+            .track_trace(true)
+            .checker_seed(1)
+            .checker_backend(backend);
+        let solution = solve_str(s, &options).unwrap();
+        assert!(matches!(
+            solution.verdict,
+            Verdict::Invalid { trace: Some(_) }
+        ));
+    }
+}